@@ -0,0 +1,287 @@
+// Compile the level maps in maps/ into tile arrays the cart embeds, so
+// levels are authored as text art or in the Tiled editor instead of as number
+// grids. Each map becomes a module in levels.rs with its LAYOUT, SPAWN and
+// ENEMIES
+//
+// Lines in a map starting with "patrol" give an enemy a route to walk while
+// it hasn't seen the player: the tile the enemy starts on, then the tiles it
+// walks to in turn, as x,y pairs separated by spaces, like
+// "patrol 12,3 14,3 14,1". Once at the last it heads back to the first.
+// A line "arena left,top right,bottom" marks the tiles of a boss arena, walls
+// and doors included, as that module's ARENA
+use std::fmt::Write as _;
+use std::path::Path;
+use std::{env, fs};
+
+// Same as map::MAX_SIZE
+const MAX_SIZE: usize = 32;
+
+#[derive(Clone, Copy)]
+enum Cell {
+    // One of the tile constants in map.rs
+    Tile(&'static str),
+    // The player's spawn point and enemy spawn points, on open floor, the
+    // latter with one of the archetypes in enemies.rs
+    Spawn,
+    Enemy(&'static str),
+}
+
+// Characters in .txt maps and the cells they stand for
+const LEGEND: [(char, Cell); 29] = [
+    ('.', Cell::Tile("EMPTY")),
+    ('#', Cell::Tile("WALL")),
+    ('D', Cell::Tile("DOOR")),
+    ('X', Cell::Tile("EXIT")),
+    ('~', Cell::Tile("HAZARD")),
+    ('S', Cell::Tile("SILVER_DOOR")),
+    ('B', Cell::Tile("BRASS_DOOR")),
+    ('I', Cell::Tile("IRON_DOOR")),
+    ('%', Cell::Tile("STONE_WALL")),
+    ('=', Cell::Tile("METAL_WALL")),
+    ('?', Cell::Tile("SECRET_WALL")),
+    ('^', Cell::Tile("LAVA")),
+    ('*', Cell::Tile("CRACKED_WALL")),
+    ('C', Cell::Tile("MONITOR")),
+    ('T', Cell::Tile("TORCH_WALL")),
+    (',', Cell::Tile("OUTDOOR")),
+    ('|', Cell::Tile("FENCE")),
+    ('W', Cell::Tile("WINDOW")),
+    ('_', Cell::Tile("LOW_WALL")),
+    ('O', Cell::Tile("PILLAR")),
+    ('Y', Cell::Tile("MIRROR")),
+    ('@', Cell::Tile("PORTAL")),
+    ('w', Cell::Tile("WATER")),
+    ('P', Cell::Spawn),
+    ('E', Cell::Enemy("Guard")),
+    ('F', Cell::Enemy("Runner")),
+    ('H', Cell::Enemy("Brute")),
+    ('R', Cell::Enemy("Sniper")),
+    ('M', Cell::Enemy("Boss")),
+];
+
+// Tile IDs in .csv layers exported from Tiled, in tileset order; cells with
+// no tile are exported as -1 and left as floor. Tiles added since go on the
+// end, so older exports keep their IDs
+const TILED_IDS: [Cell; 29] = [
+    Cell::Tile("EMPTY"),
+    Cell::Tile("WALL"),
+    Cell::Tile("DOOR"),
+    Cell::Tile("EXIT"),
+    Cell::Tile("HAZARD"),
+    Cell::Tile("SILVER_DOOR"),
+    Cell::Tile("BRASS_DOOR"),
+    Cell::Tile("IRON_DOOR"),
+    Cell::Tile("STONE_WALL"),
+    Cell::Tile("METAL_WALL"),
+    Cell::Tile("SECRET_WALL"),
+    Cell::Tile("LAVA"),
+    Cell::Tile("CRACKED_WALL"),
+    Cell::Spawn,
+    Cell::Enemy("Guard"),
+    Cell::Enemy("Runner"),
+    Cell::Enemy("Brute"),
+    Cell::Enemy("Sniper"),
+    Cell::Enemy("Boss"),
+    Cell::Tile("MONITOR"),
+    Cell::Tile("TORCH_WALL"),
+    Cell::Tile("OUTDOOR"),
+    Cell::Tile("FENCE"),
+    Cell::Tile("WINDOW"),
+    Cell::Tile("LOW_WALL"),
+    Cell::Tile("PILLAR"),
+    Cell::Tile("MIRROR"),
+    Cell::Tile("PORTAL"),
+    Cell::Tile("WATER"),
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed=maps");
+
+    let mut paths: Vec<_> = fs::read_dir("maps")
+        .expect("maps/ directory")
+        .map(|entry| entry.expect("maps/ entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt" || ext == "csv"))
+        .collect();
+    paths.sort();
+
+    let mut out = String::from("// Generated by build.rs from maps/\n");
+    for (idx, path) in paths.iter().enumerate() {
+        if paths[..idx].iter().any(|other| other.file_stem() == path.file_stem()) {
+            panic!("{}: another map has the same name", path.display());
+        }
+        compile(path, &mut out);
+    }
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("maps.rs");
+    fs::write(dest, out).expect("write maps.rs");
+}
+
+fn compile(path: &Path, out: &mut String) {
+    let name = path.file_stem().unwrap().to_str().unwrap();
+    let fail = |line: usize, message: &str| -> ! {
+        panic!("{}:{}: {}", path.display(), line + 1, message)
+    };
+
+    if !name.starts_with(|c: char| c.is_ascii_lowercase())
+        || !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        fail(0, "file names must be lowercase identifiers");
+    }
+
+    let text = fs::read_to_string(path).expect("read map");
+    let (extras, rows): (Vec<_>, Vec<_>) = text
+        .lines()
+        .map(str::trim_end)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .partition(|(_, line)| line.starts_with("patrol") || line.starts_with("arena"));
+    let rows: Vec<&str> = rows.into_iter().map(|(_, row)| row).collect();
+    let (arenas, patrols): (Vec<_>, Vec<_>) =
+        extras.into_iter().partition(|(_, line)| line.starts_with("arena"));
+
+    let mut grid = Vec::with_capacity(rows.len());
+    for (y, row) in rows.iter().enumerate() {
+        let cells: Result<Vec<_>, String> = if path.extension().unwrap() == "csv" {
+            row.trim_end_matches(',').split(',').map(|id| tiled_cell(id.trim())).collect()
+        } else {
+            row.chars().map(text_cell).collect()
+        };
+        grid.push(cells.unwrap_or_else(|message| fail(y, &message)));
+    }
+
+    let width = grid.first().map_or(0, Vec::len);
+    let height = grid.len();
+    if !(3..=MAX_SIZE).contains(&width) || !(3..=MAX_SIZE).contains(&height) {
+        fail(0, &format!("maps must be 3 to {MAX_SIZE} tiles a side"));
+    }
+
+    let mut tiles = Vec::with_capacity(width * height);
+    let mut spawn = None;
+    let mut enemies = Vec::new();
+
+    for (y, row) in grid.iter().enumerate() {
+        if row.len() != width {
+            fail(y, &format!("row is not {width} tiles wide"));
+        }
+
+        for (x, &cell) in row.iter().enumerate() {
+            let centre = (x as f32 + 0.5, y as f32 + 0.5);
+            let tile = match cell {
+                Cell::Tile(tile) => tile,
+                Cell::Spawn if spawn.is_some() => fail(y, "more than one spawn point"),
+                Cell::Spawn => {
+                    spawn = Some(centre);
+                    "EMPTY"
+                }
+                Cell::Enemy(archetype) => {
+                    enemies.push((archetype, centre));
+                    "EMPTY"
+                }
+            };
+            tiles.push(tile);
+        }
+    }
+
+    let Some((spawn_x, spawn_y)) = spawn else { fail(0, "no spawn point") };
+
+    let mut routes = vec![Vec::new(); enemies.len()];
+    for &(line, patrol) in &patrols {
+        let route = parse_tiles(&patrol["patrol".len()..])
+            .unwrap_or_else(|message| fail(line, &message));
+        if route.len() < 2 {
+            fail(line, "patrols need an enemy and at least one more tile");
+        }
+
+        for &(x, y) in &route {
+            let open = grid.get(y).and_then(|row| row.get(x)).is_some_and(|&cell| {
+                matches!(cell, Cell::Tile("EMPTY" | "HAZARD") | Cell::Spawn | Cell::Enemy(_))
+            });
+            if !open {
+                fail(line, &format!("patrol tile {x},{y} is not open floor"));
+            }
+        }
+
+        let (x, y) = route[0];
+        let centre = (x as f32 + 0.5, y as f32 + 0.5);
+        let Some(enemy) = enemies.iter().position(|&(_, spawn)| spawn == centre) else {
+            fail(line, &format!("no enemy at {x},{y} to patrol"));
+        };
+        if !routes[enemy].is_empty() {
+            fail(line, &format!("the enemy at {x},{y} already has a patrol"));
+        }
+        routes[enemy] = route;
+    }
+
+    if arenas.len() > 1 {
+        fail(arenas[1].0, "more than one arena");
+    }
+    let arena = arenas.first().map(|&(line, arena)| {
+        let corners = parse_tiles(&arena["arena".len()..])
+            .unwrap_or_else(|message| fail(line, &message));
+        match corners[..] {
+            [(left, top), (right, bottom)] if left < right && top < bottom => {
+                if right >= width || bottom >= height {
+                    fail(line, "the arena runs off the map");
+                }
+                let fields = format!("left: {left}, top: {top}, right: {right}, bottom: {bottom}");
+                format!("Some(Arena {{ {fields} }})")
+            }
+            _ => fail(line, "arenas need a top left and a bottom right tile"),
+        }
+    });
+
+    writeln!(out, "\npub mod {name} {{").unwrap();
+    writeln!(out, "    use crate::boss::Arena;").unwrap();
+    writeln!(out, "    use crate::enemies::{{Archetype, Spawn}};").unwrap();
+    writeln!(out, "    use crate::map::*;\n    use crate::rle;\n").unwrap();
+    writeln!(out, "    const TILES: [u8; {}] = [", width * height).unwrap();
+    for row in tiles.chunks(width) {
+        writeln!(out, "        {},", row.join(", ")).unwrap();
+    }
+    writeln!(out, "    ];").unwrap();
+    writeln!(out, "    const PACKED: [u8; rle::packed_len(&TILES)] =").unwrap();
+    writeln!(out, "        rle::pack(&TILES);\n").unwrap();
+    writeln!(out, "    pub const LAYOUT: Layout = Layout {{").unwrap();
+    writeln!(out, "        width: {width},\n        height: {height},").unwrap();
+    writeln!(out, "        tiles: &PACKED,\n    }};").unwrap();
+    writeln!(out, "    pub const SPAWN: (f32, f32) = ({spawn_x:?}, {spawn_y:?});").unwrap();
+    writeln!(out, "    pub const ENEMIES: &[Spawn] = &[").unwrap();
+    for (&(archetype, (x, y)), route) in enemies.iter().zip(&routes) {
+        let route: Vec<_> = route.iter().map(|(x, y)| format!("({x}, {y})")).collect();
+        let route = route.join(", ");
+        writeln!(out, "        Spawn {{").unwrap();
+        writeln!(out, "            archetype: Archetype::{archetype},").unwrap();
+        writeln!(out, "            x: {x:?},\n            y: {y:?},").unwrap();
+        writeln!(out, "            route: &[{route}],\n        }},").unwrap();
+    }
+    writeln!(out, "    ];").unwrap();
+    let arena = arena.unwrap_or("None".to_string());
+    writeln!(out, "    pub const ARENA: Option<Arena> = {arena};").unwrap();
+    writeln!(out, "}}").unwrap();
+}
+
+// The tiles listed after "patrol" or "arena", as x,y pairs
+fn parse_tiles(line: &str) -> Result<Vec<(usize, usize)>, String> {
+    line.split_whitespace()
+        .map(|tile| {
+            let (x, y) = tile.split_once(',').ok_or(format!("{tile:?} is not an x,y pair"))?;
+            match (x.parse(), y.parse()) {
+                (Ok(x), Ok(y)) => Ok((x, y)),
+                _ => Err(format!("{tile:?} is not an x,y pair")),
+            }
+        })
+        .collect()
+}
+
+fn text_cell(c: char) -> Result<Cell, String> {
+    let cell = LEGEND.iter().find(|&&(key, _)| key == c).map(|&(_, cell)| cell);
+    cell.ok_or(format!("unknown tile {c:?}"))
+}
+
+fn tiled_cell(id: &str) -> Result<Cell, String> {
+    match id.parse::<i32>() {
+        Ok(-1) => Ok(Cell::Tile("EMPTY")),
+        Ok(id) if (0..TILED_IDS.len() as i32).contains(&id) => Ok(TILED_IDS[id as usize]),
+        _ => Err(format!("unknown tile ID {id:?}")),
+    }
+}
@@ -0,0 +1,146 @@
+// Sound effects, each one call to the console's tone generator. Sounds
+// from somewhere on the level fade with distance, pan towards the side
+// they come from and drop in pitch from behind
+use crate::math::{cos, sin, sqrt};
+use crate::platform::tone;
+use crate::State;
+
+// Sound channels, and the pulse wave's duty cycle
+pub const PULSE_1: u32 = 0;
+pub const PULSE_2: u32 = 1;
+pub const TRIANGLE: u32 = 2;
+pub const NOISE: u32 = 3;
+pub const DUTY_25: u32 = 1 << 2;
+pub const DUTY_50: u32 = 2 << 2;
+const PAN_LEFT: u32 = 1 << 4;
+const PAN_RIGHT: u32 = 2 << 4;
+
+// Tiles away a sound can still be heard from
+const HEARING_RANGE: f32 = 12.0;
+// How far round to one side, as the sine of the angle off the view, before a
+// sound plays from that speaker alone
+const PAN_SIDE: f32 = 0.35;
+// Sounds from behind play at this share of their pitch
+const BEHIND_PITCH: (u32, u32) = (3, 4);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Sound {
+    // Starting and ending pitch in Hz, sliding between them
+    from: u32,
+    to: u32,
+    // Frames the sound lasts and its volume out of 100
+    frames: u32,
+    volume: u32,
+    flags: u32,
+}
+
+// A soft tick of noise on each footfall
+pub const FOOTSTEP: Sound = Sound { from: 160, to: 160, frames: 3, volume: 12, flags: NOISE };
+// Walking into a wall
+pub const BUMP: Sound = Sound { from: 90, to: 50, frames: 6, volume: 40, flags: TRIANGLE };
+pub const DOOR: Sound =
+    Sound { from: 220, to: 130, frames: 14, volume: 25, flags: PULSE_2 | DUTY_50 };
+// Using a door without its key
+pub const LOCKED: Sound = Sound { from: 110, to: 100, frames: 8, volume: 30, flags: PULSE_2 };
+pub const PICKUP: Sound =
+    Sound { from: 600, to: 900, frames: 6, volume: 35, flags: PULSE_1 | DUTY_25 };
+// Keys get a longer chime than everyday supplies
+pub const KEY: Sound =
+    Sound { from: 800, to: 1300, frames: 16, volume: 35, flags: PULSE_1 | DUTY_50 };
+// An enemy spotting the player, walking, swinging, taking a hit and dying
+pub const ALERT: Sound =
+    Sound { from: 180, to: 320, frames: 12, volume: 40, flags: PULSE_2 | DUTY_25 };
+pub const ENEMY_STEP: Sound = Sound { from: 90, to: 90, frames: 4, volume: 20, flags: NOISE };
+pub const SWING: Sound = Sound { from: 400, to: 120, frames: 8, volume: 45, flags: NOISE };
+pub const HIT: Sound = Sound { from: 300, to: 150, frames: 6, volume: 40, flags: PULSE_2 };
+pub const DEATH: Sound = Sound { from: 260, to: 60, frames: 24, volume: 45, flags: TRIANGLE };
+// An enemy firing a shot, and the shot bursting on a wall
+pub const SHOOT: Sound =
+    Sound { from: 520, to: 260, frames: 10, volume: 35, flags: PULSE_2 | DUTY_50 };
+pub const SPLASH: Sound = Sound { from: 200, to: 80, frames: 8, volume: 30, flags: NOISE };
+// A rocket bursting
+pub const BLAST: Sound = Sound { from: 120, to: 30, frames: 20, volume: 60, flags: NOISE };
+// The player's own heart at low health
+pub const HEARTBEAT: Sound = Sound { from: 70, to: 45, frames: 5, volume: 50, flags: TRIANGLE };
+
+impl Sound {
+    // The same sound at a share of its volume, but never silent
+    pub fn scaled(self, share: f32) -> Sound {
+        Sound { volume: ((self.volume as f32 * share) as u32).max(1), ..self }
+    }
+}
+
+// Play a steady note on one of the channels above
+pub fn note(frequency: u32, frames: u32, volume: u32, flags: u32) {
+    unsafe { tone(frequency, frames, volume, flags) }
+}
+
+impl State {
+    // Play a sound effect, with the music ducking under it
+    pub(crate) fn play_sound(&mut self, sound: &Sound) {
+        note(sound.from | sound.to << 16, sound.frames, sound.volume, sound.flags);
+        self.music.duck(sound.frames);
+    }
+
+    // Play a sound made at a point on the level, as heard from where the
+    // player stands and faces
+    pub(crate) fn play_sound_at(&mut self, sound: &Sound, x: f32, y: f32) {
+        if let Some(heard) = self.heard(sound, x, y) {
+            self.play_sound(&heard);
+        }
+    }
+
+    fn heard(&self, sound: &Sound, x: f32, y: f32) -> Option<Sound> {
+        let (dx, dy) = (x - self.player_x, y - self.player_y);
+        let distance = sqrt(dx * dx + dy * dy);
+        let loudness = 1.0 - distance / HEARING_RANGE;
+        if loudness <= 0.0 {
+            return None;
+        }
+
+        // How far the sound is in front of the player and off to their right,
+        // with anything right on top of them counting as ahead
+        let (dir_x, dir_y) = (cos(self.player_angle), -sin(self.player_angle));
+        let (ahead, side) = if distance > 0.0 {
+            ((dx * dir_x + dy * dir_y) / distance, (dy * dir_x - dx * dir_y) / distance)
+        } else {
+            (1.0, 0.0)
+        };
+
+        let pan = match side {
+            side if side > PAN_SIDE => PAN_RIGHT,
+            side if side < -PAN_SIDE => PAN_LEFT,
+            _ => 0,
+        };
+        let (num, den) = if ahead < 0.0 { BEHIND_PITCH } else { (1, 1) };
+
+        Some(Sound {
+            from: sound.from * num / den,
+            to: sound.to * num / den,
+            flags: sound.flags | pan,
+            ..sound.scaled(loudness)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sounds_fade_pan_and_drop_behind_the_player() {
+        let mut state = State::new();
+        state.teleport(5.0, 5.0, 0.0);
+
+        // Facing east, so south is on the right
+        let right = state.heard(&HIT, 5.0, 8.0).unwrap();
+        assert_eq!((right.flags & !HIT.flags, right.from), (PAN_RIGHT, HIT.from));
+        assert!(right.volume < HIT.volume);
+
+        let behind = state.heard(&HIT, 2.0, 5.0).unwrap();
+        assert_eq!((behind.flags, behind.from), (HIT.flags, 225));
+
+        assert_eq!(state.heard(&HIT, 5.0, 5.0).unwrap().volume, HIT.volume);
+        assert!(state.heard(&HIT, 5.0, 5.0 - HEARING_RANGE).is_none());
+    }
+}
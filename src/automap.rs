@@ -0,0 +1,133 @@
+// The automap: the level drawn over the whole view from above, with only the
+// tiles seen so far on it, filling in as the player explores. Holding left
+// and right together while pressing BUTTON_2 shows or hides it, and play
+// goes on underneath
+use crate::input::Input;
+use crate::map::{is_door, is_floor, EXIT, HAZARD, LAVA, MAX_SIZE, MAX_TILES};
+use crate::math::{cos, floor, sin};
+use crate::platform::{line, rect, DRAW_COLORS, SCREEN_SIZE};
+use crate::render::VIEW_HEIGHT;
+use crate::State;
+
+// Rays cast across the view each frame to find what the player can see
+const RAYS: usize = 16;
+
+// Steps along each ray at which the tile passed over counts as seen, in tiles
+const STEP: f32 = 0.5;
+
+// Length of the facing indicator in pixels
+const POINTER: f32 = 6.0;
+
+// One bit per tile of the largest map, set once the tile has been seen
+#[derive(Clone, Copy)]
+pub struct Explored([u8; MAX_TILES / 8]);
+
+impl Explored {
+    pub const fn new() -> Self {
+        Explored([0; MAX_TILES / 8])
+    }
+
+    fn index(x: i32, y: i32) -> Option<usize> {
+        let inside = (0..MAX_SIZE as i32).contains(&x) && (0..MAX_SIZE as i32).contains(&y);
+        inside.then(|| y as usize * MAX_SIZE + x as usize)
+    }
+
+    fn mark(&mut self, x: i32, y: i32) {
+        if let Some(idx) = Self::index(x, y) {
+            self.0[idx / 8] |= 1 << (idx % 8);
+        }
+    }
+
+    pub fn seen(&self, x: i32, y: i32) -> bool {
+        Self::index(x, y).is_some_and(|idx| self.0[idx / 8] & 1 << (idx % 8) != 0)
+    }
+}
+
+// Whether this frame's buttons are the combo that shows or hides the automap
+pub fn toggled(input: &Input) -> bool {
+    input.left.held() && input.right.held() && input.button_2.pressed()
+}
+
+impl State {
+    // Mark the tiles in view as seen, from a few rays fanned across the
+    // field of view up to the walls they stop at
+    pub(crate) fn explore(&mut self) {
+        let (x, y) = (self.player_x, self.player_y);
+        self.explored.mark(floor(x) as i32, floor(y) as i32);
+
+        let fov = self.fov();
+        for ray in 0..RAYS {
+            let angle = self.player_angle + fov * (0.5 - ray as f32 / (RAYS - 1) as f32);
+            let (dir_x, dir_y) = (cos(angle), -sin(angle));
+            let hit = self.cast_ray_along(x, y, (dir_x, dir_y));
+
+            let mut along = 0.0;
+            while along < hit.distance.min(self.config.render_distance) {
+                let (at_x, at_y) = (x + dir_x * along, y + dir_y * along);
+                self.explored.mark(floor(at_x) as i32, floor(at_y) as i32);
+                along += STEP;
+            }
+            if hit.distance < self.config.render_distance {
+                self.explored.mark(hit.tile_x, hit.tile_y);
+            }
+        }
+    }
+}
+
+// The explored tiles scaled to fill the view, with the player's position and
+// facing
+pub unsafe fn render(state: &State) {
+    let map = state.map();
+    let (width, height) = (map.width() as i32, map.height() as i32);
+    let scale = (SCREEN_SIZE / width).min(VIEW_HEIGHT / height);
+    let left = (SCREEN_SIZE - width * scale) / 2;
+    let top = (VIEW_HEIGHT - height * scale) / 2;
+
+    *DRAW_COLORS = 0x44;
+    rect(0, 0, SCREEN_SIZE as u32, VIEW_HEIGHT as u32);
+
+    for y in 0..height {
+        for x in 0..width {
+            if !state.explored.seen(x, y) {
+                continue;
+            }
+
+            *DRAW_COLORS = match map.tile(x, y) {
+                tile if is_door(tile) || matches!(tile, EXIT | HAZARD | LAVA) => 0x2,
+                tile if is_floor(tile) => 0x3,
+                _ => 0x1,
+            };
+            rect(left + x * scale, top + y * scale, scale as u32, scale as u32);
+        }
+    }
+
+    let player_x = left + (state.player_x * scale as f32) as i32;
+    let player_y = top + (state.player_y * scale as f32) as i32;
+
+    *DRAW_COLORS = 0x1;
+    line(
+        player_x,
+        player_y,
+        player_x + (cos(state.player_angle) * POINTER) as i32,
+        player_y - (sin(state.player_angle) * POINTER) as i32,
+    );
+    rect(player_x - 1, player_y - 1, 3, 3);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looking_down_a_corridor_reveals_it() {
+        let mut state = State::new();
+        state.teleport(12.5, 6.5, 0.0);
+        state.explored = Explored::new();
+        assert!(!state.explored.seen(20, 6));
+
+        state.explore();
+        assert!(state.explored.seen(12, 6) && state.explored.seen(20, 6));
+        // Nothing behind the player
+        assert!(!state.explored.seen(10, 6));
+    }
+}
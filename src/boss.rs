@@ -0,0 +1,111 @@
+// Boss fights: an arena whose doors shut behind the player until the boss is
+// dead, and the boss's health bar across the top of the screen
+use crate::enemies::{Archetype, Enemy};
+use crate::levels::LEVELS;
+use crate::platform::{rect, DRAW_COLORS, SCREEN_SIZE};
+use crate::{audio, compass, State};
+
+// Size and place of the health bar
+const BAR_WIDTH: i32 = 120;
+const BAR_HEIGHT: i32 = 6;
+const BAR_TOP: i32 = compass::HEIGHT + 4;
+
+// A rectangle of tiles, walls included, with its doors on the edges
+#[derive(Clone, Copy)]
+pub struct Arena {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl Arena {
+    fn covers(&self, tile_x: i32, tile_y: i32) -> bool {
+        (self.left..=self.right).contains(&tile_x) && (self.top..=self.bottom).contains(&tile_y)
+    }
+
+    // Well clear of the doorways, so no door shuts on the player
+    fn surrounds(&self, x: f32, y: f32) -> bool {
+        x >= (self.left + 2) as f32
+            && x < (self.right - 1) as f32
+            && y >= (self.top + 2) as f32
+            && y < (self.bottom - 1) as f32
+    }
+}
+
+impl State {
+    pub(crate) fn boss(&self) -> Option<&Enemy> {
+        self.enemies.iter().flatten().find(|enemy| enemy.archetype == Archetype::Boss)
+    }
+
+    // Shut the arena once the player is inside with the boss alive, and open
+    // it up again once the boss is dead
+    pub(crate) fn update_arena(&mut self) {
+        let Some(arena) = LEVELS[self.level].arena else { return };
+        let boss_alive = self.boss().is_some();
+
+        if self.arena_locked && !boss_alive {
+            self.arena_locked = false;
+            self.play_sound(&audio::DOOR);
+        } else if !self.arena_locked && boss_alive {
+            if !arena.surrounds(self.player_x, self.player_y) {
+                return;
+            }
+
+            self.arena_locked = true;
+            for slot in &mut self.doors {
+                if slot.is_some_and(|door| arena.covers(door.tile_x, door.tile_y)) {
+                    *slot = None;
+                }
+            }
+            self.play_sound(&audio::DOOR);
+        }
+    }
+
+    // Check if a door is held shut by the arena
+    pub(crate) fn arena_holds(&self, tile_x: i32, tile_y: i32) -> bool {
+        let arena = LEVELS[self.level].arena;
+        self.arena_locked && arena.is_some_and(|arena| arena.covers(tile_x, tile_y))
+    }
+}
+
+// The boss's health left, shown once it is after the player
+pub unsafe fn render(state: &State) {
+    let Some(boss) = state.boss().filter(|boss| boss.mode.hunting()) else { return };
+    let left = (SCREEN_SIZE - BAR_WIDTH) / 2;
+    let filled = (BAR_WIDTH - 2) * boss.health.max(0) / boss.stats().health;
+
+    *DRAW_COLORS = 0x14;
+    rect(left, BAR_TOP, BAR_WIDTH as u32, BAR_HEIGHT as u32);
+    *DRAW_COLORS = 0x2;
+    rect(left + 1, BAR_TOP + 1, filled as u32, (BAR_HEIGHT - 2) as u32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_arena_shuts_until_the_boss_is_dead() {
+        // The last level's arena has doors at (6, 3) and (18, 3)
+        let mut state = State::new();
+        state.load_level(3);
+        state.open_door(6, 3);
+        state.teleport(6.5, 3.5, 0.0);
+        state.update_arena();
+        assert!(!state.arena_locked);
+
+        state.teleport(10.5, 3.5, 0.0);
+        state.update_arena();
+        assert!(state.arena_locked);
+        assert!(state.arena_holds(18, 3));
+        assert_eq!(state.door_open(6, 3), 0.0);
+
+        let boss = |e: &Option<Enemy>| e.is_some_and(|e| e.archetype == Archetype::Boss);
+        let idx = state.enemies.iter().position(boss).unwrap();
+        state.damage_enemy(idx, 100);
+        state.update_arena();
+        assert!(!state.arena_locked);
+        assert!(!state.arena_holds(18, 3));
+    }
+}
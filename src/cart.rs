@@ -0,0 +1,355 @@
+// WASM-4 entry points and the game state they share
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+
+use crate::levels::LEVELS;
+use crate::pause::Choice;
+use crate::platform::{NETPLAY, NETPLAY_ACTIVE, PALETTE, SCREEN_SIZE};
+use crate::raycast::Viewport;
+use crate::save::Save;
+use crate::{
+    automap, boss, compass, coop, crosshair, deathmatch, heartbeat, hud, indicator, minimap, mirror,
+    monitor, render, spectator, survival, weapon, weather, GameMode, State, Transition, FADE_TIME,
+    HAZARD_INTERVAL, WIPE_TIME,
+};
+
+// Shift a color towards red by amount / 512, so at most halfway
+const fn redden(color: u32, amount: u32) -> u32 {
+    let (r, g, b) = (color >> 16, color >> 8 & 0xff, color & 0xff);
+    let r = r + (0xff - r) * amount / 512;
+    let (g, b) = (g - g * amount / 512, b - b * amount / 512);
+
+    r << 16 | g << 8 | b
+}
+
+// Scale a color towards black, leaving level / FADE_TIME of its brightness
+fn darken(color: u32, level: u32) -> u32 {
+    let scale = |channel: u32| (channel & 0xff) * level / FADE_TIME;
+    scale(color >> 16) << 16 | scale(color >> 8) << 8 | scale(color)
+}
+
+// The chosen palette fades during fade transitions, and pulses red while the
+// floor hurts, flaring on each damage tick and fading until the next
+fn palette(state: &State) -> [u32; 4] {
+    let colors = state.settings.colors();
+    if state.transition > 0 {
+        let fade = match state.effect {
+            Transition::Level => return colors,
+            Transition::FadeOut => state.transition,
+            Transition::FadeIn => FADE_TIME - state.transition,
+        };
+        return colors.map(|color| darken(color, fade));
+    }
+    // Deathmatch only tints the hurt player's quarter of the screen
+    let quartered = state.deathmatch.is_some();
+    if state.mode != GameMode::Playing || quartered || state.floor_damage().is_none() {
+        return colors;
+    }
+
+    let amount = state.hazard_timer * 256 / HAZARD_INTERVAL;
+    colors.map(|color| redden(color, amount))
+}
+
+// Holds the game between callbacks. The console calls start and then update
+// one at a time on a single thread, so only one borrow is ever live. Left
+// uninitialized rather than None, which would store the whole state's worth
+// of zeros in the cart's data
+struct StateCell(UnsafeCell<MaybeUninit<State>>);
+
+// Nothing runs concurrently on WASM-4
+unsafe impl Sync for StateCell {}
+
+static STATE: StateCell = StateCell(UnsafeCell::new(MaybeUninit::uninit()));
+
+impl StateCell {
+    // Set up a new game, replacing any previous one; nothing may still hold
+    // a reference from get. Kept out of start, so the state built up here
+    // never shares the console's small stack with reading the disk
+    #[inline(never)]
+    unsafe fn init(&self) {
+        (*self.0.get()).write(State::new());
+    }
+
+    // The game set up by start; callers must not keep the reference past the
+    // callback it was taken in
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn get(&self) -> &mut State {
+        // start always runs before the first update
+        (*self.0.get()).assume_init_mut()
+    }
+}
+
+#[no_mangle]
+unsafe fn start() {
+    STATE.init();
+
+    let state = STATE.get();
+    let netplay = *NETPLAY & NETPLAY_ACTIVE != 0;
+    state.resume_at_start(Save::read(), netplay);
+    if !netplay {
+        state.ghost.read();
+    }
+
+    // The level name shows once the title screen is left
+    state.mode = GameMode::Title;
+    state.transition = 0;
+    *PALETTE = palette(state);
+}
+
+#[no_mangle]
+unsafe fn update() {
+    let state = STATE.get();
+    state.poll_input();
+    state.update_demo();
+    *PALETTE = palette(state);
+    state.music.update(LEVELS[state.level].music);
+
+    if state.transition > 0 {
+        state.transition -= 1;
+        match state.effect {
+            Transition::Level if state.transition >= WIPE_TIME => {
+                render::draw_transition(state.level, 0);
+            }
+            // Uncover the level from the left, a few columns a frame
+            Transition::Level => {
+                draw_frame(state);
+                let covered = SCREEN_SIZE * state.transition as i32 / WIPE_TIME as i32;
+                render::draw_transition(state.level, SCREEN_SIZE - covered);
+            }
+            Transition::FadeOut | Transition::FadeIn => draw_frame(state),
+        }
+        return;
+    }
+
+    match state.mode {
+        GameMode::Title => {
+            if state.input.action.take() {
+                state.mode = GameMode::Playing;
+                state.begin_transition(Transition::Level);
+            } else if state.input.button_2.tapped() {
+                state.start_survival();
+            } else {
+                state.update_attract();
+            }
+            render::draw_title(&state.leaderboards[..LEVELS.len()], state.best_wave);
+            return;
+        }
+        GameMode::Playing => {}
+        GameMode::Dead => {
+            if state.input.action.take() {
+                state.mode = GameMode::Restarting;
+            } else if state.partner.is_some() && state.input.button_2.tapped() {
+                state.take_off();
+            }
+            render::draw_game_over();
+            return;
+        }
+        GameMode::Restarting => {
+            if !state.input.action.held() {
+                state.restart();
+            }
+            render::draw_game_over();
+            return;
+        }
+        GameMode::Paused => {
+            match state.update_pause() {
+                Some(Choice::Resume) => state.mode = GameMode::Playing,
+                Some(Choice::Restart) => state.restart(),
+                Some(Choice::Options) => {
+                    state.option = 0;
+                    state.mode = GameMode::Options;
+                }
+                // Back to the start of the campaign's level, or of the level
+                // survival was started from
+                Some(Choice::Quit) => {
+                    state.partner = None;
+                    match state.survival {
+                        Some(_) => state.end_survival(),
+                        None if state.deathmatch.is_some() => state.end_deathmatch(),
+                        None => state.resume(&state.save()),
+                    }
+                    state.mode = GameMode::Title;
+                    state.transition = 0;
+                }
+                None => {}
+            }
+
+            draw_frame(state);
+            if state.mode == GameMode::Paused {
+                render::draw_pause(state.pause_item);
+            }
+            return;
+        }
+        GameMode::Options => {
+            if state.update_options() {
+                // Only the settings change on disk, not the progress saved
+                let mut save = Save::read().unwrap_or_else(|| state.save());
+                save.settings = state.settings;
+                save.write();
+                state.mode = GameMode::Paused;
+            }
+            render::draw_options(&state.settings, state.option);
+            return;
+        }
+        GameMode::Spectating => {
+            if state.input.action.take() {
+                state.land();
+            } else {
+                state.update_spectator();
+            }
+            draw_frame(state);
+            return;
+        }
+        GameMode::Watching => {
+            state.update_monitor();
+            if state.mode == GameMode::Watching {
+                monitor::render(state);
+                hud::render(state);
+            } else {
+                draw_frame(state);
+            }
+            return;
+        }
+        GameMode::Lobby => {
+            state.update_lobby();
+            if let Some(deathmatch) = &state.deathmatch {
+                let players = deathmatch.players();
+                render::draw_lobby(&deathmatch.rules, deathmatch.item, players);
+            }
+            return;
+        }
+        GameMode::LevelComplete => {
+            // Going on saves the next level, while retrying races the ghost of
+            // the best run on this one
+            let retry = state.input.button_2.tapped();
+            if retry || state.input.action.take() {
+                if state.rank == Some(0) {
+                    state.ghost.write();
+                }
+
+                if retry {
+                    state.restart();
+                } else {
+                    state.load_level(state.level + 1);
+                    state.mode = GameMode::Playing;
+                    state.save().write();
+                }
+            }
+            render::draw_level_complete(&state.stats, state.leaderboard(), state.rank);
+            return;
+        }
+    }
+
+    // Pressing BUTTON_1 and BUTTON_2 together pauses, without firing
+    if state.input.both_buttons() {
+        state.input.action.take();
+        state.pause_item = 0;
+        state.mode = GameMode::Paused;
+        draw_frame(state);
+        render::draw_pause(state.pause_item);
+        return;
+    }
+
+    if spectator::toggled(&state.input) {
+        state.take_off();
+        draw_frame(state);
+        return;
+    }
+
+    // Tapping BUTTON_2 on its own, without strafing or looking, toggles the minimap
+    if state.input.button_2.single_tapped() {
+        state.show_minimap = !state.show_minimap;
+    }
+    if automap::toggled(&state.input) {
+        state.show_automap = !state.show_automap;
+    }
+
+    // Keys are saved as soon as they are picked up, and survival waves as
+    // soon as they beat the best, though never from the demo
+    let (keys, best_wave) = (state.keys, state.best_wave);
+    state.update();
+    if (state.keys != keys || state.best_wave != best_wave) && !state.attracting() {
+        state.save().write();
+    }
+
+    draw_frame(state);
+    if state.show_automap && !state.attracting() {
+        automap::render(state);
+    } else if state.show_minimap && !state.attracting() {
+        minimap::render(state);
+    }
+}
+
+// One player's view with their weapon over it
+unsafe fn draw_player(state: &State, viewport: Viewport) {
+    render::draw_view(state, viewport);
+    if state.wading() {
+        render::draw_ripple(viewport, state.stats.frames);
+    }
+    weather::render(state, viewport);
+    if state.floor_damage().is_some() {
+        render::draw_hazard_tint(viewport);
+    }
+    heartbeat::render(state, viewport);
+    indicator::render(state, viewport);
+    if !state.attracting() {
+        weapon::render(state, viewport);
+        crosshair::render(state, viewport);
+    }
+}
+
+// The 3D view, split in two for co-op or in four for deathmatch, with the
+// weapon and HUD over it
+unsafe fn draw_frame(state: &mut State) {
+    if state.deathmatch.is_some() {
+        draw_deathmatch(state);
+        return;
+    }
+
+    if state.partner.is_some() {
+        let [left, right] = coop::VIEWPORTS;
+        draw_player(state, left);
+        state.swap_partner();
+        draw_player(state, right);
+        state.swap_partner();
+        coop::render_divider();
+    } else {
+        draw_player(state, Viewport::FULL);
+    }
+    // The demo plays behind the title, without the HUD
+    if state.attracting() {
+        render::draw_demo_title();
+        return;
+    }
+
+    if state.partner.is_none() {
+        compass::render(state);
+        if state.settings.rear_view {
+            mirror::render(state);
+        }
+    }
+
+    hud::render(state);
+    boss::render(state);
+    survival::render(state);
+
+    if state.damage_flash > 0 {
+        render::draw_damage_flash(0, 0, SCREEN_SIZE);
+    }
+}
+
+// Each deathmatch player's quarter of the screen with their health and
+// frags in it, and a prompt to join in any without a player yet
+unsafe fn draw_deathmatch(state: &mut State) {
+    for (player, viewport) in deathmatch::VIEWPORTS.into_iter().enumerate() {
+        if !state.swap_player(player) {
+            deathmatch::render_vacancy(viewport);
+            continue;
+        }
+        draw_player(state, viewport);
+        deathmatch::render_status(state, viewport);
+        state.swap_player(player);
+    }
+    deathmatch::render_dividers();
+}
+
@@ -0,0 +1,94 @@
+// The compass along the top of the screen: the cardinal points laid out
+// around the way the player faces, half a turn across, and a marker on the
+// bearing to the objective, the boss while it lives and the exit after. A
+// marker off the strip waits at the edge on the side to turn towards
+use core::f32::consts::{FRAC_PI_4, PI};
+
+use crate::map::EXIT;
+use crate::math::{abs, atan2};
+use crate::platform::{rect, text, DRAW_COLORS, SCREEN_SIZE};
+use crate::player::normalize_angle;
+use crate::State;
+
+pub const HEIGHT: i32 = 10;
+
+// Bearings across the width of the strip
+const SPAN: f32 = PI;
+
+// Going counterclockwise from east, as angles do
+const POINTS: [&[u8]; 4] = [b"E", b"N", b"W", b"S"];
+
+impl State {
+    // Where the player is meant to be heading, if anywhere
+    pub(crate) fn objective(&self) -> Option<(f32, f32)> {
+        if let Some(boss) = self.boss() {
+            return Some((boss.x, boss.y));
+        }
+        // Survival and deathmatch have no way out
+        if self.survival.is_some() || self.deathmatch.is_some() {
+            return None;
+        }
+
+        let (x, y) = self.map().find(EXIT, 0)?;
+        Some((x as f32 + 0.5, y as f32 + 0.5))
+    }
+}
+
+// Where a bearing falls across the strip, counting from the middle, which
+// is the way the player faces
+fn offset(state: &State, bearing: f32) -> f32 {
+    let turn = normalize_angle(bearing - state.player_angle + PI) - PI;
+    -turn / SPAN * SCREEN_SIZE as f32
+}
+
+pub unsafe fn render(state: &State) {
+    let middle = SCREEN_SIZE / 2;
+
+    *DRAW_COLORS = 0x4;
+    rect(0, 0, SCREEN_SIZE as u32, HEIGHT as u32);
+
+    if let Some((x, y)) = state.objective() {
+        let bearing = atan2(state.player_y - y, x - state.player_x);
+        let half = (middle - 3) as f32;
+        let column = middle + offset(state, bearing).clamp(-half, half) as i32;
+        *DRAW_COLORS = 0x3;
+        rect(column - 2, 0, 5, HEIGHT as u32);
+    }
+
+    // Letters on the cardinal points, with ticks between
+    for eighth in 0..8 {
+        let along = offset(state, eighth as f32 * FRAC_PI_4);
+        if abs(along) > middle as f32 {
+            continue;
+        }
+
+        let column = middle + along as i32;
+        if eighth % 2 == 0 {
+            *DRAW_COLORS = 0x1;
+            text(POINTS[eighth / 2], column - 3, 1);
+        } else {
+            *DRAW_COLORS = 0x2;
+            rect(column, HEIGHT - 3, 1, 2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_objective_is_the_exit_once_no_boss_is_left() {
+        let mut state = State::new();
+        let (x, y) = state.objective().unwrap();
+        assert_eq!(state.map().tile(x as i32, y as i32), EXIT);
+
+        // North lies at the strip's left edge when facing east
+        state.player_angle = 0.0;
+        let along = offset(&state, PI / 2.0);
+        assert!(abs(along + SCREEN_SIZE as f32 / 2.0) < 1.0);
+
+        state.start_survival();
+        assert!(state.objective().is_none());
+    }
+}
@@ -0,0 +1,87 @@
+// Engine settings that can be changed while the game runs, e.g. from an
+// options menu
+use core::f32::consts::PI;
+
+#[derive(Clone, Copy)]
+pub struct Config {
+    // Horizontal field of view in radians, before any zoom
+    pub fov: f32,
+    // Height in pixels of a wall one tile away
+    pub wall_height: f32,
+    // Rays give up after this many tiles, leaving the column open
+    pub render_distance: f32,
+    // How far the view reaches through fog, which is only drawn, so shots
+    // and sight lines still go the whole render distance
+    pub fog_distance: f32,
+    // Top walking speed in tiles per frame
+    pub max_speed: f32,
+    // Radians per frame when a turn starts, and the fastest a held turn gets
+    pub turn_speed: f32,
+    pub max_turn_speed: f32,
+    // Swap the directions the d-pad and mouse turn
+    pub invert_turn: bool,
+    // With the lights out, half the width of the flashlight's beam in
+    // radians and how many tiles it reaches
+    pub flashlight_cone: f32,
+    pub flashlight_range: f32,
+}
+
+impl Config {
+    pub const fn new() -> Self {
+        Config {
+            fov: PI / 2.7,
+            wall_height: 100.0,
+            render_distance: 32.0,
+            fog_distance: 32.0,
+            max_speed: 0.045,
+            turn_speed: 0.02,
+            max_turn_speed: 0.08,
+            invert_turn: false,
+            flashlight_cone: PI / 16.0,
+            flashlight_range: 6.0,
+        }
+    }
+
+    pub const fn with_fov(mut self, fov: f32) -> Self {
+        self.fov = fov;
+        self
+    }
+
+    pub const fn with_wall_height(mut self, wall_height: f32) -> Self {
+        self.wall_height = wall_height;
+        self
+    }
+
+    pub const fn with_render_distance(mut self, render_distance: f32) -> Self {
+        self.render_distance = render_distance;
+        self
+    }
+
+    pub const fn with_max_speed(mut self, max_speed: f32) -> Self {
+        self.max_speed = max_speed;
+        self
+    }
+
+    pub const fn with_turn_speed(mut self, turn_speed: f32, max_turn_speed: f32) -> Self {
+        self.turn_speed = turn_speed;
+        self.max_turn_speed = max_turn_speed;
+        self
+    }
+
+    pub const fn with_invert_turn(mut self, invert_turn: bool) -> Self {
+        self.invert_turn = invert_turn;
+        self
+    }
+
+    pub const fn with_flashlight(mut self, cone: f32, range: f32) -> Self {
+        self.flashlight_cone = cone;
+        self.flashlight_range = range;
+        self
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,221 @@
+// Co-op: a second player on the second gamepad joins by pressing BUTTON_1,
+// and plays the same level at the first player's side on the right half of
+// a split screen. The two share health, ammo and keys, and only have their
+// own camera and controls. Those are swapped into the fields the first
+// player's live in whenever the second player moves, is drawn or is
+// targeted, so all the player code serves both
+use core::mem::swap;
+
+use crate::indicator::Indicator;
+use crate::input::Input;
+use crate::platform::{rect, BUTTON_1, DRAW_COLORS, SCREEN_SIZE};
+use crate::raycast::Viewport;
+use crate::render::VIEW_HEIGHT;
+use crate::sprites::{self, Sprite};
+use crate::{GameMode, State};
+
+// The two halves of the split screen, first player on the left
+pub const VIEWPORTS: [Viewport; 2] = [
+    Viewport { left: 0, columns: SCREEN_SIZE as usize / 2, ..Viewport::FULL },
+    Viewport { left: SCREEN_SIZE / 2, columns: SCREEN_SIZE as usize / 2, ..Viewport::FULL },
+];
+
+#[derive(Clone, Copy)]
+pub struct Partner {
+    pub x: f32,
+    pub y: f32,
+    angle: f32,
+    velocity_x: f32,
+    velocity_y: f32,
+    turn_speed: f32,
+    pitch: i32,
+    zoom: f32,
+    bob_phase: f32,
+    bob_weight: f32,
+    weapon_cooldown: u32,
+    reload: u32,
+    hit_marker: u32,
+    // Where their last hit came from and how hard their view shakes
+    indicator: Option<Indicator>,
+    trauma: f32,
+    input: Input,
+}
+
+impl Partner {
+    // A player standing still, with the press that brought them in spent
+    pub(crate) fn new(x: f32, y: f32, angle: f32, turn_speed: f32, gamepad: u8) -> Partner {
+        let mut input = Input::new();
+        input.replay(gamepad);
+        input.swallow();
+
+        Partner {
+            x,
+            y,
+            angle,
+            velocity_x: 0.0,
+            velocity_y: 0.0,
+            turn_speed,
+            pitch: 0,
+            zoom: 0.0,
+            bob_phase: 0.0,
+            bob_weight: 0.0,
+            weapon_cooldown: 0,
+            reload: 0,
+            hit_marker: 0,
+            indicator: None,
+            trauma: 0.0,
+            input,
+        }
+    }
+
+    // The player as another player sees them from (from_x, from_y), turned
+    // the way they face
+    pub(crate) fn sprite(&self, from_x: f32, from_y: f32) -> Sprite {
+        let image = sprites::FIGURES.side(self.x, self.y, self.angle, from_x, from_y);
+        Sprite { x: self.x, y: self.y, image }
+    }
+
+    pub(crate) fn place(&mut self, x: f32, y: f32, angle: f32) {
+        (self.x, self.y, self.angle) = (x, y, angle);
+        (self.velocity_x, self.velocity_y, self.pitch) = (0.0, 0.0, 0);
+    }
+
+    // Take this frame's buttons from the player's gamepad
+    pub(crate) fn poll(&mut self, gamepad: u8) {
+        self.input.replay(gamepad);
+    }
+}
+
+impl State {
+    // Read the second gamepad, letting a second player in on a press
+    pub(crate) fn poll_partner(&mut self, gamepad: u8) {
+        if let Some(partner) = &mut self.partner {
+            partner.poll(gamepad);
+            return;
+        }
+        let playing = self.mode == GameMode::Playing && self.deathmatch.is_none();
+        if gamepad & BUTTON_1 == 0 || !playing || self.attracting() {
+            return;
+        }
+
+        // Arriving beside the first player
+        let (x, y, angle) = self.player();
+        self.partner = Some(Partner::new(x, y, angle, self.config.turn_speed, gamepad));
+    }
+
+    // Start the second player off at the first player's spawn point, if
+    // there is one
+    pub(crate) fn place_partner(&mut self) {
+        let Some(partner) = &mut self.partner else { return };
+        partner.place(self.player_x, self.player_y, self.player_angle);
+    }
+
+    // Trade the first player's camera and controls for the second's, or
+    // back again; nothing happens without a second player
+    pub(crate) fn swap_partner(&mut self) {
+        let Some(mut partner) = self.partner else { return };
+        self.trade_places(&mut partner);
+        self.partner = Some(partner);
+    }
+
+    // Trade the camera and controls in the first player's fields for
+    // another player's
+    pub(crate) fn trade_places(&mut self, partner: &mut Partner) {
+        swap(&mut self.player_x, &mut partner.x);
+        swap(&mut self.player_y, &mut partner.y);
+        swap(&mut self.player_angle, &mut partner.angle);
+        swap(&mut self.velocity_x, &mut partner.velocity_x);
+        swap(&mut self.velocity_y, &mut partner.velocity_y);
+        swap(&mut self.turn_speed, &mut partner.turn_speed);
+        swap(&mut self.pitch, &mut partner.pitch);
+        swap(&mut self.zoom, &mut partner.zoom);
+        swap(&mut self.bob_phase, &mut partner.bob_phase);
+        swap(&mut self.bob_weight, &mut partner.bob_weight);
+        swap(&mut self.weapon_cooldown, &mut partner.weapon_cooldown);
+        swap(&mut self.reload, &mut partner.reload);
+        swap(&mut self.hit_marker, &mut partner.hit_marker);
+        swap(&mut self.indicator, &mut partner.indicator);
+        swap(&mut self.trauma, &mut partner.trauma);
+        swap(&mut self.input, &mut partner.input);
+    }
+
+    // The second player's turn at moving, picking up, using and firing
+    pub(crate) fn update_partner(&mut self) {
+        if self.partner.is_none() {
+            return;
+        }
+
+        self.swap_partner();
+        self.move_player();
+        self.explore();
+        self.weapon_cooldown = self.weapon_cooldown.saturating_sub(1);
+        self.reload = self.reload.saturating_sub(1);
+        self.hit_marker = self.hit_marker.saturating_sub(1);
+        self.update_indicator();
+        self.update_shake();
+        self.collect_pickups();
+        self.act();
+        self.swap_partner();
+    }
+
+    // Whether the second player is the nearer of the two to a point
+    pub(crate) fn partner_nearer(&self, x: f32, y: f32) -> bool {
+        let distance = |px: f32, py: f32| (px - x) * (px - x) + (py - y) * (py - y);
+        self.partner.is_some_and(|p| distance(p.x, p.y) < distance(self.player_x, self.player_y))
+    }
+}
+
+// A line between the two halves of the split screen
+pub unsafe fn render_divider() {
+    *DRAW_COLORS = 0x44;
+    rect(SCREEN_SIZE / 2 - 1, 0, 2, VIEW_HEIGHT as u32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::BUTTON_UP;
+    use crate::projectiles::{Kind, Projectile};
+
+    #[test]
+    fn partners_join_and_move_on_their_own() {
+        let mut state = State::new();
+        state.teleport(4.2, 1.5, 0.0);
+        state.transition = 0;
+        state.poll_partner(BUTTON_1);
+        let (x, y, _) = state.player();
+
+        // Only the second player walks up the corridor
+        for _ in 0..30 {
+            state.input.replay(0);
+            state.poll_partner(BUTTON_UP);
+            state.update();
+        }
+        let partner = state.partner.unwrap();
+        assert_eq!(state.player(), (x, y, 0.0));
+        assert!(partner.x > x + 0.5 && partner.y == y);
+
+        // Enemies go for whichever player is closer
+        assert!(state.partner_nearer(partner.x + 1.0, y));
+        assert!(!state.partner_nearer(x - 1.0, y));
+    }
+
+    #[test]
+    fn only_the_partner_hit_sees_and_feels_it() {
+        let mut state = State::new();
+        state.teleport(4.2, 1.5, 0.0);
+        state.transition = 0;
+        state.poll_partner(BUTTON_1);
+        let partner = state.partner.as_mut().unwrap();
+        (partner.x, partner.y) = (5.5, 6.5);
+
+        state.launch(Projectile::aimed(Kind::Shot(8), 8.5, 6.5, 5.5, 6.5));
+        while state.projectiles[0].is_some() {
+            state.update_projectiles();
+        }
+        assert!(state.indicator.is_none() && state.trauma == 0.0);
+
+        state.swap_partner();
+        assert!(state.indicator.is_some() && state.trauma > 0.0);
+    }
+}
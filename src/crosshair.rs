@@ -0,0 +1,78 @@
+// The crosshair on the horizon in the middle of the view. It closes in and
+// darkens while a shot fired now would hit an enemy or rival, checked along
+// the middle ray each frame, and a marker flashes round it when one lands
+use crate::math::{cos, sin};
+use crate::platform::{line, rect, DRAW_COLORS};
+use crate::raycast::Viewport;
+use crate::State;
+
+// Frames the hit marker shows for after a shot lands
+pub const HIT_MARKER_TIME: u32 = 10;
+
+// Pixels from the middle to the crosshair's ticks, idle and on a target, and
+// how long they are
+const GAP: i32 = 3;
+const TARGET_GAP: i32 = 1;
+const TICK: i32 = 2;
+
+// Pixels out from the middle along each diagonal the hit marker's strokes
+// run from and to
+const MARKER_FROM: i32 = 4;
+const MARKER_TO: i32 = 6;
+
+impl State {
+    // Check if a shot fired straight ahead would hit someone
+    pub(crate) fn on_target(&self) -> bool {
+        let (dir_x, dir_y) = (cos(self.player_angle), -sin(self.player_angle));
+        let wall = self.cast_ray(self.player_x, self.player_y, self.player_angle);
+        let mut nearest = wall.distance.min(self.weapon.spec().range);
+
+        self.enemy_in_line(dir_x, dir_y, &mut nearest).is_some()
+            || self.rival_in_line(dir_x, dir_y, &mut nearest).is_some()
+    }
+}
+
+pub unsafe fn render(state: &State, viewport: Viewport) {
+    let (x, y) = (viewport.left + viewport.columns as i32 / 2, state.horizon(viewport));
+    let (gap, color) = if state.on_target() { (TARGET_GAP, 0x4) } else { (GAP, 0x1) };
+
+    *DRAW_COLORS = color;
+    rect(x - gap - TICK, y, TICK as u32, 1);
+    rect(x + gap + 1, y, TICK as u32, 1);
+    rect(x, y - gap - TICK, 1, TICK as u32);
+    rect(x, y + gap + 1, 1, TICK as u32);
+
+    if state.hit_marker > 0 {
+        *DRAW_COLORS = 0x1;
+        for (dx, dy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+            let (from_x, from_y) = (x + dx * MARKER_FROM, y + dy * MARKER_FROM);
+            line(from_x, from_y, x + dx * MARKER_TO, y + dy * MARKER_TO);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enemies::{Archetype, Enemy, MAX_ENEMIES};
+    use crate::weapon::Weapon;
+
+    #[test]
+    fn the_crosshair_finds_enemies_in_reach_and_marks_hits() {
+        let mut state = State::new();
+        state.teleport(5.5, 6.5, 0.0);
+        state.enemies = [None; MAX_ENEMIES];
+        assert!(!state.on_target());
+
+        state.enemies[0] = Some(Enemy::new(Archetype::Guard, 8.5, 6.5));
+        assert!(state.on_target());
+
+        // Out of the knife's reach
+        state.weapon = Weapon::Knife;
+        assert!(!state.on_target());
+
+        state.weapon = Weapon::Pistol;
+        state.fire_bullet(Weapon::Pistol.spec());
+        assert_eq!(state.hit_marker, HIT_MARKER_TIME);
+    }
+}
@@ -0,0 +1,443 @@
+// Deathmatch: up to four players, one to a gamepad and a quarter of the
+// screen each, fighting it out on the first level's map emptied of enemies.
+// Pressing BUTTON_1 on any other gamepad at the title screen opens the lobby
+// for a match, and from then on lets that player in. Each player sees the
+// others as figures and shoots them like enemies, and whoever goes down is
+// straight back at the spawn point furthest from everyone else, for a frag
+// to the one who shot them unless they are on the same team. As in co-op
+// the other players take turns in the first player's fields, only with their
+// own health and weapons going along with them
+use core::mem::swap;
+
+use crate::coop::Partner;
+use crate::enemies::{self, MAX_ENEMIES};
+use crate::hud::{self, digits};
+use crate::levels::LEVELS;
+use crate::lobby::Rules;
+use crate::math::abs;
+use crate::platform::{blit, rect, text, BUTTON_1, DRAW_COLORS, SCREEN_SIZE};
+use crate::raycast::Viewport;
+use crate::render::draw_damage_flash;
+use crate::save::Save;
+use crate::weapon::{self, Weapon};
+use crate::{audio, GameMode, State, MAX_HEALTH};
+
+// The campaign level fought on
+const LEVEL: usize = 0;
+
+// Health taken from a player by each point of a weapon's damage, which is
+// counted out for enemies with far less; four pistol shots take a player down
+const DAMAGE_SCALE: i32 = 25;
+
+pub const MAX_PLAYERS: usize = 4;
+
+// Side of each player's square of the screen, and rows of the band of
+// health and frags along its top
+const QUARTER: i32 = SCREEN_SIZE / 2;
+const STATUS_HEIGHT: i32 = 10;
+
+// The quarters of the screen in reading order, one for each player
+pub const VIEWPORTS: [Viewport; MAX_PLAYERS] =
+    [quarter(0, 0), quarter(QUARTER, 0), quarter(0, QUARTER), quarter(QUARTER, QUARTER)];
+
+const fn quarter(left: i32, top: i32) -> Viewport {
+    Viewport { left, columns: QUARTER as usize, top, rows: QUARTER }
+}
+
+// A player other than the first, with what they keep to themselves
+#[derive(Clone, Copy)]
+struct Fighter {
+    body: Partner,
+    // Counting the first gamepad's player as 0
+    player: usize,
+    health: i32,
+    ammo: u32,
+    rockets: u32,
+    weapon: Weapon,
+    weapons: u8,
+    damage_flash: u32,
+    hazard_timer: u32,
+    heartbeat: u32,
+}
+
+impl Fighter {
+    fn new(player: usize, turn_speed: f32, gamepad: u8) -> Fighter {
+        Fighter {
+            body: Partner::new(0.0, 0.0, 0.0, turn_speed, gamepad),
+            player,
+            health: MAX_HEALTH,
+            ammo: weapon::START_AMMO,
+            rockets: 0,
+            weapon: Weapon::Pistol,
+            weapons: weapon::START_WEAPONS,
+            damage_flash: 0,
+            hazard_timer: 0,
+            heartbeat: 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Deathmatch {
+    // The second to fourth players once they join, each in their own slot
+    // except while trading places with the state's player
+    rivals: [Option<Fighter>; MAX_PLAYERS - 1],
+    // The player in the state's fields right now
+    player: usize,
+    // The player whose shot is landing, while it lands
+    attacker: Option<usize>,
+    frags: [u32; MAX_PLAYERS],
+    pub rules: Rules,
+    // The row picked in the lobby
+    pub item: usize,
+    // The campaign progress to keep on disk and go back to afterwards
+    pub campaign: Save,
+}
+
+impl Deathmatch {
+    // The number of players in, the first included
+    pub fn players(&self) -> usize {
+        1 + self.rivals.iter().flatten().count()
+    }
+
+    // Whether a shot from one player passes through another, as it does
+    // between teammates without friendly fire
+    fn spares(&self, shooter: usize, target: usize) -> bool {
+        self.rules.teammates(shooter, target) && !self.rules.friendly_fire
+    }
+
+    // The frags a player has to show, their team's in a team match
+    fn score(&self, player: usize) -> u32 {
+        let scorers = (0..MAX_PLAYERS).filter(|&other| {
+            other == player || self.rules.teams && self.rules.teammates(player, other)
+        });
+        scorers.map(|other| self.frags[other]).sum()
+    }
+}
+
+impl State {
+    // Read the other three gamepads, opening the lobby from the title when
+    // any of them presses BUTTON_1, and letting a player in on a press
+    pub(crate) fn poll_deathmatch(&mut self, gamepads: [u8; MAX_PLAYERS - 1]) {
+        let pressed = gamepads.iter().any(|gamepad| gamepad & BUTTON_1 != 0);
+        let title = self.mode == GameMode::Title && !self.attracting();
+        if self.deathmatch.is_none() && pressed && title {
+            self.open_lobby();
+        }
+
+        let open = matches!(self.mode, GameMode::Lobby | GameMode::Playing);
+        for (slot, gamepad) in gamepads.into_iter().enumerate() {
+            let Some(deathmatch) = &mut self.deathmatch else { return };
+            if let Some(rival) = &mut deathmatch.rivals[slot] {
+                rival.body.poll(gamepad);
+            } else if gamepad & BUTTON_1 != 0 && open {
+                let rival = Fighter::new(slot + 1, self.config.turn_speed, gamepad);
+                deathmatch.rivals[slot] = Some(rival);
+                self.swap_fighter(slot);
+                self.deploy();
+                self.swap_fighter(slot);
+            }
+        }
+    }
+
+    // Leave the campaign for the lobby of a match, with only the first
+    // player in so far
+    fn open_lobby(&mut self) {
+        let campaign = self.save();
+        self.deathmatch = Some(Deathmatch {
+            rivals: [None; MAX_PLAYERS - 1],
+            player: 0,
+            attacker: None,
+            frags: [0; MAX_PLAYERS],
+            rules: Rules::new(),
+            item: 0,
+            campaign,
+        });
+        (self.survival, self.partner) = (None, None);
+        self.mode = GameMode::Lobby;
+    }
+
+    // Leave the lobby for the match itself
+    pub(crate) fn start_deathmatch(&mut self) {
+        self.level = LEVEL;
+        self.restart();
+    }
+
+    // Back to the start of the campaign level the match was started from
+    pub(crate) fn end_deathmatch(&mut self) {
+        let save = self.save();
+        self.deathmatch = None;
+        self.resume(&save);
+    }
+
+    // Start the match over with no frags, on the level emptied of its
+    // enemies, sending everyone back to a spawn point
+    pub(crate) fn reset_deathmatch(&mut self) {
+        let Some(deathmatch) = &mut self.deathmatch else { return };
+        deathmatch.frags = [0; MAX_PLAYERS];
+        self.enemies = [None; MAX_ENEMIES];
+
+        self.deploy();
+        for slot in 0..MAX_PLAYERS - 1 {
+            if self.swap_fighter(slot) {
+                self.deploy();
+                self.swap_fighter(slot);
+            }
+        }
+    }
+
+    // Trade the player in the state's fields for the one in a slot, or back
+    // again, kit and all; false if nobody has joined in that slot
+    fn swap_fighter(&mut self, slot: usize) -> bool {
+        let Some(mut rival) = self.deathmatch.as_ref().and_then(|d| d.rivals[slot]) else {
+            return false;
+        };
+
+        self.trade_places(&mut rival.body);
+        swap(&mut self.health, &mut rival.health);
+        swap(&mut self.ammo, &mut rival.ammo);
+        swap(&mut self.rockets, &mut rival.rockets);
+        swap(&mut self.weapon, &mut rival.weapon);
+        swap(&mut self.weapons, &mut rival.weapons);
+        swap(&mut self.damage_flash, &mut rival.damage_flash);
+        swap(&mut self.hazard_timer, &mut rival.hazard_timer);
+        swap(&mut self.heartbeat, &mut rival.heartbeat);
+
+        if let Some(deathmatch) = &mut self.deathmatch {
+            swap(&mut deathmatch.player, &mut rival.player);
+            deathmatch.rivals[slot] = Some(rival);
+        }
+        true
+    }
+
+    // Bring a player into the state's fields between frames, while everyone
+    // is in their own slot, or send them back; false if they haven't joined
+    pub(crate) fn swap_player(&mut self, player: usize) -> bool {
+        player == 0 || self.swap_fighter(player - 1)
+    }
+
+    // The other players' turns at moving, picking up, using and firing
+    pub(crate) fn update_rivals(&mut self) {
+        for slot in 0..MAX_PLAYERS - 1 {
+            if self.swap_fighter(slot) {
+                self.move_player();
+                self.tick_player();
+                self.collect_pickups();
+                self.act();
+                self.swap_fighter(slot);
+            }
+        }
+    }
+
+    // Everyone but the player in the state's fields, with their slots
+    pub(crate) fn rivals(&self) -> impl Iterator<Item = (usize, &Partner)> + '_ {
+        let rivals = self.deathmatch.as_ref().map_or(&[][..], |deathmatch| &deathmatch.rivals);
+        let rivals = rivals.iter().enumerate();
+        rivals.filter_map(|(slot, rival)| rival.as_ref().map(|r| (slot, &r.body)))
+    }
+
+    // The nearest rival a shot down a direction passes close enough to hit
+    // before `nearest`, which comes down to them
+    pub(crate) fn rival_in_line(&self, dir_x: f32, dir_y: f32, nearest: &mut f32) -> Option<usize> {
+        let deathmatch = self.deathmatch.as_ref()?;
+        let mut target = None;
+
+        for (slot, rival) in deathmatch.rivals.iter().enumerate() {
+            let Some(Fighter { body: rival, player, .. }) = rival else { continue };
+            if deathmatch.spares(deathmatch.player, *player) {
+                continue;
+            }
+
+            let (rel_x, rel_y) = (rival.x - self.player_x, rival.y - self.player_y);
+            let depth = rel_x * dir_x + rel_y * dir_y;
+            let offset = rel_x * dir_y - rel_y * dir_x;
+
+            if depth > 0.0 && depth < *nearest && abs(offset) < enemies::HIT_RADIUS {
+                *nearest = depth;
+                target = Some(slot);
+            }
+        }
+
+        target
+    }
+
+    // Hurt the rival in a slot, with the frag going to the shooter should
+    // they go down
+    pub(crate) fn damage_rival(&mut self, slot: usize, damage: i32) {
+        let Some(deathmatch) = &mut self.deathmatch else { return };
+        deathmatch.attacker = Some(deathmatch.player);
+
+        let (x, y) = (self.player_x, self.player_y);
+        self.swap_fighter(slot);
+        self.play_sound_at(&audio::HIT, self.player_x, self.player_y);
+        self.indicate_hit(x, y);
+        self.hurt(damage * DAMAGE_SCALE);
+        self.swap_fighter(slot);
+
+        if let Some(deathmatch) = &mut self.deathmatch {
+            deathmatch.attacker = None;
+        }
+    }
+
+    // The player in the state's fields has gone down, so score the frag,
+    // unless a teammate's shot did it, and bring them back in
+    pub(crate) fn respawn(&mut self) {
+        let Some(deathmatch) = &mut self.deathmatch else { return };
+        let player = deathmatch.player;
+        let rules = deathmatch.rules;
+        if let Some(attacker) = deathmatch.attacker.filter(|&a| !rules.teammates(a, player)) {
+            deathmatch.frags[attacker] += 1;
+        }
+
+        self.play_sound_at(&audio::DEATH, self.player_x, self.player_y);
+        self.deploy();
+    }
+
+    // Put the player in the state's fields down at the spawn point furthest
+    // from everyone else, standing still with a fresh kit
+    fn deploy(&mut self) {
+        let level = &LEVELS[LEVEL];
+        let mut best = (level.spawn_x, level.spawn_y);
+
+        for point in level.enemies.iter().map(|spawn| (spawn.x, spawn.y)) {
+            if self.room_at(point) > self.room_at(best) {
+                best = point;
+            }
+        }
+
+        (self.player_x, self.player_y) = best;
+        self.player_angle = level.spawn_angle;
+        (self.velocity_x, self.velocity_y, self.pitch) = (0.0, 0.0, 0);
+        self.health = MAX_HEALTH;
+        (self.ammo, self.rockets) = (weapon::START_AMMO, 0);
+        (self.weapon, self.weapons) = (Weapon::Pistol, weapon::START_WEAPONS);
+    }
+
+    // Squared distance from a point to the nearest rival
+    fn room_at(&self, (x, y): (f32, f32)) -> f32 {
+        let distance = |(_, rival): (usize, &Partner)| {
+            (rival.x - x) * (rival.x - x) + (rival.y - y) * (rival.y - y)
+        };
+        self.rivals().map(distance).fold(f32::MAX, f32::min)
+    }
+}
+
+// A band along the top of the player's quarter with their health on the
+// left and frags, or their team's, on the right, and the damage flash kept
+// to the quarter
+pub unsafe fn render_status(state: &State, viewport: Viewport) {
+    let Some(deathmatch) = &state.deathmatch else { return };
+    let (left, top) = (viewport.left, viewport.top);
+    let mut buffer = [0; 3];
+
+    *DRAW_COLORS = 0x4;
+    rect(left, top, QUARTER as u32, STATUS_HEIGHT as u32);
+
+    *DRAW_COLORS = 0x10;
+    blit(hud::HEART.as_ptr(), left + 2, top + 1, 8, 8, hud::BLIT_1BPP);
+
+    *DRAW_COLORS = 0x1;
+    text(digits(state.health.max(0) as u32, &mut buffer), left + 12, top + 1);
+    let frags = digits(deathmatch.score(deathmatch.player), &mut buffer);
+    text(frags, left + QUARTER - 2 - frags.len() as i32 * 8, top + 1);
+
+    if state.damage_flash > 0 {
+        draw_damage_flash(left, top, QUARTER);
+    }
+}
+
+// A quarter still waiting for its player to press BUTTON_1
+pub unsafe fn render_vacancy(viewport: Viewport) {
+    *DRAW_COLORS = 0x44;
+    rect(viewport.left, viewport.top, QUARTER as u32, QUARTER as u32);
+
+    *DRAW_COLORS = 0x2;
+    text(b"PRESS X", viewport.left + 12, viewport.top + 36);
+}
+
+// Lines between the quarters
+pub unsafe fn render_dividers() {
+    *DRAW_COLORS = 0x44;
+    rect(QUARTER - 1, 0, 2, SCREEN_SIZE as u32);
+    rect(0, QUARTER - 1, SCREEN_SIZE as u32, 2);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::PI;
+
+    #[test]
+    fn shooting_a_rival_down_scores_and_sends_them_back() {
+        let mut state = State::new();
+        state.mode = GameMode::Title;
+        state.poll_deathmatch([0, BUTTON_1, 0]);
+        assert!(state.mode == GameMode::Lobby);
+        state.start_deathmatch();
+        assert!(state.enemies.iter().all(Option::is_none));
+
+        // Face each other down the corridor along row 6
+        state.transition = 0;
+        (state.player_x, state.player_y, state.player_angle) = (13.5, 6.5, 0.0);
+        state.deathmatch.as_mut().unwrap().rivals[1].as_mut().unwrap().body.place(17.5, 6.5, PI);
+
+        for frame in 0..600 {
+            state.input.replay(if frame % 2 == 0 { BUTTON_1 } else { 0 });
+            state.poll_deathmatch([0; MAX_PLAYERS - 1]);
+            state.update();
+            if state.deathmatch.unwrap().frags[0] > 0 {
+                break;
+            }
+        }
+
+        let deathmatch = state.deathmatch.unwrap();
+        assert_eq!(deathmatch.frags, [1, 0, 0, 0]);
+        let rival = deathmatch.rivals[1].unwrap();
+        assert_eq!((rival.player, rival.health), (2, MAX_HEALTH));
+        assert!(rival.body.x != 17.5 && state.mode == GameMode::Playing);
+    }
+
+    #[test]
+    fn shots_pass_through_teammates_without_friendly_fire() {
+        let mut state = State::new();
+        state.mode = GameMode::Title;
+        state.poll_deathmatch([BUTTON_1, BUTTON_1, 0]);
+        state.deathmatch.as_mut().unwrap().rules.teams = true;
+        state.start_deathmatch();
+
+        // The second player stands between the first and the third
+        state.transition = 0;
+        (state.player_x, state.player_y, state.player_angle) = (13.5, 6.5, 0.0);
+        let rivals = &mut state.deathmatch.as_mut().unwrap().rivals;
+        rivals[0].as_mut().unwrap().body.place(15.5, 6.5, PI);
+        rivals[1].as_mut().unwrap().body.place(17.5, 6.5, PI);
+
+        state.input.replay(BUTTON_1);
+        state.poll_deathmatch([0; MAX_PLAYERS - 1]);
+        state.update();
+
+        let rivals = state.deathmatch.unwrap().rivals;
+        assert_eq!(rivals[0].unwrap().health, MAX_HEALTH);
+        assert!(rivals[1].unwrap().health < MAX_HEALTH);
+    }
+
+    #[test]
+    fn each_fighter_keeps_their_own_heartbeat() {
+        let mut state = State::new();
+        state.mode = GameMode::Title;
+        state.poll_deathmatch([BUTTON_1, 0, 0]);
+        state.start_deathmatch();
+        state.transition = 0;
+
+        // Only the first player is low on health, so only their heart beats,
+        // a frame further each frame
+        state.health = 5;
+        for frame in 1..=10 {
+            state.input.replay(0);
+            state.poll_deathmatch([0; MAX_PLAYERS - 1]);
+            state.update();
+            assert_eq!(state.heartbeat, frame);
+        }
+        assert_eq!(state.deathmatch.unwrap().rivals[0].unwrap().heartbeat, 0);
+    }
+}
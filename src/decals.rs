@@ -0,0 +1,107 @@
+// Bullet holes left in walls where hitscan shots strike, fading after a
+// while. Only a few are kept, the newest taking the place of the oldest
+use crate::map::{is_wall, wall_height};
+use crate::math::abs;
+use crate::raycast::{Hit, RayHit};
+use crate::State;
+
+pub const MAX_DECALS: usize = 8;
+
+// Frames a hole lasts
+const DECAL_TIME: u32 = 600;
+
+// Half the width and height of a hole, as a share of the face
+const HALF_SIZE: f32 = 1.0 / 16.0;
+
+#[derive(Clone, Copy)]
+pub struct Decal {
+    // The wall tile, which of its faces and where on the face
+    tile_x: i32,
+    tile_y: i32,
+    side: bool,
+    u: f32,
+    v: f32,
+    frames: u32,
+}
+
+impl State {
+    // Leave a hole where a shot struck a wall, at eye height, which is
+    // further up the face of a taller wall
+    pub(crate) fn mark_wall(&mut self, hit: &Hit) {
+        let tile = self.map().tile(hit.tile_x, hit.tile_y);
+        if !is_wall(tile) {
+            return;
+        }
+
+        let decal = Decal {
+            tile_x: hit.tile_x,
+            tile_y: hit.tile_y,
+            side: hit.shadow,
+            u: hit.u,
+            v: wall_height(tile) - 0.5,
+            frames: DECAL_TIME,
+        };
+        if let Some(slot) = self.decals.iter_mut().min_by_key(|d| d.map_or(0, |d| d.frames)) {
+            *slot = Some(decal);
+        }
+    }
+
+    pub(crate) fn update_decals(&mut self) {
+        for slot in self.decals.iter_mut() {
+            if let Some(decal) = slot {
+                decal.frames -= 1;
+                if decal.frames == 0 {
+                    *slot = None;
+                }
+            }
+        }
+    }
+
+    // Where a hole down the column of wall a view ray hit sits, as a
+    // vertical texture coordinate, if there is one there
+    pub(crate) fn bullet_hole(&self, wall: &RayHit) -> Option<f32> {
+        let decal = self.decals.iter().flatten().find(|d| {
+            (d.tile_x, d.tile_y, d.side) == (wall.tile_x, wall.tile_y, wall.side)
+                && abs(d.u - wall.wall_u) < HALF_SIZE
+        })?;
+        Some(decal.v)
+    }
+}
+
+// Check if a vertical texture coordinate falls inside a hole
+pub fn covers(hole: Option<f32>, v: f32) -> bool {
+    hole.is_some_and(|hole| abs(v - hole) < HALF_SIZE)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::f32::consts::PI;
+
+    use super::*;
+    use crate::raycast::{Camera, Viewport};
+    use crate::weapon::Weapon;
+
+    #[test]
+    fn shots_leave_holes_in_the_wall_that_fade() {
+        let mut state = State::new();
+
+        // Facing the wall north of the corridor, half a tile away, where the
+        // knife leaves no mark
+        let camera = Camera { x: 5.5, y: 6.5, angle: PI / 2.0, fov: 1.0 };
+        let view = state.get_view(camera, Viewport::FULL);
+        state.teleport(5.5, 6.5, PI / 2.0);
+        state.fire_bullet(Weapon::Knife.spec());
+        assert!(state.bullet_hole(&view[80]).is_none());
+
+        let hit = state.cast_ray(5.5, 6.5, PI / 2.0);
+        state.mark_wall(&hit);
+        let hole = state.bullet_hole(&view[80]);
+        assert!(covers(hole, 0.5) && !covers(hole, 0.7));
+        assert!(state.bullet_hole(&view[20]).is_none());
+
+        for _ in 0..DECAL_TIME {
+            state.update_decals();
+        }
+        assert!(state.bullet_hole(&view[80]).is_none());
+    }
+}
@@ -0,0 +1,447 @@
+// Enemy pool and chase AI
+use crate::math::{floor, sqrt};
+use crate::sprites::{self, Animation};
+use crate::texture::Texture;
+use crate::State;
+
+pub const MAX_ENEMIES: usize = 16;
+
+// Corpses left lying about at once
+pub const MAX_CORPSES: usize = 16;
+
+// How far enemies can see the player, in tiles
+const SIGHT_RANGE: f32 = 8.0;
+
+// How close enemies get before they start attacking
+const ATTACK_RANGE: f32 = 0.8;
+
+// Frames between swings
+const ATTACK_COOLDOWN: u32 = 45;
+
+// Frames an enemy is drawn attacking after a swing or shot
+const ATTACK_POSE_FRAMES: u32 = 12;
+
+// How far off-center a shot can land and still hit
+pub const HIT_RADIUS: f32 = 0.3;
+
+// Frames between finding a fresh path to the target, which is also done on
+// reaching each tile along the way
+const REPATH_FRAMES: u32 = 30;
+
+const RADIUS: f32 = 0.25;
+
+// Behaviour flags: swings at the player up close, fires shots from range,
+// and stops closing in once near enough to shoot
+pub const MELEE: u8 = 1 << 0;
+pub const SHOOTS: u8 = 1 << 1;
+pub const KEEPS_DISTANCE: u8 = 1 << 2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Archetype {
+    Guard,
+    Runner,
+    Brute,
+    Sniper,
+    Boss,
+}
+
+pub struct Stats {
+    // Tiles walked per frame
+    pub speed: f32,
+    pub health: i32,
+    // Health taken off the player per swing or shot
+    pub damage: i32,
+    // How far off shots are fired from, and the frames between them while
+    // the player stays in sight
+    pub shot_range: f32,
+    pub shot_cooldown: u32,
+    // Standing and attacking
+    pub images: [&'static Texture; 2],
+    // Going down, ending on the corpse left behind
+    pub death: Animation,
+    // Attack patterns taking over from the speed and shot cooldown above as
+    // health runs down, an equal share of it each
+    pub phases: &'static [Phase],
+    pub flags: u8,
+}
+
+pub struct Phase {
+    pub speed: f32,
+    pub shot_cooldown: u32,
+    // Shots fired at once, fanned out around the aim
+    pub volley: u32,
+}
+
+const STATS: [Stats; 5] = [
+    Stats {
+        speed: 0.025,
+        health: 3,
+        damage: 10,
+        shot_range: 6.0,
+        shot_cooldown: 120,
+        images: [&sprites::GUARD, &sprites::GUARD_ATTACK],
+        death: Animation {
+            frames: &[&sprites::GUARD_FALLING, &sprites::GUARD_FALLEN, &sprites::GUARD_CORPSE],
+        },
+        phases: &[],
+        flags: MELEE | SHOOTS,
+    },
+    Stats {
+        speed: 0.05,
+        health: 2,
+        damage: 6,
+        shot_range: 0.0,
+        shot_cooldown: 0,
+        images: [&sprites::RUNNER, &sprites::RUNNER_ATTACK],
+        death: Animation {
+            frames: &[&sprites::RUNNER_FALLING, &sprites::RUNNER_FALLEN, &sprites::RUNNER_CORPSE],
+        },
+        phases: &[],
+        flags: MELEE,
+    },
+    Stats {
+        speed: 0.015,
+        health: 8,
+        damage: 20,
+        shot_range: 0.0,
+        shot_cooldown: 0,
+        images: [&sprites::BRUTE, &sprites::BRUTE_ATTACK],
+        death: Animation {
+            frames: &[&sprites::BRUTE_FALLING, &sprites::BRUTE_FALLEN, &sprites::BRUTE_CORPSE],
+        },
+        phases: &[],
+        flags: MELEE,
+    },
+    Stats {
+        speed: 0.02,
+        health: 2,
+        damage: 15,
+        shot_range: SIGHT_RANGE,
+        shot_cooldown: 90,
+        images: [&sprites::SNIPER, &sprites::SNIPER_ATTACK],
+        death: Animation {
+            frames: &[&sprites::SNIPER_FALLING, &sprites::SNIPER_FALLEN, &sprites::SNIPER_CORPSE],
+        },
+        phases: &[],
+        flags: SHOOTS | KEEPS_DISTANCE,
+    },
+    // Aimed shots, then wider volleys, then charging in firing fast
+    Stats {
+        speed: 0.02,
+        health: 40,
+        damage: 12,
+        shot_range: SIGHT_RANGE,
+        shot_cooldown: 75,
+        images: [&sprites::BOSS, &sprites::BOSS_ATTACK],
+        death: Animation {
+            frames: &[&sprites::BOSS_FALLING, &sprites::BOSS_FALLEN, &sprites::BOSS_CORPSE],
+        },
+        phases: &[
+            Phase { speed: 0.02, shot_cooldown: 75, volley: 1 },
+            Phase { speed: 0.02, shot_cooldown: 90, volley: 3 },
+            Phase { speed: 0.04, shot_cooldown: 45, volley: 5 },
+        ],
+        flags: MELEE | SHOOTS,
+    },
+];
+
+impl Archetype {
+    pub const fn stats(self) -> &'static Stats {
+        &STATS[self as usize]
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    // Standing still until the player shows up
+    Idle,
+    // Walking its route in a loop until the player shows up
+    Patrol,
+    // Walking towards where the player was last seen
+    Chase,
+    // In range and swinging at the player, for those that swing
+    Attack,
+}
+
+impl Mode {
+    // After the player, rather than minding its own business
+    pub const fn hunting(self) -> bool {
+        matches!(self, Mode::Chase | Mode::Attack)
+    }
+}
+
+// What kind of enemy starts out where, and the tiles it walks between while it has
+// not noticed the player, looping back to the first after the last
+pub struct Spawn {
+    pub archetype: Archetype,
+    pub x: f32,
+    pub y: f32,
+    pub route: &'static [(i32, i32)],
+}
+
+// What an enemy did to the player this frame
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Attack {
+    Swing,
+    Shot,
+}
+
+// A sound loud enough to bring enemies running, heard through walls by any
+// enemy within its radius
+#[derive(Clone, Copy)]
+pub struct Noise {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+}
+
+#[derive(Clone, Copy)]
+pub struct Enemy {
+    pub archetype: Archetype,
+    pub x: f32,
+    pub y: f32,
+    pub health: i32,
+    pub mode: Mode,
+    target_x: f32,
+    target_y: f32,
+    cooldown: u32,
+    shot_cooldown: u32,
+    // Frames left of the attacking pose
+    pose: u32,
+    // Next tile along the path to the target, and the frames until the path
+    // is worked out again
+    waypoint: Option<(i32, i32)>,
+    repath: u32,
+    // Patrol route and the stop along it being walked to
+    route: &'static [(i32, i32)],
+    stop: usize,
+}
+
+impl Enemy {
+    pub const fn new(archetype: Archetype, x: f32, y: f32) -> Enemy {
+        let stats = archetype.stats();
+        Enemy {
+            archetype,
+            x,
+            y,
+            health: stats.health,
+            mode: Mode::Idle,
+            target_x: x,
+            target_y: y,
+            cooldown: ATTACK_COOLDOWN,
+            shot_cooldown: stats.shot_cooldown,
+            pose: 0,
+            waypoint: None,
+            repath: 0,
+            route: &[],
+            stop: 0,
+        }
+    }
+
+    pub const fn stats(&self) -> &'static Stats {
+        self.archetype.stats()
+    }
+
+    // The attack pattern for the health left, for enemies that have them
+    pub fn phase(&self) -> Option<&'static Phase> {
+        let stats = self.stats();
+        let lost = (stats.health - self.health).max(0) as usize;
+        let idx = lost * stats.phases.len() / stats.health as usize;
+        stats.phases.get(idx).or(stats.phases.last())
+    }
+
+    fn speed(&self) -> f32 {
+        self.phase().map_or(self.stats().speed, |phase| phase.speed)
+    }
+
+    fn shot_cooldown(&self) -> u32 {
+        self.phase().map_or(self.stats().shot_cooldown, |phase| phase.shot_cooldown)
+    }
+
+    pub fn volley(&self) -> u32 {
+        self.phase().map_or(1, |phase| phase.volley)
+    }
+
+    pub fn image(&self) -> &'static Texture {
+        self.stats().images[(self.pose > 0) as usize]
+    }
+
+    // Returns the attack made on the frames the enemy swings or fires
+    pub fn update(&mut self, state: &State) -> Option<Attack> {
+        let attack = self.think(state);
+        self.pose = if attack.is_some() { ATTACK_POSE_FRAMES } else { self.pose.saturating_sub(1) };
+        attack
+    }
+
+    fn think(&mut self, state: &State) -> Option<Attack> {
+        let stats = self.stats();
+        let range = distance(self.x, self.y, state.player_x, state.player_y);
+        let sees_player = range < SIGHT_RANGE
+            && state.line_of_sight(self.x, self.y, state.player_x, state.player_y);
+
+        if sees_player {
+            (self.target_x, self.target_y) = (state.player_x, state.player_y);
+        }
+
+        self.mode = match self.mode {
+            _ if sees_player && range < ATTACK_RANGE && stats.flags & MELEE != 0 => Mode::Attack,
+            _ if sees_player && !self.mode.hunting() => Mode::Chase,
+            Mode::Attack => Mode::Chase,
+            // Give up once the last known position turns up empty
+            Mode::Chase
+                if !sees_player
+                    && distance(self.x, self.y, self.target_x, self.target_y) < self.speed() =>
+            {
+                self.calm_mode()
+            }
+            mode => mode,
+        };
+
+        // Shots need the player in sight for a while first, and only come
+        // from those that shoot
+        let aiming = sees_player && range < stats.shot_range && stats.flags & SHOOTS != 0;
+        if self.mode == Mode::Chase && aiming {
+            self.shot_cooldown = self.shot_cooldown.saturating_sub(1);
+        } else {
+            self.shot_cooldown = self.shot_cooldown();
+        }
+
+        match self.mode {
+            Mode::Idle => None,
+            Mode::Patrol => {
+                self.patrol(state);
+                None
+            }
+            Mode::Chase => {
+                self.cooldown = ATTACK_COOLDOWN;
+                if !(aiming && stats.flags & KEEPS_DISTANCE != 0) {
+                    self.walk_to(state, self.target_x, self.target_y, sees_player);
+                }
+                if !aiming || self.shot_cooldown > 0 {
+                    return None;
+                }
+
+                self.shot_cooldown = self.shot_cooldown();
+                Some(Attack::Shot)
+            }
+            Mode::Attack => {
+                self.cooldown = self.cooldown.saturating_sub(1);
+                if self.cooldown > 0 {
+                    return None;
+                }
+
+                self.cooldown = ATTACK_COOLDOWN;
+                Some(Attack::Swing)
+            }
+        }
+    }
+
+    // Take a hit and go after whoever fired it
+    pub fn hurt(&mut self, damage: i32, from_x: f32, from_y: f32) {
+        self.health -= damage;
+        (self.target_x, self.target_y) = (from_x, from_y);
+
+        if !self.mode.hunting() {
+            self.mode = Mode::Chase;
+        }
+    }
+
+    // Go and see where a noise came from, if close enough to hear it
+    pub fn hear(&mut self, noise: &Noise) {
+        if distance(self.x, self.y, noise.x, noise.y) > noise.radius {
+            return;
+        }
+
+        (self.target_x, self.target_y) = (noise.x, noise.y);
+        self.repath = 0;
+        if !self.mode.hunting() {
+            self.mode = Mode::Chase;
+        }
+    }
+
+    // What to go back to on losing the player
+    fn calm_mode(&self) -> Mode {
+        if self.route.is_empty() {
+            Mode::Idle
+        } else {
+            Mode::Patrol
+        }
+    }
+
+    // Head for the next stop on the route, from wherever the enemy is, and
+    // move on to the one after once there
+    fn patrol(&mut self, state: &State) {
+        let (x, y) = self.route[self.stop];
+        let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+
+        if distance(self.x, self.y, x, y) < self.speed() {
+            self.stop = (self.stop + 1) % self.route.len();
+            self.repath = 0;
+        } else {
+            self.walk_to(state, x, y, false);
+        }
+    }
+
+    // Walk straight at a point when told it is in sight, otherwise follow the
+    // path towards it, walking straight if none can be found
+    fn walk_to(&mut self, state: &State, x: f32, y: f32, in_sight: bool) {
+        let tile = (floor(self.x) as i32, floor(self.y) as i32);
+        let goal = (floor(x) as i32, floor(y) as i32);
+
+        self.repath = self.repath.saturating_sub(1);
+        if self.repath == 0 || self.waypoint == Some(tile) {
+            self.repath = REPATH_FRAMES;
+            self.waypoint = state.next_step(tile, goal);
+        }
+
+        match self.waypoint {
+            Some((x, y)) if !in_sight => {
+                self.step_towards(state, x as f32 + 0.5, y as f32 + 0.5);
+            }
+            _ => self.step_towards(state, x, y),
+        }
+    }
+
+    // Walk straight at a point, sliding along any walls in the way
+    fn step_towards(&mut self, state: &State, x: f32, y: f32) {
+        let (range, speed) = (distance(self.x, self.y, x, y), self.speed());
+        if range < speed {
+            return;
+        }
+
+        let step_x = (x - self.x) / range * speed;
+        let step_y = (y - self.y) / range * speed;
+
+        if !state.collides(self.x + step_x, self.y, RADIUS) {
+            self.x += step_x;
+        }
+        if !state.collides(self.x, self.y + step_y, RADIUS) {
+            self.y += step_y;
+        }
+    }
+}
+
+fn distance(from_x: f32, from_y: f32, to_x: f32, to_y: f32) -> f32 {
+    let (dx, dy) = (to_x - from_x, to_y - from_y);
+    sqrt(dx * dx + dy * dy)
+}
+
+// Fill a fresh pool from a level's spawn points, setting the enemies with a
+// route off on patrol
+pub const fn spawn(points: &[Spawn]) -> [Option<Enemy>; MAX_ENEMIES] {
+    let mut pool = [None; MAX_ENEMIES];
+
+    let mut idx = 0;
+    while idx < points.len() && idx < MAX_ENEMIES {
+        let point = &points[idx];
+        let mut enemy = Enemy::new(point.archetype, point.x, point.y);
+        if !point.route.is_empty() {
+            enemy.route = point.route;
+            enemy.mode = Mode::Patrol;
+        }
+        pool[idx] = Some(enemy);
+        idx += 1;
+    }
+
+    pool
+}
@@ -0,0 +1,219 @@
+// Q16.16 fixed-point numbers with table-driven trigonometry
+use core::f32::consts::FRAC_PI_4;
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Fixed(pub i32);
+
+// Fractional bits
+const SHIFT: u32 = 16;
+
+// Entries in one full turn of the sine wave
+const TURN: i64 = 1024;
+
+// sin(i / 1024 * 2π) for the first quarter turn, in Q16.16
+const QUARTER_SINE: [i32; 257] = [
+    0, 402, 804, 1206, 1608, 2010, 2412, 2814,
+    3216, 3617, 4019, 4420, 4821, 5222, 5623, 6023,
+    6424, 6824, 7224, 7623, 8022, 8421, 8820, 9218,
+    9616, 10014, 10411, 10808, 11204, 11600, 11996, 12391,
+    12785, 13180, 13573, 13966, 14359, 14751, 15143, 15534,
+    15924, 16314, 16703, 17091, 17479, 17867, 18253, 18639,
+    19024, 19409, 19792, 20175, 20557, 20939, 21320, 21699,
+    22078, 22457, 22834, 23210, 23586, 23961, 24335, 24708,
+    25080, 25451, 25821, 26190, 26558, 26925, 27291, 27656,
+    28020, 28383, 28745, 29106, 29466, 29824, 30182, 30538,
+    30893, 31248, 31600, 31952, 32303, 32652, 33000, 33347,
+    33692, 34037, 34380, 34721, 35062, 35401, 35738, 36075,
+    36410, 36744, 37076, 37407, 37736, 38064, 38391, 38716,
+    39040, 39362, 39683, 40002, 40320, 40636, 40951, 41264,
+    41576, 41886, 42194, 42501, 42806, 43110, 43412, 43713,
+    44011, 44308, 44604, 44898, 45190, 45480, 45769, 46056,
+    46341, 46624, 46906, 47186, 47464, 47741, 48015, 48288,
+    48559, 48828, 49095, 49361, 49624, 49886, 50146, 50404,
+    50660, 50914, 51166, 51417, 51665, 51911, 52156, 52398,
+    52639, 52878, 53114, 53349, 53581, 53812, 54040, 54267,
+    54491, 54714, 54934, 55152, 55368, 55582, 55794, 56004,
+    56212, 56418, 56621, 56823, 57022, 57219, 57414, 57607,
+    57798, 57986, 58172, 58356, 58538, 58718, 58896, 59071,
+    59244, 59415, 59583, 59750, 59914, 60075, 60235, 60392,
+    60547, 60700, 60851, 60999, 61145, 61288, 61429, 61568,
+    61705, 61839, 61971, 62101, 62228, 62353, 62476, 62596,
+    62714, 62830, 62943, 63054, 63162, 63268, 63372, 63473,
+    63572, 63668, 63763, 63854, 63944, 64031, 64115, 64197,
+    64277, 64354, 64429, 64501, 64571, 64639, 64704, 64766,
+    64827, 64884, 64940, 64993, 65043, 65091, 65137, 65180,
+    65220, 65259, 65294, 65328, 65358, 65387, 65413, 65436,
+    65457, 65476, 65492, 65505, 65516, 65525, 65531, 65535,
+    65536,
+];
+
+const PI: Fixed = Fixed::from_f32(core::f32::consts::PI);
+const HALF_PI: Fixed = Fixed::from_f32(core::f32::consts::FRAC_PI_2);
+
+// 2^32 / 2π, converts radians in Q16.16 to turns in Q16.16 after a shift by 32
+const INV_TAU: i64 = 683565276;
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(1 << SHIFT);
+    pub const MAX: Fixed = Fixed(i32::MAX);
+    pub const MIN: Fixed = Fixed(i32::MIN);
+
+    pub const fn from_f32(value: f32) -> Fixed {
+        Fixed((value * (1 << SHIFT) as f32) as i32)
+    }
+
+    pub const fn from_int(value: i32) -> Fixed {
+        Fixed(value << SHIFT)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        self.0 as f32 / (1 << SHIFT) as f32
+    }
+
+    pub fn floor(self) -> Fixed {
+        Fixed(self.0 & !((1 << SHIFT) - 1))
+    }
+
+    pub fn abs(self) -> Fixed {
+        Fixed(self.0.saturating_abs())
+    }
+
+    pub fn sin(self) -> Fixed {
+        let turns = (self.0 as i64 * INV_TAU) >> 32;
+
+        // Interpolate between neighbouring table entries
+        let idx = turns >> (SHIFT - 10);
+        let frac = turns & ((1 << (SHIFT - 10)) - 1);
+
+        let a = wave(idx) as i64;
+        let b = wave(idx + 1) as i64;
+
+        Fixed((a + (((b - a) * frac) >> (SHIFT - 10))) as i32)
+    }
+
+    pub fn cos(self) -> Fixed {
+        (self + HALF_PI).sin()
+    }
+
+    pub fn tan(self) -> Fixed {
+        self.sin() / self.cos()
+    }
+
+    pub fn sqrt(self) -> Fixed {
+        if self.0 <= 0 {
+            return Fixed::ZERO;
+        }
+
+        // Integer square root of the value shifted up by another 16 bits
+        let n = (self.0 as u64) << SHIFT;
+        let mut root = 0u64;
+        let mut bit = 1u64 << 46;
+
+        while bit > n {
+            bit >>= 2;
+        }
+        let mut rest = n;
+        while bit != 0 {
+            if rest >= root + bit {
+                rest -= root + bit;
+                root = (root >> 1) + bit;
+            } else {
+                root >>= 1;
+            }
+            bit >>= 2;
+        }
+
+        Fixed(root as i32)
+    }
+
+    // Four-quadrant arctangent of self / x
+    pub fn atan2(self, x: Fixed) -> Fixed {
+        let y = self;
+
+        if x == Fixed::ZERO && y == Fixed::ZERO {
+            Fixed::ZERO
+        } else if x.abs() >= y.abs() {
+            let angle = atan(y / x);
+
+            if x > Fixed::ZERO {
+                angle
+            } else if y >= Fixed::ZERO {
+                angle + PI
+            } else {
+                angle - PI
+            }
+        } else {
+            let angle = -atan(x / y);
+
+            if y > Fixed::ZERO { angle + HALF_PI } else { angle - HALF_PI }
+        }
+    }
+}
+
+// atan(z) ≈ π/4·z − z(|z| − 1)(0.2447 + 0.0663|z|), for |z| <= 1
+fn atan(z: Fixed) -> Fixed {
+    let abs = z.abs();
+
+    Fixed::from_f32(FRAC_PI_4) * z
+        - z * (abs - Fixed::ONE) * (Fixed::from_f32(0.2447) + Fixed::from_f32(0.0663) * abs)
+}
+
+// Sine at a table index, for any index in turns of 1024
+fn wave(idx: i64) -> i32 {
+    let idx = idx.rem_euclid(TURN) as usize;
+
+    match idx / 256 {
+        0 => QUARTER_SINE[idx],
+        1 => QUARTER_SINE[512 - idx],
+        2 => -QUARTER_SINE[idx - 512],
+        _ => -QUARTER_SINE[1024 - idx],
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+
+    fn add(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0.wrapping_add(rhs.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+
+    fn sub(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0.wrapping_sub(rhs.0))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+
+    fn mul(self, rhs: Fixed) -> Fixed {
+        Fixed(((self.0 as i64 * rhs.0 as i64) >> SHIFT) as i32)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Fixed;
+
+    // Saturates instead of trapping on division by zero
+    fn div(self, rhs: Fixed) -> Fixed {
+        if rhs.0 == 0 {
+            return if self.0 < 0 { Fixed::MIN } else { Fixed::MAX };
+        }
+
+        let quotient = ((self.0 as i64) << SHIFT) / rhs.0 as i64;
+        Fixed(quotient.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+
+    fn neg(self) -> Fixed {
+        Fixed(self.0.wrapping_neg())
+    }
+}
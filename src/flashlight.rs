@@ -0,0 +1,67 @@
+// Darkness mode: with the lights out only the flashlight shows anything, in
+// a cone around the middle of the view that fades out over its range. The
+// cone is set in the Config. A flashlight pickup marks the way into the dark
+// and puts the lights out, and the next one turns them back on
+use crate::math::{abs, cos, sin};
+use crate::raycast::{Camera, Viewport};
+use crate::State;
+
+// How far light reaches down each column of a viewport
+#[derive(Clone, Copy)]
+pub struct Light {
+    // Half the width of the beam in columns, if the lights are out
+    beam: Option<f32>,
+    center: f32,
+    range: f32,
+}
+
+impl Light {
+    pub fn new(state: &State, camera: Camera, viewport: Viewport) -> Self {
+        let config = &state.config;
+        let cone = config.flashlight_cone;
+        let beam = state.lights_out.then(|| camera.focal_length() * sin(cone) / cos(cone));
+        let range = if state.lights_out {
+            config.flashlight_range.min(state.view_distance())
+        } else {
+            state.view_distance()
+        };
+
+        Light { beam, center: viewport.center(), range }
+    }
+
+    // Tiles the light reaches down a column, none at all outside the beam
+    pub fn reach(&self, column: i32) -> f32 {
+        match self.beam {
+            Some(beam) if abs(column as f32 + 0.5 - self.center) > beam => 0.0,
+            _ => self.range,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::levels::LEVELS;
+    use crate::pickups::Item;
+
+    #[test]
+    fn flashlight_pickups_put_the_lights_out_and_on_again() {
+        let mut state = State::with_config(Config::new().with_flashlight(0.2, 5.0));
+        state.load_level(2);
+        let light = Light::new(&state, state.camera(), Viewport::FULL);
+        assert_eq!((light.reach(0), light.reach(80)), (32.0, 32.0));
+
+        let flashlights = LEVELS[2].pickups.iter().filter(|pickup| pickup.item == Item::Flashlight);
+        for (count, pickup) in flashlights.enumerate() {
+            state.teleport(pickup.x, pickup.y, 0.0);
+            state.collect_pickups();
+            assert_eq!(state.lights_out, count == 0);
+
+            let light = Light::new(&state, state.camera(), Viewport::FULL);
+            let edge = if state.lights_out { 0.0 } else { 32.0 };
+            assert_eq!((light.reach(0), light.reach(80)), (edge, light.range));
+        }
+        assert!(!state.lights_out);
+    }
+}
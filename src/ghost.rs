@@ -0,0 +1,239 @@
+// Ghost runs for time attack: the player's position and angle are sampled
+// through every run of a level, and the latest run to top its level's
+// leaderboard is kept on disk after the save, for a see-through figure to
+// retrace on later attempts at that level
+use core::f32::consts::TAU;
+
+use crate::platform::{diskr, diskw};
+use crate::save::{self, DISK_SIZE};
+use crate::{player, rle, State};
+
+// Frames between samples; the ghost moves in a straight line between them
+pub const SAMPLE_FRAMES: u32 = 20;
+
+// A little over two minutes of samples; the ghost vanishes where the
+// recording of a longer run stops
+const MAX_SAMPLES: usize = 384;
+
+// Positions kept in eighths of a tile and angles in 256ths of a turn, each
+// in a plane of its own
+const POSITION_SCALE: f32 = 8.0;
+const ANGLE_SCALE: f32 = 256.0 / TAU;
+const PLANES: usize = 3;
+
+// The disk after the save holds magic, the level, the sample count and the
+// packed length, then each plane as the change from one sample to the next,
+// packed. Standing still and walking straight give long runs of the same
+// change, which pack well
+const MAGIC: [u8; 2] = *b"GH";
+const HEADER: usize = 7;
+const SIZE: usize = DISK_SIZE - save::SIZE;
+const MAX_PACKED: usize = SIZE - HEADER;
+
+#[derive(Clone, Copy)]
+pub struct Recording {
+    level: u8,
+    len: usize,
+    planes: [[u8; MAX_SAMPLES]; PLANES],
+}
+
+impl Recording {
+    pub const fn new(level: usize) -> Self {
+        Recording { level: level as u8, len: 0, planes: [[0; MAX_SAMPLES]; PLANES] }
+    }
+
+    pub fn level(&self) -> usize {
+        self.level as usize
+    }
+
+    fn push(&mut self, x: f32, y: f32, angle: f32) {
+        if self.len == MAX_SAMPLES {
+            return;
+        }
+
+        let angle = player::normalize_angle(angle) * ANGLE_SCALE;
+        let sample = [x * POSITION_SCALE + 0.5, y * POSITION_SCALE + 0.5, angle + 0.5];
+        for (plane, value) in self.planes.iter_mut().zip(sample) {
+            plane[self.len] = value as u8;
+        }
+        self.len += 1;
+    }
+
+    // Where the run was a number of frames in, between the samples either
+    // side, or None once past its last sample
+    fn at(&self, frames: u32) -> Option<(f32, f32, f32)> {
+        let idx = (frames / SAMPLE_FRAMES) as usize;
+        if idx + 1 >= self.len {
+            return None;
+        }
+
+        let t = (frames % SAMPLE_FRAMES) as f32 / SAMPLE_FRAMES as f32;
+        let [xs, ys, angles] = &self.planes;
+        let between = |plane: &[u8; MAX_SAMPLES]| {
+            let change = plane[idx + 1] as f32 - plane[idx] as f32;
+            (plane[idx] as f32 + change * t) / POSITION_SCALE
+        };
+        // Turning the short way round
+        let turn = angles[idx + 1].wrapping_sub(angles[idx]) as i8 as f32;
+        let angle = (angles[idx] as f32 + turn * t) / ANGLE_SCALE;
+
+        Some((between(xs), between(ys), player::normalize_angle(angle)))
+    }
+
+    // The first `len` samples, plane after plane, as changes from the last
+    fn changes<'a>(&self, len: usize, out: &'a mut [u8; PLANES * MAX_SAMPLES]) -> &'a [u8] {
+        let mut at = 0;
+        for plane in &self.planes {
+            let mut last = 0;
+            for &value in &plane[..len] {
+                out[at] = value.wrapping_sub(last);
+                (last, at) = (value, at + 1);
+            }
+        }
+        &out[..at]
+    }
+
+    // Samples past what fits on disk are dropped off the end
+    fn encode(&self) -> [u8; SIZE] {
+        let mut changes = [0; PLANES * MAX_SAMPLES];
+        let mut bytes = [0; SIZE];
+
+        let mut len = self.len;
+        let mut packed = rle::pack_into(self.changes(len, &mut changes), &mut bytes[HEADER..]);
+        while packed > MAX_PACKED {
+            len = len * 7 / 8;
+            packed = rle::pack_into(self.changes(len, &mut changes), &mut bytes[HEADER..]);
+        }
+
+        bytes[..2].copy_from_slice(&MAGIC);
+        bytes[2] = self.level;
+        bytes[3..5].copy_from_slice(&(len as u16).to_le_bytes());
+        bytes[5..HEADER].copy_from_slice(&(packed as u16).to_le_bytes());
+        bytes
+    }
+
+    // Unpacked in place, as recordings are big for the console's stack; left
+    // empty if there is no ghost to read
+    fn decode(&mut self, bytes: &[u8]) {
+        self.len = 0;
+        if bytes.len() < HEADER || bytes[..2] != MAGIC {
+            return;
+        }
+
+        let len = u16::from_le_bytes([bytes[3], bytes[4]]) as usize;
+        let packed = u16::from_le_bytes([bytes[5], bytes[6]]) as usize;
+        let Some(packed) = bytes.get(HEADER..HEADER + packed) else { return };
+        let samples = self.planes.as_flattened_mut();
+        if len > MAX_SAMPLES || rle::unpack(packed, samples) != PLANES * len {
+            return;
+        }
+
+        // The planes come back to back, so spread them out from the last,
+        // then add the changes back up
+        for plane in (1..PLANES).rev() {
+            samples.copy_within(plane * len..(plane + 1) * len, plane * MAX_SAMPLES);
+        }
+        for plane in &mut self.planes {
+            let mut last = 0u8;
+            for value in &mut plane[..len] {
+                last = last.wrapping_add(*value);
+                *value = last;
+            }
+        }
+        (self.level, self.len) = (bytes[2], len);
+    }
+
+    // The ghost kept on disk, if there is one
+    pub unsafe fn read(&mut self) {
+        let mut disk = [0; DISK_SIZE];
+        let len = diskr(disk.as_mut_ptr(), DISK_SIZE as u32) as usize;
+        self.decode(disk.get(save::SIZE..len).unwrap_or_default());
+    }
+
+    // Keep this run as the ghost, after whatever save is on disk
+    pub unsafe fn write(&self) {
+        let mut disk = [0; DISK_SIZE];
+        diskr(disk.as_mut_ptr(), save::SIZE as u32);
+        disk[save::SIZE..].copy_from_slice(&self.encode());
+        diskw(disk.as_ptr(), DISK_SIZE as u32);
+    }
+}
+
+impl State {
+    // Sample the player every SAMPLE_FRAMES frames of the run
+    pub(crate) fn record_ghost(&mut self) {
+        if self.stats.frames.is_multiple_of(SAMPLE_FRAMES) {
+            self.recording.push(self.player_x, self.player_y, self.player_angle);
+        }
+    }
+
+    // Where the ghost is at this point of the current level, angle included,
+    // while there is one racing the player
+    pub fn ghost(&self) -> Option<(f32, f32, f32)> {
+        let racing = self.survival.is_none() && self.deathmatch.is_none();
+        if self.ghost.level() != self.level || !racing {
+            return None;
+        }
+        self.ghost.at(self.stats.frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ghosts_read_back_and_follow_their_run() {
+        let mut run = Recording::new(2);
+        for idx in 0..MAX_SAMPLES {
+            // A straight walk, a wait and a turn on the spot
+            let x = 1.5 + (idx.min(200) as f32) * 0.125;
+            run.push(x, 3.5, (idx.saturating_sub(300) as f32) * 0.05);
+        }
+
+        let mut ghost = Recording::new(0);
+        ghost.decode(&run.encode());
+        assert_eq!((ghost.level(), ghost.len), (2, MAX_SAMPLES));
+        assert!(ghost.planes == run.planes);
+
+        let (x, y, angle) = ghost.at(SAMPLE_FRAMES * 10 + SAMPLE_FRAMES / 2).unwrap();
+        assert!((x - 2.8125).abs() < 0.01 && y == 3.5 && angle == 0.0);
+        assert!(ghost.at(SAMPLE_FRAMES * MAX_SAMPLES as u32).is_none());
+        ghost.decode(&[0; SIZE]);
+        assert!(ghost.at(0).is_none());
+    }
+
+    #[test]
+    fn ghosts_cut_short_on_disk_read_back_empty() {
+        let mut run = Recording::new(1);
+        for idx in 0..100 {
+            run.push(1.5 + idx as f32 * 0.25, 2.5, 0.0);
+        }
+        let mut bytes = run.encode();
+        let packed = u16::from_le_bytes([bytes[5], bytes[6]]);
+
+        // Claiming less packed data than there is cuts a block short
+        let mut ghost = Recording::new(0);
+        for cut in 1..packed {
+            bytes[5..HEADER].copy_from_slice(&(packed - cut).to_le_bytes());
+            ghost.decode(&bytes);
+            assert_eq!(ghost.len, 0);
+        }
+    }
+
+    #[test]
+    fn runs_too_long_for_the_disk_lose_their_end() {
+        let mut run = Recording::new(0);
+        for idx in 0..MAX_SAMPLES {
+            let wander = (idx * 37 % 11) as f32 * 0.125;
+            run.push(wander, 4.0 - wander, idx as f32);
+        }
+
+        let mut ghost = Recording::new(0);
+        ghost.decode(&run.encode());
+        assert!(ghost.len > 0 && ghost.len < MAX_SAMPLES);
+        for plane in 0..PLANES {
+            assert!(ghost.planes[plane][..ghost.len] == run.planes[plane][..ghost.len]);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// Low health: below LOW_HEALTH the player's heart beats, and the edges of the
+// view darken with each beat and lighten again before the next, both quicker
+// and stronger the nearer to none health falls
+use crate::audio;
+use crate::raycast::Viewport;
+use crate::render::draw_vignette;
+use crate::{GameMode, State};
+
+const LOW_HEALTH: i32 = 30;
+
+// Frames from one beat to the next at LOW_HEALTH and at none, and from the
+// first thump of a beat to the softer second
+const SLOW_BEAT: u32 = 60;
+const FAST_BEAT: u32 = 24;
+const SECOND_THUMP: u32 = 8;
+
+// Pixels the darkening reaches in at LOW_HEALTH and at none, on a beat
+const MIN_DEPTH: f32 = 6.0;
+const MAX_DEPTH: f32 = 18.0;
+
+impl State {
+    // How near the player is to dying, from 0 at LOW_HEALTH to 1 at none, if
+    // they are below it
+    fn danger(&self) -> Option<f32> {
+        let low = self.mode == GameMode::Playing && self.health < LOW_HEALTH;
+        low.then(|| 1.0 - self.health as f32 / LOW_HEALTH as f32)
+    }
+
+    fn beat_length(danger: f32) -> u32 {
+        SLOW_BEAT - ((SLOW_BEAT - FAST_BEAT) as f32 * danger) as u32
+    }
+
+    // Count through the frames of each beat, thumping at the start of one,
+    // louder the lower health is
+    pub(crate) fn update_heartbeat(&mut self) {
+        let Some(danger) = self.danger() else {
+            self.heartbeat = 0;
+            return;
+        };
+
+        let loudness = 0.5 + danger / 2.0;
+        if self.heartbeat == 0 {
+            self.play_sound(&audio::HEARTBEAT.scaled(loudness));
+        } else if self.heartbeat == SECOND_THUMP {
+            self.play_sound(&audio::HEARTBEAT.scaled(loudness / 2.0));
+        }
+        self.heartbeat = (self.heartbeat + 1) % Self::beat_length(danger);
+    }
+
+    // Pixels the edges are darkened by this frame, fading to half between beats
+    fn vignette_depth(&self) -> i32 {
+        let Some(danger) = self.danger() else { return 0 };
+        let fade = self.heartbeat as f32 / Self::beat_length(danger) as f32;
+        let depth = MIN_DEPTH + (MAX_DEPTH - MIN_DEPTH) * danger;
+        (depth * (1.0 - fade / 2.0)) as i32
+    }
+}
+
+pub unsafe fn render(state: &State, viewport: Viewport) {
+    let depth = state.vignette_depth();
+    if depth > 0 {
+        draw_vignette(viewport, depth);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_heart_beats_faster_and_the_edges_darken_more_as_health_falls() {
+        let mut state = State::new();
+        assert_eq!(state.vignette_depth(), 0);
+
+        // Frames in one beat, and how far the edges darken on it
+        let beat = |state: &mut State, health: i32| {
+            state.health = health;
+            state.heartbeat = 0;
+            state.update_heartbeat();
+            let depth = state.vignette_depth();
+
+            let mut frames = 1;
+            while state.heartbeat != 0 {
+                state.update_heartbeat();
+                frames += 1;
+            }
+            (frames, depth)
+        };
+
+        let (slow, faint) = beat(&mut state, LOW_HEALTH - 1);
+        let (fast, dark) = beat(&mut state, 1);
+        assert!(fast < slow && dark > faint);
+        assert!(fast >= FAST_BEAT && slow <= SLOW_BEAT);
+    }
+}
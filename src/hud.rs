@@ -0,0 +1,79 @@
+// Status bar along the bottom of the screen
+use crate::pickups::Key;
+use crate::platform::{blit, rect, text, DRAW_COLORS, SCREEN_SIZE};
+use crate::weapon::Ammo;
+use crate::State;
+
+// Rows taken by the bar
+pub const HEIGHT: i32 = 20;
+
+// blit flags
+pub(crate) const BLIT_1BPP: u32 = 0;
+const BLIT_2BPP: u32 = 1;
+
+// 8x8 icons in the WASM-4 1BPP format
+pub(crate) const HEART: [u8; 8] = [0x66, 0xff, 0xff, 0xff, 0x7e, 0x3c, 0x18, 0x00];
+const BULLET: [u8; 8] = [0x18, 0x3c, 0x3c, 0x3c, 0x3c, 0x00, 0x3c, 0x3c];
+const ROCKET: [u8; 8] = [0x18, 0x3c, 0x3c, 0x3c, 0x3c, 0x7e, 0xff, 0x99];
+
+// 8x8 2BPP key: 1 is the outline, 2 is filled with the key's shade
+const KEY: [u8; 16] = [
+    0x00, 0x00, 0x15, 0x00, 0x6a, 0x55, 0x62, 0xa9,
+    0x6a, 0x65, 0x15, 0x11, 0x00, 0x00, 0x00, 0x00,
+];
+
+pub unsafe fn render(state: &State) {
+    let top = SCREEN_SIZE - HEIGHT;
+    // Baseline for the 8 pixel tall icons and text
+    let row = top + (HEIGHT - 8) / 2;
+    let mut buffer = [0; 3];
+
+    *DRAW_COLORS = 0x4;
+    rect(0, top, SCREEN_SIZE as u32, HEIGHT as u32);
+
+    *DRAW_COLORS = 0x10;
+    blit(HEART.as_ptr(), 2, row, 8, 8, BLIT_1BPP);
+    // Ammo for the weapon in hand, if it takes any
+    let ammo = state.ammo_for(state.weapon);
+    let icon = if state.weapon.spec().ammo == Ammo::Rockets { &ROCKET } else { &BULLET };
+    if ammo.is_some() {
+        blit(icon.as_ptr(), 42, row, 8, 8, BLIT_1BPP);
+    }
+
+    *DRAW_COLORS = 0x1;
+    text(digits(state.health.max(0) as u32, &mut buffer), 12, row);
+    if let Some(ammo) = ammo {
+        text(digits(ammo, &mut buffer), 52, row);
+    }
+    // The wave under way in survival, instead of the level
+    let (label, number) = match &state.survival {
+        Some(survival) => (b"W", survival.wave),
+        None => (b"L", state.level as u32 + 1),
+    };
+    text(label, 126, row);
+    text(digits(number, &mut buffer), 134, row);
+
+    let held = Key::ALL.iter().filter(|key| state.keys & key.bit() != 0);
+    for (idx, key) in held.enumerate() {
+        *DRAW_COLORS = (key.shade() as u16 + 1) << 8 | 0x10;
+        blit(KEY.as_ptr(), 80 + idx as i32 * 10, row, 8, 8, BLIT_2BPP);
+    }
+}
+
+// Write a number of up to three decimal digits, returning the used tail
+pub(crate) fn digits(value: u32, buffer: &mut [u8; 3]) -> &[u8] {
+    let mut value = value.min(999);
+    let mut start = buffer.len();
+
+    loop {
+        start -= 1;
+        buffer[start] = b'0' + (value % 10) as u8;
+        value /= 10;
+
+        if value == 0 {
+            break;
+        }
+    }
+
+    &buffer[start..]
+}
@@ -0,0 +1,124 @@
+// Arcs at the edges of the view pointing out where the last hit came from:
+// the left or right edge for an attacker off to that side, and the bottom
+// for one behind. The bearing is taken afresh each frame, so the arc moves
+// across as the player turns to face them, and goes once they are in sight
+use core::f32::consts::{FRAC_PI_4, PI};
+
+use crate::math::{abs, atan2};
+use crate::platform::{rect, DRAW_COLORS};
+use crate::player::normalize_angle;
+use crate::raycast::Viewport;
+use crate::State;
+
+// Frames an arc stays up
+const INDICATOR_TIME: u32 = 40;
+
+// Pixels the middle of an arc reaches in from the edge
+const ARC_DEPTH: i32 = 5;
+
+// Attackers less than this far either side of the way the player faces are
+// in front, and more than this far are behind
+const AHEAD: f32 = FRAC_PI_4;
+const BEHIND: f32 = PI * 0.75;
+
+// Where the last hit came from, and the frames left to show it
+#[derive(Clone, Copy)]
+pub struct Indicator {
+    x: f32,
+    y: f32,
+    frames: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Side {
+    Left,
+    Right,
+    Behind,
+}
+
+impl State {
+    // Point the indicator at whoever dealt a hit from (x, y)
+    pub(crate) fn indicate_hit(&mut self, x: f32, y: f32) {
+        self.indicator = Some(Indicator { x, y, frames: INDICATOR_TIME });
+    }
+
+    pub(crate) fn update_indicator(&mut self) {
+        if let Some(indicator) = &mut self.indicator {
+            indicator.frames -= 1;
+            if indicator.frames == 0 {
+                self.indicator = None;
+            }
+        }
+    }
+
+    fn hit_side(&self) -> Option<Side> {
+        let indicator = self.indicator?;
+        let bearing = atan2(self.player_y - indicator.y, indicator.x - self.player_x);
+        let turn = normalize_angle(bearing - self.player_angle + PI) - PI;
+
+        if abs(turn) > BEHIND {
+            Some(Side::Behind)
+        } else if turn > AHEAD {
+            Some(Side::Left)
+        } else if turn < -AHEAD {
+            Some(Side::Right)
+        } else {
+            None
+        }
+    }
+}
+
+// An arc bulging in from the middle of the edge on the side of the hit, half
+// as long as the view is high
+pub unsafe fn render(state: &State, viewport: Viewport) {
+    let Some(side) = state.hit_side() else { return };
+    let half = viewport.rows / 4;
+    let right = viewport.left + viewport.columns as i32;
+    let (middle_x, middle_y) = ((viewport.left + right) / 2, viewport.top + half * 2);
+
+    // Light with a dark rim, to stand out against walls and floor alike
+    for (color, rim) in [(0x4, 0), (0x1, 1)] {
+        *DRAW_COLORS = color;
+        for step in -half..=half {
+            let depth = (ARC_DEPTH - ARC_DEPTH * step * step / (half * half) - rim).max(0);
+            match side {
+                Side::Left => rect(viewport.left, middle_y + step, depth as u32, 1),
+                Side::Right => rect(right - depth, middle_y + step, depth as u32, 1),
+                Side::Behind => rect(middle_x + step, viewport.bottom() - depth, 1, depth as u32),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hits_show_on_the_side_they_came_from() {
+        let mut state = State::new();
+        state.teleport(5.5, 6.5, 0.0);
+        assert_eq!(state.hit_side(), None);
+
+        // Facing east, north is on the left
+        for ((x, y), side) in [
+            ((5.5, 3.5), Some(Side::Left)),
+            ((5.5, 9.5), Some(Side::Right)),
+            ((2.5, 6.5), Some(Side::Behind)),
+            ((8.5, 6.5), None),
+        ] {
+            state.indicate_hit(x, y);
+            assert_eq!(state.hit_side(), side);
+        }
+
+        // Turning to face the attacker puts them in front
+        state.indicate_hit(5.5, 3.5);
+        state.teleport(5.5, 6.5, PI / 2.0);
+        assert_eq!(state.hit_side(), None);
+
+        for _ in 0..INDICATOR_TIME {
+            state.update_indicator();
+        }
+        assert!(state.indicator.is_none());
+    }
+}
@@ -0,0 +1,243 @@
+// Gamepad and mouse state with a frame of history
+use crate::platform::{
+    BUTTON_1, BUTTON_2, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP, GAMEPAD1, MOUSE_BUTTONS,
+    MOUSE_LEFT, MOUSE_RIGHT, MOUSE_X, SCREEN_SIZE,
+};
+
+// Frames a press is remembered for when it can't be acted on right away
+const BUFFER_FRAMES: u8 = 8;
+
+// Most frames apart two presses can be and still count as pressed together
+const CHORD_FRAMES: u8 = 4;
+
+// Most frames between two taps for them to count as a double tap
+const DOUBLE_TAP_FRAMES: u8 = 12;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Tap {
+    None,
+    Single,
+    Double,
+}
+
+#[derive(Clone, Copy)]
+pub struct Button {
+    held: bool,
+    was_held: bool,
+    // Set once another button goes down while this one is held
+    chorded: bool,
+    // Frames left before an unused press is dropped
+    buffer: u8,
+    // Frames since the button last went down
+    since_press: u8,
+    // Frames left for a second tap to follow the first, and what the taps
+    // added up to this frame
+    tap_window: u8,
+    tap: Tap,
+}
+
+impl Button {
+    const fn new() -> Button {
+        Button {
+            held: false,
+            was_held: false,
+            chorded: false,
+            buffer: 0,
+            since_press: u8::MAX,
+            tap_window: 0,
+            tap: Tap::None,
+        }
+    }
+
+    fn update(&mut self, held: bool, others_held: bool) {
+        self.was_held = self.held;
+        self.held = held;
+
+        if self.pressed() {
+            self.chorded = false;
+        }
+        self.chorded |= held && others_held;
+
+        self.buffer = if self.pressed() { BUFFER_FRAMES } else { self.buffer.saturating_sub(1) };
+        self.since_press = if self.pressed() { 0 } else { self.since_press.saturating_add(1) };
+        self.update_taps();
+    }
+
+    // A tap only counts as single once the window for a second one closes,
+    // unless the button is down again by then for something else
+    fn update_taps(&mut self) {
+        self.tap = Tap::None;
+
+        if self.tapped() {
+            if self.tap_window > 0 {
+                self.tap_window = 0;
+                self.tap = Tap::Double;
+            } else {
+                self.tap_window = DOUBLE_TAP_FRAMES;
+            }
+        } else if self.tap_window > 0 {
+            self.tap_window -= 1;
+            if self.tap_window == 0 && !self.held {
+                self.tap = Tap::Single;
+            }
+        }
+    }
+
+    pub fn held(&self) -> bool {
+        self.held
+    }
+
+    // True only on the frame the button goes down
+    pub fn pressed(&self) -> bool {
+        self.held && !self.was_held
+    }
+
+    // True on the frame the button comes back up after being pressed on its own
+    pub fn tapped(&self) -> bool {
+        !self.held && self.was_held && !self.chorded
+    }
+
+    // True on the frame a tap turns out not to be the start of a double tap
+    pub fn single_tapped(&self) -> bool {
+        self.tap == Tap::Single
+    }
+
+    // True on the frame the second of two quick taps comes up
+    pub fn double_tapped(&self) -> bool {
+        self.tap == Tap::Double
+    }
+
+    // Held since going down no more than `frames` ago
+    fn pressed_within(&self, frames: u8) -> bool {
+        self.held && self.since_press <= frames
+    }
+
+    // True for a few frames after a press, until it is taken
+    pub fn buffered(&self) -> bool {
+        self.buffer > 0
+    }
+
+    // Drop any press waiting, and keep the next release from being a tap
+    fn swallow(&mut self) {
+        (self.buffer, self.tap_window, self.tap) = (0, 0, Tap::None);
+        self.chorded = true;
+    }
+
+    // Use up a recent press; returns false if there is none waiting
+    pub fn take(&mut self) -> bool {
+        let buffered = self.buffer > 0;
+        self.buffer = 0;
+        buffered
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Input {
+    pub up: Button,
+    pub down: Button,
+    pub left: Button,
+    pub right: Button,
+    // BUTTON_1, or the left mouse button with mouse look on
+    pub action: Button,
+    pub button_2: Button,
+    // The right mouse button with mouse look on
+    pub zoom: Button,
+    // Horizontal mouse movement since the last frame
+    pub mouse_dx: i16,
+    mouse_x: i16,
+}
+
+impl Input {
+    pub const fn new() -> Input {
+        Input {
+            up: Button::new(),
+            down: Button::new(),
+            left: Button::new(),
+            right: Button::new(),
+            action: Button::new(),
+            button_2: Button::new(),
+            zoom: Button::new(),
+            mouse_dx: 0,
+            mouse_x: -1,
+        }
+    }
+
+    // True on the frame BUTTON_1 and BUTTON_2 end up pressed together, a
+    // moment apart at most, as opposed to one held while the other is pressed
+    pub fn both_buttons(&self) -> bool {
+        let (action, button_2) = (&self.action, &self.button_2);
+
+        (action.pressed() || button_2.pressed())
+            && action.pressed_within(CHORD_FRAMES)
+            && button_2.pressed_within(CHORD_FRAMES)
+    }
+
+    // Read this frame's hardware state, once at the start of each frame
+    pub unsafe fn poll(&mut self, mouse_look: bool) {
+        let mouse = if mouse_look { *MOUSE_BUTTONS } else { 0 };
+        self.update_buttons(*GAMEPAD1, mouse);
+
+        // Only count movement while the pointer stays over the screen
+        let mouse_x = *MOUSE_X;
+        let on_screen = |x: i16| (0..SCREEN_SIZE as i16).contains(&x);
+        self.mouse_dx = if mouse_look && on_screen(mouse_x) && on_screen(self.mouse_x) {
+            mouse_x - self.mouse_x
+        } else {
+            0
+        };
+        self.mouse_x = mouse_x;
+    }
+
+    // Take this frame's buttons from a gamepad byte instead, with the mouse
+    // left alone
+    pub fn replay(&mut self, gamepad: u8) {
+        self.update_buttons(gamepad, 0);
+        self.mouse_dx = 0;
+    }
+
+    // The buttons held this frame as a gamepad byte, with the left mouse
+    // button counting as BUTTON_1
+    pub fn gamepad(&self) -> u8 {
+        let buttons = [
+            (&self.up, BUTTON_UP),
+            (&self.down, BUTTON_DOWN),
+            (&self.left, BUTTON_LEFT),
+            (&self.right, BUTTON_RIGHT),
+            (&self.action, BUTTON_1),
+            (&self.button_2, BUTTON_2),
+        ];
+        buttons.iter().filter(|(button, _)| button.held).fold(0, |byte, (_, bit)| byte | bit)
+    }
+
+    // Forget the presses made so far, so buttons still down only count again
+    // once let go and pressed anew
+    pub fn swallow(&mut self) {
+        let buttons = [
+            &mut self.up,
+            &mut self.down,
+            &mut self.left,
+            &mut self.right,
+            &mut self.action,
+            &mut self.button_2,
+            &mut self.zoom,
+        ];
+        buttons.into_iter().for_each(Button::swallow);
+    }
+
+    fn update_buttons(&mut self, gamepad: u8, mouse: u8) {
+        let buttons = [
+            (&mut self.up, gamepad & BUTTON_UP != 0),
+            (&mut self.down, gamepad & BUTTON_DOWN != 0),
+            (&mut self.left, gamepad & BUTTON_LEFT != 0),
+            (&mut self.right, gamepad & BUTTON_RIGHT != 0),
+            (&mut self.action, gamepad & BUTTON_1 != 0 || mouse & MOUSE_LEFT != 0),
+            (&mut self.button_2, gamepad & BUTTON_2 != 0),
+            (&mut self.zoom, mouse & MOUSE_RIGHT != 0),
+        ];
+        let held_count = buttons.iter().filter(|(_, held)| *held).count();
+
+        for (button, held) in buttons {
+            button.update(held, held_count > held as usize);
+        }
+    }
+}
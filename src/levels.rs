@@ -0,0 +1,146 @@
+// Campaign levels, played in order
+use core::f32::consts::PI;
+
+use crate::boss::Arena;
+use crate::enemies::Spawn;
+use crate::lighting;
+use crate::map::Layout;
+use crate::monitor;
+use crate::music::{self, Track};
+use crate::pickups::{Item, Key, Pickup};
+use crate::raycast::Camera;
+use crate::sprites::{self, Sprite};
+use crate::weather::{Kind, Weather};
+
+// The maps themselves are text art in maps/, compiled by build.rs, which has
+// the key to the characters
+include!(concat!(env!("OUT_DIR"), "/maps.rs"));
+
+pub struct Level {
+    pub map: Layout,
+    pub spawn_x: f32,
+    pub spawn_y: f32,
+    pub spawn_angle: f32,
+    pub decorations: &'static [Sprite],
+    pub enemies: &'static [Spawn],
+    pub pickups: &'static [Pickup],
+    pub music: &'static Track,
+    // Where the level's boss waits, if it has one
+    pub arena: Option<Arena>,
+    // The security cameras its monitors show, none if it has no monitors
+    pub cameras: &'static [Camera],
+    // Light everywhere before any light sources, up to lighting::BRIGHT
+    pub ambient: u8,
+    // What falls from the sky over its outdoor tiles, if anything
+    pub weather: Option<Weather>,
+}
+
+pub const LEVELS: [Level; 4] = [
+    Level {
+        map: level1::LAYOUT,
+        spawn_x: level1::SPAWN.0,
+        spawn_y: level1::SPAWN.1,
+        spawn_angle: 0.0,
+        decorations: &[
+            Sprite { x: 3.5, y: 1.5, image: &sprites::BARREL },
+            Sprite { x: 1.5, y: 5.5, image: &sprites::LAMP },
+            Sprite { x: 10.5, y: 6.5, image: &sprites::BARREL },
+        ],
+        enemies: level1::ENEMIES,
+        pickups: &[
+            Pickup { x: 5.5, y: 6.5, item: Item::Health },
+            Pickup { x: 8.5, y: 8.5, item: Item::Ammo },
+            Pickup { x: 22.5, y: 1.5, item: Item::Key(Key::Silver) },
+            Pickup { x: 16.5, y: 14.5, item: Item::Health },
+            Pickup { x: 9.5, y: 6.5, item: Item::Rockets },
+        ],
+        music: &music::MARCH,
+        arena: level1::ARENA,
+        cameras: &[],
+        ambient: lighting::BRIGHT,
+        weather: None,
+    },
+    Level {
+        map: level2::LAYOUT,
+        spawn_x: level2::SPAWN.0,
+        spawn_y: level2::SPAWN.1,
+        spawn_angle: 0.0,
+        decorations: &[
+            Sprite { x: 3.5, y: 13.5, image: &sprites::LAMP },
+            Sprite { x: 9.5, y: 13.5, image: &sprites::LAMP },
+            Sprite { x: 3.5, y: 17.5, image: &sprites::LAMP },
+            Sprite { x: 9.5, y: 17.5, image: &sprites::LAMP },
+            Sprite { x: 14.5, y: 1.5, image: &sprites::BARREL },
+        ],
+        enemies: level2::ENEMIES,
+        pickups: &[
+            Pickup { x: 14.5, y: 1.5, item: Item::Ammo },
+            Pickup { x: 12.5, y: 3.5, item: Item::Health },
+            Pickup { x: 3.5, y: 9.5, item: Item::Key(Key::Brass) },
+            Pickup { x: 1.5, y: 17.5, item: Item::Ammo },
+            Pickup { x: 6.5, y: 7.5, item: Item::Rockets },
+            Pickup { x: 13.5, y: 13.5, item: Item::RapidGun },
+        ],
+        music: &music::CRYPT,
+        arena: level2::ARENA,
+        // Down the corridor past the acid, and across the hall by the exit
+        cameras: &[
+            Camera { x: 3.3, y: 5.5, angle: 0.0, fov: monitor::FOV },
+            Camera { x: 14.6, y: 18.6, angle: PI * 0.75, fov: monitor::FOV },
+        ],
+        ambient: 1,
+        weather: Some(Weather { kind: Kind::Rain, density: 40 }),
+    },
+    Level {
+        map: level3::LAYOUT,
+        spawn_x: level3::SPAWN.0,
+        spawn_y: level3::SPAWN.1,
+        spawn_angle: 0.0,
+        decorations: &[
+            Sprite { x: 5.5, y: 3.5, image: &sprites::BARREL },
+            Sprite { x: 12.5, y: 9.5, image: &sprites::LAMP },
+            Sprite { x: 18.5, y: 9.5, image: &sprites::BARREL },
+        ],
+        enemies: level3::ENEMIES,
+        pickups: &[
+            Pickup { x: 1.5, y: 5.5, item: Item::Health },
+            Pickup { x: 7.5, y: 7.5, item: Item::Ammo },
+            Pickup { x: 18.5, y: 1.5, item: Item::Key(Key::Iron) },
+            Pickup { x: 12.5, y: 10.5, item: Item::Ammo },
+            Pickup { x: 8.5, y: 6.5, item: Item::Rockets },
+            Pickup { x: 3.5, y: 3.5, item: Item::RapidGun },
+            // Into the dark, and out of it by the exit
+            Pickup { x: 1.5, y: 3.5, item: Item::Flashlight },
+            Pickup { x: 14.5, y: 7.5, item: Item::Flashlight },
+        ],
+        music: &music::RUSH,
+        arena: level3::ARENA,
+        cameras: &[],
+        ambient: lighting::BRIGHT,
+        weather: None,
+    },
+    Level {
+        map: level4::LAYOUT,
+        spawn_x: level4::SPAWN.0,
+        spawn_y: level4::SPAWN.1,
+        spawn_angle: 0.0,
+        decorations: &[
+            Sprite { x: 9.5, y: 2.5, image: &sprites::LAMP },
+            Sprite { x: 15.5, y: 2.5, image: &sprites::LAMP },
+            Sprite { x: 9.5, y: 6.5, image: &sprites::LAMP },
+            Sprite { x: 15.5, y: 6.5, image: &sprites::LAMP },
+        ],
+        enemies: level4::ENEMIES,
+        pickups: &[
+            Pickup { x: 4.5, y: 1.5, item: Item::Ammo },
+            Pickup { x: 1.5, y: 8.5, item: Item::Health },
+            Pickup { x: 4.5, y: 8.5, item: Item::Rockets },
+            Pickup { x: 10.5, y: 8.5, item: Item::Health },
+        ],
+        music: &music::RUSH,
+        arena: level4::ARENA,
+        cameras: &[],
+        ambient: lighting::BRIGHT,
+        weather: Some(Weather { kind: Kind::Snow, density: 30 }),
+    },
+];
@@ -1,219 +1,1039 @@
-// Don't link the Rust standard library
-#![no_std]
+// Don't link the Rust standard library into the cartridge; host builds keep
+// it so the engine can be unit-tested with `cargo test`
+#![cfg_attr(target_arch = "wasm32", no_std)]
+// Without the cartridge's own entry point only the engine API is used, and
+// the rest of the game goes unreferenced
+#![cfg_attr(not(feature = "cart"), allow(dead_code))]
 
-use core::{arch::wasm32, panic::PanicInfo};
-use core::f32::consts::{PI, FRAC_PI_2};
-use libm::{cosf, sinf, ceilf, fabsf, floorf, sqrtf, tanf};
+mod audio;
+mod automap;
+mod boss;
+#[cfg(feature = "cart")]
+mod cart;
+mod compass;
+pub mod config;
+mod coop;
+mod crosshair;
+mod deathmatch;
+mod decals;
+mod enemies;
+pub mod fixed;
+mod flashlight;
+mod ghost;
+mod heartbeat;
+mod hud;
+mod indicator;
+mod input;
+mod levels;
+mod lighting;
+mod lobby;
+pub mod map;
+pub mod mapgen;
+mod math;
+mod minimap;
+mod mirror;
+mod monitor;
+mod music;
+mod options;
+mod particles;
+mod pathfind;
+mod pause;
+mod pickups;
+mod platform;
+mod player;
+mod portals;
+mod projectiles;
+mod pushwalls;
+pub mod raycast;
+pub mod render;
+mod replay;
+pub mod rle;
+pub mod rng;
+mod save;
+mod shake;
+mod spectator;
+mod sprites;
+mod survival;
+mod texture;
+mod weapon;
+mod weather;
 
-// Import WASM functions
-extern "C" {
-    fn vline(x: i32, y: i32, len: u32);
+use automap::Explored;
+use config::Config;
+use coop::Partner;
+use deathmatch::Deathmatch;
+use decals::{Decal, MAX_DECALS};
+use enemies::{Attack, Enemy, Mode, Noise, MAX_CORPSES, MAX_ENEMIES};
+use ghost::Recording;
+use indicator::Indicator;
+use input::Input;
+use levels::{Level, LEVELS};
+use lighting::Lighting;
+use map::{
+    circle_overlaps_tile, door_lock, is_door, Map, CRACKED_WALL, EMPTY, EXIT, HAZARD, LAVA,
+    MONITOR, SECRET_WALL,
+};
+use math::{abs, cos, floor, sin};
+use music::Music;
+use options::Settings;
+use pickups::{Item, Pickup, MAX_PICKUPS};
+use particles::{Particle, MAX_PARTICLES};
+use platform::{GAMEPAD1, GAMEPAD2, GAMEPAD3, GAMEPAD4, NETPLAY, NETPLAY_ACTIVE};
+use projectiles::{Kind, Projectile, MAX_PROJECTILES};
+use pushwalls::{PushWall, MAX_PUSH_WALLS};
+use replay::Replay;
+use rng::Rng;
+use save::{Leaderboard, Record, Save, MAX_LEVELS, RANKS};
+use spectator::Spectator;
+use sprites::{Animated, Sprite, MAX_SPRITES};
+use survival::Survival;
+use texture::Textures;
+use weapon::{Ammo, Spec, Weapon};
+
+// Doors
+const MAX_DOORS: usize = 4;
+const DOOR_SPEED: f32 = 1.0 / 32.0;
+const DOOR_OPEN_TIME: u32 = 180;
+const USE_DISTANCE: f32 = 0.8;
+
+// Frames between a chasing enemy's footsteps
+const ENEMY_STEP_FRAMES: u32 = 20;
+
+// Frames the level name is shown for before play starts, the last WIPE_TIME
+// of them revealing the level column by column
+const TRANSITION_TIME: u32 = 90;
+const WIPE_TIME: u32 = 24;
+// Frames a fade to or from black takes
+const FADE_TIME: u32 = 30;
+
+// What the screen shows while the transition timer runs down; the game is
+// frozen until it is over
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Transition {
+    // The level's name, then a wipe into it
+    Level,
+    // The frame the player died on, fading to black
+    FadeOut,
+    // The view fading in from black after a teleport
+    FadeIn,
+}
+
+impl Transition {
+    const fn frames(self) -> u32 {
+        match self {
+            Transition::Level => TRANSITION_TIME,
+            Transition::FadeOut | Transition::FadeIn => FADE_TIME,
+        }
+    }
+}
+
+// Player health
+const MAX_HEALTH: i32 = 100;
+const HAZARD_DAMAGE: i32 = 5;
+const LAVA_DAMAGE: i32 = 10;
+// Frames between hazard tile damage ticks
+const HAZARD_INTERVAL: u32 = 30;
+// Frames the screen border flashes after taking damage
+const DAMAGE_FLASH_TIME: u32 = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GameMode {
+    // Showing the title screen until BUTTON_1 starts the game
+    Title,
+    Playing,
+    // Showing the game over screen until BUTTON_1 is pressed
+    Dead,
+    // Waiting for BUTTON_1 to be released before play resumes
+    Restarting,
+    // Showing how the level went until BUTTON_1 moves on to the next one
+    LevelComplete,
+    // Frozen under the pause menu
+    Paused,
+    // On the options screen until BUTTON_1 or BUTTON_2 goes back to the
+    // pause menu
+    Options,
+    // Picking the rules for a deathmatch until START begins it
+    Lobby,
+    // Flying the spectator camera until BUTTON_1 lands it
+    Spectating,
+    // Watching the security cameras on a monitor until BUTTON_1 or BUTTON_2
+    // looks away
+    Watching,
+}
+
+// How the player is doing on the current level
+#[derive(Clone, Copy)]
+struct LevelStats {
+    frames: u32,
+    kills: u32,
+    enemies: u32,
+    secrets: u32,
+    secret_walls: u32,
+}
+
+impl LevelStats {
+    const fn new(level: &Level, map: &Map) -> Self {
+        let secret_walls = map.count(SECRET_WALL);
+
+        // Only as many enemies as there are slots ever spawn
+        let enemies =
+            if level.enemies.len() < MAX_ENEMIES { level.enemies.len() } else { MAX_ENEMIES };
+
+        LevelStats { frames: 0, kills: 0, enemies: enemies as u32, secrets: 0, secret_walls }
+    }
 }
 
-// Pointer to keyboard state
-const DRAW_COLORS: *mut u16 = 0x14 as *mut u16;
-const GAMEPAD1: *const u8 = 0x16 as *const u8;
-
-// Binary masks for GAMEPAD
-const BUTTON_LEFT: u8 = 16;   // 0b00010000
-const BUTTON_RIGHT: u8 = 32;  // 0b00100000
-const BUTTON_UP: u8 = 64;     // 0b01000000
-const BUTTON_DOWN: u8 = 128;  // 0b10000000
-
-// Map walls
-const MAP: [u16; 8] = [
-    0b1111111111111111,
-    0b1000001010000101,
-    0b1011100000110101,
-    0b1000111010010001,
-    0b1010001011110111,
-    0b1011101001100001,
-    0b1000100000001101,
-    0b1111111111111111,
-];
-
-// Views
-const FOV: f32 = PI / 2.7;
-const HALF_FOV: f32 = FOV * 0.5;
-const ANGLE_STEP: f32 = FOV / 160.0;
-const WALL_HEIGHT: f32 = 100.0;
-const STEP_SIZE: f32 = 0.045;
-
-fn distance(a: f32, b: f32) -> f32 {
-    sqrtf((a * a) + (b * b))
+// A door that is opening, open or closing; closed doors have no state
+#[derive(Clone, Copy)]
+struct Door {
+    tile_x: i32,
+    tile_y: i32,
+    open: f32,
+    opening: bool,
+    timer: u32,
 }
 
 // Game State
-struct State {
+pub struct State {
     player_x: f32,
     player_y: f32,
     player_angle: f32,
+    velocity_x: f32,
+    velocity_y: f32,
+    turn_speed: f32,
+    // Horizon offset in pixels, positive when looking up
+    pitch: i32,
+    doors: [Option<Door>; MAX_DOORS],
+    push_walls: [Option<PushWall>; MAX_PUSH_WALLS],
+    enemies: [Option<Enemy>; MAX_ENEMIES],
+    pickups: [Option<Pickup>; MAX_PICKUPS],
+    projectiles: [Option<Projectile>; MAX_PROJECTILES],
+    particles: [Option<Particle>; MAX_PARTICLES],
+    decals: [Option<Decal>; MAX_DECALS],
+    corpses: [Option<Animated>; MAX_CORPSES],
+    // The level's boss arena has shut with the player inside
+    arena_locked: bool,
+    // The loudest noise made this frame, for enemies to hear on their next
+    // update
+    noise: Option<Noise>,
+    // The weapon in hand and the flags of those carried
+    weapon: Weapon,
+    weapons: u8,
+    // Frames until the weapon can fire again
+    weapon_cooldown: u32,
+    // Frames left of the weapon going down and back up to load
+    reload: u32,
+    // Frames left of the hit marker after a shot lands
+    hit_marker: u32,
+    rockets: u32,
+    ammo: u32,
+    // Key::bit flags for the keys picked up on this level
+    keys: u8,
+    health: i32,
+    mode: GameMode,
+    damage_flash: u32,
+    indicator: Option<Indicator>,
+    // Screen shake, from 0 to 1
+    trauma: f32,
+    // Frames into the current beat of the heart at low health
+    heartbeat: u32,
+    hazard_timer: u32,
+    level: usize,
+    map: Map,
+    textures: Textures,
+    stats: LevelStats,
+    // The best runs on each level, and where the last one finished placed
+    leaderboards: [Leaderboard; MAX_LEVELS],
+    rank: Option<usize>,
+    transition: u32,
+    effect: Transition,
+    music: Music,
+    show_minimap: bool,
+    // The full-screen map instead of the view, and the tiles it shows
+    show_automap: bool,
+    explored: Explored,
+    // Darkness mode, with only the flashlight to see by
+    lights_out: bool,
+    lighting: Lighting,
+    // Walk cycle position and how strongly it currently shows
+    bob_phase: f32,
+    bob_weight: f32,
+    // Turn with the mouse and fire with its left button
+    mouse_look: bool,
+    // How far into the zoomed-in field of view, from 0 to 1
+    zoom: f32,
+    settings: Settings,
+    // Rows picked on the pause menu and the options screen
+    pause_item: usize,
+    option: usize,
+    input: Input,
+    rng: Rng,
+    config: Config,
+    // The survival run under way instead of the campaign, and the furthest
+    // wave any run has reached
+    survival: Option<Survival>,
+    best_wave: u8,
+    // This run of the level so far, and the best run to race on its level,
+    // left empty until there is one
+    recording: Recording,
+    ghost: Recording,
+    replay: Replay,
+    // The progress to go back to once the demo under the title is over
+    demo: Option<Save>,
+    // The second player in co-op, once one has joined
+    partner: Option<Partner>,
+    // The deathmatch under way instead of the campaign
+    deathmatch: Option<Deathmatch>,
+    // Where the player left off while the spectator camera is out
+    spectator: Option<Spectator>,
+    // The level's security camera on the monitor
+    feed: usize,
 }
 
 impl State {
-    // Move the character
-    pub fn update(&mut self, up: bool, down: bool, left: bool, right: bool) {
-        // Store current position just in case
-        let previous_position = (self.player_x, self.player_y);
+    // A new game at the start of the first level
+    pub const fn new() -> Self {
+        Self::with_config(Config::new())
+    }
+
+    pub const fn with_config(config: Config) -> Self {
+        let map = Map::load(&LEVELS[0].map);
+
+        State {
+            player_x: 1.5,
+            player_y: 1.5,
+            player_angle: 0.0,
+            velocity_x: 0.0,
+            velocity_y: 0.0,
+            turn_speed: config.turn_speed,
+            pitch: 0,
+            doors: [None; MAX_DOORS],
+            push_walls: [None; MAX_PUSH_WALLS],
+            enemies: enemies::spawn(LEVELS[0].enemies),
+            pickups: pickups::spawn(LEVELS[0].pickups),
+            projectiles: [None; MAX_PROJECTILES],
+            particles: [None; MAX_PARTICLES],
+            decals: [None; MAX_DECALS],
+            corpses: [None; MAX_CORPSES],
+            arena_locked: false,
+            noise: None,
+            weapon: Weapon::Pistol,
+            weapons: weapon::START_WEAPONS,
+            weapon_cooldown: 0,
+            reload: 0,
+            hit_marker: 0,
+            rockets: 0,
+            ammo: weapon::START_AMMO,
+            keys: 0,
+            health: MAX_HEALTH,
+            mode: GameMode::Playing,
+            damage_flash: 0,
+            indicator: None,
+            trauma: 0.0,
+            heartbeat: 0,
+            hazard_timer: 0,
+            level: 0,
+            map,
+            textures: Textures::unpack(),
+            stats: LevelStats::new(&LEVELS[0], &map),
+            leaderboards: [[Record::EMPTY; RANKS]; MAX_LEVELS],
+            rank: None,
+            transition: TRANSITION_TIME,
+            effect: Transition::Level,
+            music: Music::new(),
+            show_minimap: false,
+            show_automap: false,
+            explored: Explored::new(),
+            lights_out: false,
+            lighting: Lighting::new(),
+            bob_phase: 0.0,
+            bob_weight: 0.0,
+            mouse_look: true,
+            zoom: 0.0,
+            settings: Settings::new(),
+            pause_item: 0,
+            option: 0,
+            input: Input::new(),
+            rng: Rng::new(0),
+            config,
+            survival: None,
+            best_wave: 0,
+            recording: Recording::new(0),
+            ghost: Recording::new(0),
+            replay: Replay::new(),
+            demo: None,
+            partner: None,
+            deathmatch: None,
+            spectator: None,
+            feed: 0,
+        }
+    }
+
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    // The live map, for gameplay systems and editors that change the level;
+    // changes last until the next level loads
+    pub fn map_mut(&mut self) -> &mut Map {
+        &mut self.map
+    }
+
+    // Shared randomness for anything built on the engine
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    // Swap in new settings, taking effect from the next frame
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+    }
+
+    // Player position and view angle
+    pub fn player(&self) -> (f32, f32, f32) {
+        (self.player_x, self.player_y, self.player_angle)
+    }
+
+    // Put the player somewhere else on the current level, standing still
+    pub fn teleport(&mut self, x: f32, y: f32, angle: f32) {
+        (self.player_x, self.player_y) = (x, y);
+        self.player_angle = player::normalize_angle(angle);
+        (self.velocity_x, self.velocity_y) = (0.0, 0.0);
+        self.begin_transition(Transition::FadeIn);
+    }
+
+    fn begin_transition(&mut self, effect: Transition) {
+        self.effect = effect;
+        self.transition = effect.frames();
+    }
 
-        // Move the player
-        if up {
-            self.player_x += cosf(self.player_angle) * STEP_SIZE;
-            self.player_y += -sinf(self.player_angle) * STEP_SIZE;
+    /// Read this frame's gamepad and mouse for the next `update`, or the
+    /// replay's gamepad while one is playing
+    ///
+    /// # Safety
+    ///
+    /// Reads the WASM-4 input registers, so it must run on the console.
+    pub unsafe fn poll_input(&mut self) {
+        match self.replayed_input(*GAMEPAD1) {
+            Some(gamepad) => self.input.replay(gamepad),
+            None => {
+                // Peers only see each other's gamepads, so under netplay the
+                // mouse would steer each peer's game its own way, as the disk
+                // would if start read it
+                let mouse_look = self.mouse_look && *NETPLAY & NETPLAY_ACTIVE == 0;
+                self.input.poll(mouse_look);
+                self.record_input();
+            }
+        }
+        self.poll_partner(*GAMEPAD2);
+        self.poll_deathmatch([*GAMEPAD2, *GAMEPAD3, *GAMEPAD4]);
+    }
+
+    // Start a level from its spawn point, wrapping around after the last one
+    pub fn load_level(&mut self, level: usize) {
+        self.level = level % LEVELS.len();
+
+        let level = &LEVELS[self.level];
+        self.map = Map::load(&level.map);
+        self.player_x = level.spawn_x;
+        self.player_y = level.spawn_y;
+        self.player_angle = player::normalize_angle(level.spawn_angle);
+        (self.velocity_x, self.velocity_y) = (0.0, 0.0);
+        self.pitch = 0;
+        self.doors = [None; MAX_DOORS];
+        self.push_walls = [None; MAX_PUSH_WALLS];
+        self.enemies = enemies::spawn(level.enemies);
+        self.pickups = pickups::spawn(level.pickups);
+        self.projectiles = [None; MAX_PROJECTILES];
+        self.particles = [None; MAX_PARTICLES];
+        self.decals = [None; MAX_DECALS];
+        self.corpses = [None; MAX_CORPSES];
+        self.arena_locked = false;
+        self.noise = None;
+        self.stats = LevelStats::new(level, &self.map);
+        self.recording = Recording::new(self.level);
+        self.keys = 0;
+        self.weapon_cooldown = 0;
+        self.reload = 0;
+        self.hit_marker = 0;
+        self.damage_flash = 0;
+        self.indicator = None;
+        self.trauma = 0.0;
+        self.hazard_timer = 0;
+        self.explored = Explored::new();
+        self.lights_out = false;
+        self.lighting = Lighting::bake(&self.map, level.ambient);
+        self.begin_transition(Transition::Level);
+        self.music = Music::new();
+        self.reset_survival();
+        self.reset_deathmatch();
+        self.restart_recording();
+        self.place_partner();
+    }
+
+    // Start the current level over with full health
+    fn restart(&mut self) {
+        self.load_level(self.level);
+        self.health = MAX_HEALTH;
+        self.ammo = weapon::START_AMMO;
+        self.rockets = 0;
+        self.weapon = Weapon::Pistol;
+        self.weapons = weapon::START_WEAPONS;
+        self.mode = GameMode::Playing;
+    }
+
+    fn hurt(&mut self, damage: i32) {
+        if !matches!(self.mode, GameMode::Playing | GameMode::Watching) {
+            return;
+        }
+
+        self.health -= damage;
+        self.damage_flash = DAMAGE_FLASH_TIME;
+        self.shake_from_damage(damage);
+
+        if self.health <= 0 {
+            self.health = 0;
+            // Deathmatch players are straight back in
+            if self.deathmatch.is_some() {
+                self.respawn();
+                return;
+            }
+            self.mode = GameMode::Dead;
+            self.begin_transition(Transition::FadeOut);
+        }
+    }
+
+    // Advance the game by one frame of input
+    pub fn update(&mut self) {
+        self.move_player();
+        self.explore();
+        self.record_ghost();
+        self.stats.frames += 1;
+
+        // The console has no clock to seed from, so the frames between
+        // presses are the randomness
+        if self.input.action.pressed() {
+            self.rng.mix(self.stats.frames);
+        }
+
+        self.tick_player();
+        self.collect_pickups();
+        self.act();
+        self.update_level();
+    }
+
+    // Everything in the level but the first player going on a frame, also
+    // while they are off flying the spectator camera or watching a monitor
+    fn update_level(&mut self) {
+        self.update_partner();
+        self.update_rivals();
+
+        self.update_doors();
+        self.update_push_walls();
+        self.update_enemies();
+        self.update_projectiles();
+        self.update_particles();
+        self.update_decals();
+        self.update_arena();
+        self.update_survival();
+        self.corpses.iter_mut().flatten().for_each(Animated::tick);
+    }
+
+    // Run down the player's timers, and hurt them on a floor that hurts
+    fn tick_player(&mut self) {
+        self.weapon_cooldown = self.weapon_cooldown.saturating_sub(1);
+        self.reload = self.reload.saturating_sub(1);
+        self.hit_marker = self.hit_marker.saturating_sub(1);
+        self.damage_flash = self.damage_flash.saturating_sub(1);
+        self.update_indicator();
+        self.update_shake();
+        self.update_heartbeat();
+        self.hazard_timer = self.hazard_timer.saturating_sub(1);
+
+        if let Some(damage) = self.floor_damage() {
+            if self.hazard_timer == 0 {
+                self.hurt(damage);
+                self.hazard_timer = HAZARD_INTERVAL;
+            }
         }
-        if down {
-            self.player_x -= cosf(self.player_angle) * STEP_SIZE;
-            self.player_y -= -sinf(self.player_angle) * STEP_SIZE;
+    }
+
+    // The action button uses whatever is in front, and fires otherwise.
+    // Presses stay buffered for a moment, so one made just before the
+    // weapon is ready still fires
+    fn act(&mut self) {
+        if self.input.action.buffered() && (self.use_tile() || self.fire()) {
+            self.input.action.take();
+        } else if self.weapon.spec().automatic && self.input.action.held() {
+            self.fire();
         }
-        if right {
-            self.player_angle -= STEP_SIZE;
+
+        if self.input.button_2.double_tapped() {
+            self.cycle_weapon();
         }
-        if left {
-            self.player_angle += STEP_SIZE;
+    }
+
+    // Damage dealt by the floor tile the player stands on, if it hurts
+    fn floor_damage(&self) -> Option<i32> {
+        let (tile_x, tile_y) = (floor(self.player_x) as i32, floor(self.player_y) as i32);
+
+        match self.map().tile(tile_x, tile_y) {
+            HAZARD => Some(HAZARD_DAMAGE),
+            LAVA => Some(LAVA_DAMAGE),
+            _ => None,
         }
+    }
 
-        // If moving into a wall, undo the move
-        if point_in_wall(self.player_x, self.player_y) {
-            (self.player_x, self.player_y) = previous_position;
+    // Open the door, push the secret wall or flip the exit switch the player
+    // is facing, if any
+    fn use_tile(&mut self) -> bool {
+        let tile_x = floor(self.player_x + cos(self.player_angle) * USE_DISTANCE) as i32;
+        let tile_y = floor(self.player_y - sin(self.player_angle) * USE_DISTANCE) as i32;
+
+        match self.map().tile(tile_x, tile_y) {
+            // Survival and deathmatch have no way out
+            EXIT if self.survival.is_none() && self.deathmatch.is_none() => {
+                self.record_run();
+                self.mode = GameMode::LevelComplete;
+            }
+            SECRET_WALL if !self.pushed(tile_x, tile_y) => self.push_wall(tile_x, tile_y),
+            // A feed would take over both halves of the split screen
+            MONITOR if self.partner.is_none() => return self.watch(),
+            tile if is_door(tile) => {
+                // Locked doors swallow the press until the key turns up, or
+                // until the boss holding them shut is dead
+                let unlocked = door_lock(tile).is_none_or(|key| self.keys & key.bit() != 0);
+                if unlocked && !self.arena_holds(tile_x, tile_y) {
+                    self.open_door(tile_x, tile_y);
+                    self.play_sound_at(&audio::DOOR, tile_x as f32 + 0.5, tile_y as f32 + 0.5);
+                } else {
+                    self.play_sound(&audio::LOCKED);
+                }
+            }
+            _ => return false,
         }
+
+        true
     }
 
-    fn horizontal_intersection(&self, angle: f32) -> f32 {
-        let up = fabsf(floorf(angle / PI) % 2.0) != 0.0;
+    // Fire the weapon in hand; returns false if it isn't ready
+    fn fire(&mut self) -> bool {
+        if self.weapon_cooldown > 0 {
+            return false;
+        }
 
-        let first_y = if up {
-            ceilf(self.player_y) - self.player_y
-        } else {
-            floorf(self.player_y) - self.player_y
+        let weapon = self.weapon;
+        let fired = match weapon {
+            Weapon::Launcher => self.fire_rocket(),
+            _ => self.fire_bullet(weapon.spec()),
         };
-        let first_x = -first_y / tanf(angle);
+        if fired {
+            self.weapon_cooldown = weapon.spec().cooldown;
+            self.make_noise(self.player_x, self.player_y, weapon.spec().noise);
+            self.switch_if_empty();
+        }
+        fired
+    }
 
-        let dy = if up { 1.0 } else { -1.0 };
-        let dx = -dy / tanf(angle);
+    // Let enemies within `radius` hear a noise, keeping only the one heard
+    // furthest off when several go off in a frame
+    fn make_noise(&mut self, x: f32, y: f32, radius: f32) {
+        if radius > 0.0 && self.noise.is_none_or(|noise| radius > noise.radius) {
+            self.noise = Some(Noise { x, y, radius });
+        }
+    }
+
+    // Strike down the view direction, give or take the weapon's spread,
+    // hitting the nearest enemy or deathmatch rival in reach in front of the
+    // wall; returns false when out of ammo
+    fn fire_bullet(&mut self, spec: &Spec) -> bool {
+        if spec.ammo == Ammo::Bullets {
+            if self.ammo == 0 {
+                return false;
+            }
+            self.ammo -= 1;
+        }
 
-        let mut next_x = first_x;
-        let mut next_y = first_y;
+        let mut angle = self.player_angle;
+        if spec.spread > 0.0 {
+            angle += (self.rng.next_f32() * 2.0 - 1.0) * spec.spread;
+        }
 
-        for _ in 0..256 {
-            let current_x = next_x + self.player_x;
-            let current_y = if up {
-                next_y + self.player_y
-            } else {
-                next_y + self.player_y - 1.0
-            };
+        let (dir_x, dir_y) = (cos(angle), -sin(angle));
+        let wall = self.cast_ray(self.player_x, self.player_y, angle);
+        let mut nearest = wall.distance.min(spec.range);
+        let rival = self.rival_in_line(dir_x, dir_y, &mut nearest);
+        let target = self.enemy_in_line(dir_x, dir_y, &mut nearest);
+        if target.is_some() || rival.is_some() {
+            self.hit_marker = crosshair::HIT_MARKER_TIME;
+        }
 
-            if point_in_wall(current_x, current_y) {
-                break;
+        match (target, rival) {
+            (Some(idx), _) => self.damage_enemy(idx, spec.damage),
+            (None, Some(slot)) => self.damage_rival(slot, spec.damage),
+            // With nobody in the way the shot reaches the wall, if it is in
+            // range, and knocks through it if it is cracked or, from a gun,
+            // leaves a hole
+            (None, None) if wall.distance <= spec.range => {
+                if self.map.tile(wall.tile_x, wall.tile_y) == CRACKED_WALL {
+                    self.map.set_tile(wall.tile_x, wall.tile_y, EMPTY);
+                } else if spec.ammo == Ammo::Bullets {
+                    self.mark_wall(&wall);
+                }
             }
+            (None, None) => {}
+        }
 
-            next_x += dx;
-            next_y += dy;
+        true
+    }
+
+    // The nearest enemy a shot from the player along a direction would hit
+    // short of `nearest` tiles away, which then becomes its distance
+    fn enemy_in_line(&self, dir_x: f32, dir_y: f32, nearest: &mut f32) -> Option<usize> {
+        let mut target = None;
+
+        for (idx, slot) in self.enemies.iter().enumerate() {
+            let Some(enemy) = slot else { continue };
+            let (rel_x, rel_y) = (enemy.x - self.player_x, enemy.y - self.player_y);
+
+            // Distance along the ray and how far the enemy sits off it
+            let depth = rel_x * dir_x + rel_y * dir_y;
+            let offset = rel_x * dir_y - rel_y * dir_x;
+
+            if depth > 0.0 && depth < *nearest && abs(offset) < enemies::HIT_RADIUS {
+                *nearest = depth;
+                target = Some(idx);
+            }
         }
 
-        return distance(next_x, next_y);
+        target
     }
 
-    fn vertical_intersection(&self, angle: f32) -> f32 {
-        let right = fabsf(floorf((angle - FRAC_PI_2) / PI) % 2.0) != 0.0;
+    // Hurt an enemy, turning it on the player, and remove it if it dies
+    fn damage_enemy(&mut self, idx: usize, damage: i32) {
+        let Some(enemy) = &mut self.enemies[idx] else { return };
+        enemy.hurt(damage, self.player_x, self.player_y);
 
-        let first_x = if right {
-            ceilf(self.player_x) - self.player_x
+        let (x, y) = (enemy.x, enemy.y);
+        if enemy.health <= 0 {
+            // Corpses take the slots in turn, the oldest making way once
+            // they are all used
+            let corpse = Animated::new(x, y, &enemy.stats().death);
+            self.corpses[self.stats.kills as usize % MAX_CORPSES] = Some(corpse);
+            self.enemies[idx] = None;
+            self.stats.kills += 1;
+            self.play_sound_at(&audio::DEATH, x, y);
+            self.spray(particles::Kind::Blood, x, y);
         } else {
-            floorf(self.player_x) - self.player_x
-        };
-        let first_y = -tanf(angle) * first_x;
+            self.play_sound_at(&audio::HIT, x, y);
+        }
+    }
 
-        let dx = if right { 1.0 } else { -1.0 };
-        let dy = dx * -tanf(angle);
-        
-        let mut next_x = first_x;
-        let mut next_y = first_y;
+    fn open_door(&mut self, tile_x: i32, tile_y: i32) {
+        let door = Door { tile_x, tile_y, open: 0.0, opening: true, timer: DOOR_OPEN_TIME };
 
-        for _ in 0..256 {
-            let current_x = if right {
-                next_x + self.player_x
-            } else {
-                next_x + self.player_x - 1.0
-            };
-            let current_y = next_y + self.player_y;
+        match self.doors.iter_mut().flatten().find(|d| (d.tile_x, d.tile_y) == (tile_x, tile_y)) {
+            Some(active) => {
+                active.opening = true;
+                active.timer = DOOR_OPEN_TIME;
+            }
+            None => {
+                if let Some(slot) = self.doors.iter_mut().find(|d| d.is_none()) {
+                    *slot = Some(door);
+                }
+            }
+        }
+    }
+
+    // Grab any items within reach, leaving ones that would be wasted
+    fn collect_pickups(&mut self) {
+        for idx in 0..MAX_PICKUPS {
+            let Some(pickup) = self.pickups[idx] else { continue };
 
-            if point_in_wall(current_x, current_y) {
-                break;
+            let (dx, dy) = (pickup.x - self.player_x, pickup.y - self.player_y);
+            if dx * dx + dy * dy > pickups::RADIUS * pickups::RADIUS {
+                continue;
             }
 
-            next_x += dx;
-            next_y += dy;
+            let key = matches!(pickup.item, Item::Key(_));
+            let sound = if key { audio::KEY } else { audio::PICKUP };
+            match pickup.item {
+                Item::Health if self.health < MAX_HEALTH => {
+                    self.health = (self.health + pickups::HEALTH_AMOUNT).min(MAX_HEALTH);
+                }
+                Item::Ammo if self.ammo < weapon::MAX_AMMO => {
+                    self.ammo = (self.ammo + pickups::AMMO_AMOUNT).min(weapon::MAX_AMMO);
+                    if self.weapon.spec().ammo == Ammo::Bullets {
+                        self.reload();
+                    }
+                }
+                // Picking up rockets puts the launcher in hand
+                Item::Rockets if self.rockets < weapon::MAX_ROCKETS => {
+                    self.rockets = (self.rockets + pickups::ROCKET_AMOUNT).min(weapon::MAX_ROCKETS);
+                    self.give_weapon(Weapon::Launcher);
+                }
+                // New guns come loaded
+                Item::RapidGun => {
+                    self.ammo = (self.ammo + pickups::AMMO_AMOUNT).min(weapon::MAX_AMMO);
+                    self.give_weapon(Weapon::Rapid);
+                }
+                Item::Flashlight => self.lights_out = !self.lights_out,
+                Item::Key(key) => self.keys |= key.bit(),
+                _ => continue,
+            }
+
+            self.play_sound(&sound);
+            self.pickups[idx] = None;
         }
+    }
+
+    fn update_enemies(&mut self) {
+        let noise = self.noise.take();
+
+        for idx in 0..MAX_ENEMIES {
+            let Some(mut enemy) = self.enemies[idx] else { continue };
+            let mode = enemy.mode;
+            if let Some(noise) = &noise {
+                enemy.hear(noise);
+            }
+
+            // Each enemy goes for the nearer player
+            let partner = self.partner_nearer(enemy.x, enemy.y);
+            if partner {
+                self.swap_partner();
+            }
+            let attack = enemy.update(self);
+            self.enemies[idx] = Some(enemy);
+
+            // Stagger the footsteps so a crowd doesn't march in step
+            let step = (self.stats.frames + idx as u32 * 7).is_multiple_of(ENEMY_STEP_FRAMES);
+            let walking = matches!(enemy.mode, Mode::Patrol | Mode::Chase);
+            if !mode.hunting() && enemy.mode.hunting() {
+                self.play_sound_at(&audio::ALERT, enemy.x, enemy.y);
+            } else if walking && step {
+                self.play_sound_at(&audio::ENEMY_STEP, enemy.x, enemy.y);
+            }
 
-        distance(next_x, next_y)
+            match attack {
+                Some(Attack::Swing) => {
+                    self.play_sound_at(&audio::SWING, enemy.x, enemy.y);
+                    self.indicate_hit(enemy.x, enemy.y);
+                    self.hurt(enemy.stats().damage);
+                }
+                Some(Attack::Shot) => {
+                    self.play_sound_at(&audio::SHOOT, enemy.x, enemy.y);
+                    let (x, y) = (self.player_x, self.player_y);
+                    let shot = Kind::Shot(enemy.stats().damage);
+                    self.launch_volley(shot, enemy.x, enemy.y, x, y, enemy.volley());
+                }
+                None => {}
+            }
+            if partner {
+                self.swap_partner();
+            }
+        }
     }
 
-    pub fn get_view(&self) -> [(i32, bool); 160] {
-        let starting_angle = self.player_angle + HALF_FOV;
+    // Collect the level decorations, items and living enemies for the sprite pass
+    fn sprites(&self) -> ([Sprite; MAX_SPRITES], usize) {
+        let mut buffer = [Sprite { x: 0.0, y: 0.0, image: &sprites::GUARD }; MAX_SPRITES];
+        let mut count = 0;
+
+        let mut push = |sprite| {
+            if count < MAX_SPRITES {
+                buffer[count] = sprite;
+                count += 1;
+            }
+        };
+
+        for &decoration in LEVELS[self.level].decorations {
+            push(decoration);
+        }
+        for corpse in self.corpses.iter().flatten() {
+            push(corpse.sprite());
+        }
+        for pickup in self.pickups.iter().flatten() {
+            push(pickup.sprite());
+        }
+        for enemy in self.enemies.iter().flatten() {
+            push(Sprite { x: enemy.x, y: enemy.y, image: enemy.image() });
+        }
+        // Whichever players aren't looking, in co-op or deathmatch
+        for player in self.partner.iter().chain(self.rivals().map(|(_, rival)| rival)) {
+            push(player.sprite(self.player_x, self.player_y));
+        }
+        if let Some((x, y, _)) = self.ghost() {
+            push(Sprite { x, y, image: &sprites::GHOST });
+        }
+        for shot in self.projectiles.iter().flatten() {
+            push(shot.sprite());
+        }
+        for particle in self.particles.iter().flatten() {
+            push(particle.sprite());
+        }
+
+        (buffer, count)
+    }
 
-        let mut walls = [(0, false); 160];
+    // Slide doors open, hold them, then slide them shut again
+    fn update_doors(&mut self) {
+        let (player_x, player_y) = (self.player_x, self.player_y);
 
-        for (idx, wall) in walls.iter_mut().enumerate() {
-            let angle = starting_angle - idx as f32 * ANGLE_STEP;
+        for slot in self.doors.iter_mut() {
+            let Some(door) = slot else { continue };
+            let occupied =
+                circle_overlaps_tile(player_x, player_y, player::RADIUS, door.tile_x, door.tile_y);
 
-            let h_dist = self.horizontal_intersection(angle);
-            let v_dist = self.vertical_intersection(angle);
+            if door.opening {
+                door.open = (door.open + DOOR_SPEED).min(1.0);
 
-            let (min_dist, shadow) = if h_dist < v_dist {
-                (h_dist, false)
+                if door.open >= 1.0 {
+                    door.timer = door.timer.saturating_sub(1);
+                    door.opening = door.timer > 0 || occupied;
+                }
+            } else if occupied {
+                door.opening = true;
             } else {
-                (v_dist, true)
-            };
+                door.open -= DOOR_SPEED;
 
-            *wall = (
-                (WALL_HEIGHT / (min_dist * cosf(angle - self.player_angle))) as i32,
-                shadow,
-            );
+                if door.open <= 0.0 {
+                    *slot = None;
+                }
+            }
         }
+    }
 
-        walls
+    // How far a door tile has slid open, from 0 (closed) to 1 (open)
+    fn door_open(&self, tile_x: i32, tile_y: i32) -> f32 {
+        self.doors
+            .iter()
+            .flatten()
+            .find(|d| (d.tile_x, d.tile_y) == (tile_x, tile_y))
+            .map_or(0.0, |d| d.open)
     }
 }
 
-static mut STATE: State = State {
-    player_x: 1.5,
-    player_y: 1.5,
-    player_angle: 0.0,
-};
-
-// Required by #![no_std] to handle panic
-#[panic_handler]
-fn phandler(_: &PanicInfo<'_>) -> ! {
-    wasm32::unreachable();
+impl Default for State {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-// Check if the map contains a wall at a point
-fn point_in_wall(x: f32, y: f32) -> bool {
-    match MAP.get(y as usize) {
-        Some(line) => (line & (0b1 << x as usize)) != 0,
-        None => true,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::PI;
+    use platform::{BUTTON_1, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP};
+
+    #[test]
+    fn shooting_a_cracked_wall_knocks_it_through() {
+        // The first level has a cracked wall at (7, 8) with a corridor on
+        // either side
+        let mut state = State::new();
+        state.teleport(6.5, 8.5, 0.0);
+        assert_eq!(state.map().tile(7, 8), CRACKED_WALL);
+
+        assert!(state.fire());
+        assert_eq!(state.map().tile(7, 8), EMPTY);
     }
-}
 
-#[no_mangle]
-unsafe fn update() {
-    STATE.update(
-        *GAMEPAD1 & BUTTON_UP != 0,
-        *GAMEPAD1 & BUTTON_DOWN != 0,
-        *GAMEPAD1 & BUTTON_LEFT != 0,
-        *GAMEPAD1 & BUTTON_RIGHT != 0,
-    );
+    #[test]
+    fn gunshots_bring_enemies_out_of_sight_running() {
+        // Facing away from the enemy at (10.5, 8.5), with the cracked wall
+        // at (7, 8) between them
+        let mut state = State::new();
+        state.teleport(6.5, 8.5, PI);
+        let idx = state.enemies.iter().position(|e| e.is_some_and(|e| e.x == 10.5)).unwrap();
 
-    for (x, wall) in STATE.get_view().iter().enumerate() {
-        let (height, shadow) = wall;
+        state.update_enemies();
+        assert!(state.enemies[idx].unwrap().mode == Mode::Idle);
 
-        if *shadow {
-            *DRAW_COLORS = 0x2;
-        } else {
-            *DRAW_COLORS = 0x3;
+        assert!(state.fire());
+        state.update_enemies();
+        assert!(state.enemies[idx].unwrap().mode == Mode::Chase);
+    }
+
+    #[test]
+    fn patrolling_enemies_walk_their_route() {
+        // Level 1's guard at (12.5, 3.5) patrols to (14, 3) first, out of
+        // sight of the spawn point
+        let mut state = State::new();
+        let idx = state.enemies.iter().position(|e| e.is_some_and(|e| e.x == 12.5)).unwrap();
+        assert!(state.enemies[idx].unwrap().mode == Mode::Patrol);
+
+        for _ in 0..90 {
+            state.update_enemies();
         }
+        let enemy = state.enemies[idx].unwrap();
+        assert!(enemy.mode == Mode::Patrol);
+        assert_eq!((floor(enemy.x), floor(enemy.y)), (14.0, 3.0));
+    }
 
-        vline(x as i32, 80 - (height / 2), *height as u32);
+    #[test]
+    fn melee_enemies_chase_without_shooting() {
+        for archetype in [enemies::Archetype::Runner, enemies::Archetype::Brute] {
+            let mut state = State::new();
+            state.teleport(5.5, 6.5, 0.0);
+            state.enemies = [None; MAX_ENEMIES];
+            state.enemies[0] = Some(Enemy::new(archetype, 9.5, 6.5));
+
+            for _ in 0..10 {
+                state.update_enemies();
+                assert!(state.enemies[0].unwrap().mode == Mode::Chase);
+            }
+            assert!(state.projectiles.iter().all(Option::is_none));
+        }
     }
-}
 
+    // Everything play depends on, down to the bit
+    fn fingerprint(state: &State) -> [u32; 8] {
+        let mut enemies = 0u32;
+        for enemy in state.enemies.iter().flatten() {
+            let bits = [enemy.x.to_bits(), enemy.y.to_bits(), enemy.health as u32];
+            enemies = bits.iter().fold(enemies, |hash, &bits| (hash ^ bits).wrapping_mul(16777619));
+        }
+        let mut rng = state.rng;
+
+        [
+            state.player_x.to_bits(),
+            state.player_y.to_bits(),
+            state.player_angle.to_bits(),
+            state.velocity_x.to_bits() ^ state.velocity_y.to_bits(),
+            state.health as u32 ^ state.ammo << 8 ^ (state.level as u32) << 16,
+            state.mode as u32,
+            enemies,
+            rng.next_u32(),
+        ]
+    }
+
+    #[test]
+    fn games_fed_the_same_input_never_drift_apart() {
+        // Netplay runs one game per peer on the same gamepad bytes, so the
+        // two here step in turn to catch any state kept outside State
+        let mut games = [State::new(), State::new()];
+        let mut pads = Rng::new(7);
+        let mut gamepad = 0;
+
+        for frame in 0..6000 {
+            if frame % 12 == 0 {
+                let turn = [0, BUTTON_LEFT, BUTTON_RIGHT][pads.below(3) as usize];
+                let fire = if pads.below(3) == 0 { BUTTON_1 } else { 0 };
+                gamepad = BUTTON_UP | turn | fire;
+            }
+
+            for state in &mut games {
+                state.input.replay(gamepad);
+                match state.mode {
+                    GameMode::Playing => state.update(),
+                    GameMode::Dead => state.restart(),
+                    _ => {}
+                }
+            }
+            assert_eq!(fingerprint(&games[0]), fingerprint(&games[1]), "apart at frame {frame}");
+        }
+    }
+}
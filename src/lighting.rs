@@ -0,0 +1,96 @@
+// Baked lighting: how brightly each tile of a level is lit, worked out once
+// at load. Every tile starts at the level's ambient light, and the tiles that
+// give off light, torches, monitors and lava, light the floor around them a
+// level less with each tile further off. Walls take the light of the floor
+// next to them but pass none through
+use crate::map::{is_floor, Map, LAVA, MAX_SIZE, MAX_TILES, MONITOR, TORCH_WALL};
+
+// The brightest a tile gets, and the light levels below it, each a half-shade
+// darker
+pub const BRIGHT: u8 = 3;
+
+const fn is_light(tile: u8) -> bool {
+    matches!(tile, TORCH_WALL | MONITOR | LAVA)
+}
+
+// Two bits of light level per tile of the largest map
+#[derive(Clone, Copy)]
+pub struct Lighting([u8; MAX_TILES / 4]);
+
+impl Lighting {
+    // Everything fully lit
+    pub const fn new() -> Self {
+        Lighting([0xff; MAX_TILES / 4])
+    }
+
+    fn index(x: i32, y: i32) -> Option<usize> {
+        let inside = (0..MAX_SIZE as i32).contains(&x) && (0..MAX_SIZE as i32).contains(&y);
+        inside.then(|| y as usize * MAX_SIZE + x as usize)
+    }
+
+    // Off the map counts as fully lit, like the walls around it
+    pub fn level(&self, x: i32, y: i32) -> u8 {
+        Self::index(x, y).map_or(BRIGHT, |idx| self.0[idx / 4] >> (idx % 4 * 2) & BRIGHT)
+    }
+
+    fn set(&mut self, x: i32, y: i32, level: u8) {
+        if let Some(idx) = Self::index(x, y) {
+            let shift = idx % 4 * 2;
+            self.0[idx / 4] = (self.0[idx / 4] & !(BRIGHT << shift)) | level << shift;
+        }
+    }
+
+    pub fn bake(map: &Map, ambient: u8) -> Self {
+        let (width, height) = (map.width() as i32, map.height() as i32);
+
+        let mut lighting = Lighting::new();
+        for y in 0..height {
+            for x in 0..width {
+                let light = is_light(map.tile(x, y));
+                lighting.set(x, y, if light { BRIGHT } else { ambient.min(BRIGHT) });
+            }
+        }
+
+        // Light spreads at least a tile further with each pass, and is gone
+        // by the time it has come BRIGHT tiles
+        for _ in 0..BRIGHT {
+            for y in 0..height {
+                for x in 0..width {
+                    let mut level = lighting.level(x, y);
+                    for (x, y) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                        let tile = map.tile(x, y);
+                        if is_floor(tile) || is_light(tile) {
+                            level = level.max(lighting.level(x, y).saturating_sub(1));
+                        }
+                    }
+                    lighting.set(x, y, level);
+                }
+            }
+        }
+
+        lighting
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{Layout, EMPTY, WALL};
+    use crate::rle;
+
+    #[test]
+    fn torches_light_the_floor_around_them_fading_with_distance() {
+        const TILES: [u8; 12] = [
+            TORCH_WALL, EMPTY, EMPTY, EMPTY, EMPTY, WALL,
+            WALL, WALL, WALL, WALL, WALL, WALL,
+        ];
+        const PACKED: [u8; rle::packed_len(&TILES)] = rle::pack(&TILES);
+        let map = Map::load(&Layout { width: 6, height: 2, tiles: &PACKED });
+
+        let lighting = Lighting::bake(&map, 0);
+        let row: [u8; 6] = core::array::from_fn(|x| lighting.level(x as i32, 0));
+        assert_eq!(row, [3, 2, 1, 0, 0, 0]);
+        // Walls are lit from the floor beside them
+        assert_eq!((lighting.level(1, 1), lighting.level(3, 1)), (1, 0));
+    }
+}
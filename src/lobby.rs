@@ -0,0 +1,99 @@
+// The deathmatch lobby: the rules for the match, picked with the d-pad
+// while the other players join, until START begins it
+use crate::{GameMode, State};
+
+const LABELS: [&[u8]; ROWS] = [b"MODE", b"TEAM FIRE", b"START"];
+pub const ROWS: usize = 3;
+
+// Only the first gamepad changes the rules, and netplay shares it, while
+// they are never kept on disk, so every peer plays by the same ones
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rules {
+    // Two against two, the first and second players against the others
+    pub teams: bool,
+    // Whether teammates' shots hurt each other
+    pub friendly_fire: bool,
+}
+
+impl Rules {
+    pub const fn new() -> Self {
+        Rules { teams: false, friendly_fire: false }
+    }
+
+    pub fn teammates(&self, a: usize, b: usize) -> bool {
+        self.teams && a / 2 == b / 2
+    }
+
+    fn change(&mut self, row: usize) {
+        match row {
+            0 => self.teams = !self.teams,
+            _ => self.friendly_fire = !self.friendly_fire,
+        }
+    }
+
+    // Each row's label and the text for its current value
+    pub fn rows(&self) -> [(&'static [u8], &'static [u8]); ROWS] {
+        let values: [&[u8]; ROWS] = [
+            if self.teams { b"TEAMS" } else { b"FREE" },
+            if self.friendly_fire { b"ON" } else { b"OFF" },
+            b"",
+        ];
+
+        core::array::from_fn(|row| (LABELS[row], values[row]))
+    }
+}
+
+impl State {
+    // Move through the lobby with up and down and change the rules with left,
+    // right or BUTTON_1, which on START begins the match; BUTTON_2 goes back
+    // to the title
+    pub(crate) fn update_lobby(&mut self) {
+        let input = self.input;
+        let Some(deathmatch) = &mut self.deathmatch else { return };
+
+        if input.up.pressed() {
+            deathmatch.item = (deathmatch.item + ROWS - 1) % ROWS;
+        }
+        if input.down.pressed() {
+            deathmatch.item = (deathmatch.item + 1) % ROWS;
+        }
+
+        let start = deathmatch.item == ROWS - 1;
+        let action = self.input.action.take();
+        if !start && (action || input.left.pressed() || input.right.pressed()) {
+            deathmatch.rules.change(deathmatch.item);
+        } else if start && action {
+            self.start_deathmatch();
+        } else if input.button_2.tapped() {
+            self.end_deathmatch();
+            self.mode = GameMode::Title;
+            self.transition = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::{BUTTON_1, BUTTON_DOWN, BUTTON_RIGHT};
+
+    #[test]
+    fn the_lobby_sets_the_rules_and_starts_the_match() {
+        let mut state = State::new();
+        state.mode = GameMode::Title;
+        state.poll_deathmatch([BUTTON_1, 0, 0]);
+
+        for gamepad in [BUTTON_RIGHT, 0, BUTTON_DOWN, 0, BUTTON_1, 0, BUTTON_DOWN, 0] {
+            state.input.replay(gamepad);
+            state.update_lobby();
+        }
+        let rules = state.deathmatch.unwrap().rules;
+        assert_eq!(rules, Rules { teams: true, friendly_fire: true });
+        assert!(state.mode == GameMode::Lobby);
+
+        state.input.replay(BUTTON_1);
+        state.update_lobby();
+        assert!(state.mode == GameMode::Playing);
+        assert!(rules.teammates(0, 1) && !rules.teammates(1, 2));
+    }
+}
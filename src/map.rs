@@ -0,0 +1,249 @@
+// Tile types and the grids levels are built from
+use crate::pickups::Key;
+use crate::rle;
+
+// Map tile types
+pub const EMPTY: u8 = 0;
+pub const WALL: u8 = 1;
+pub const DOOR: u8 = 2;
+pub const EXIT: u8 = 3;
+// Floors that hurt to stand on: acid, and lava which burns harder
+pub const HAZARD: u8 = 4;
+// Doors that need the matching key
+pub const SILVER_DOOR: u8 = 5;
+pub const BRASS_DOOR: u8 = 6;
+pub const IRON_DOOR: u8 = 7;
+// Solid walls that only look different from WALL, which is brick
+pub const STONE_WALL: u8 = 8;
+pub const METAL_WALL: u8 = 9;
+// Looks like brick, but slides back when used
+pub const SECRET_WALL: u8 = 10;
+pub const LAVA: u8 = 11;
+// Brick that a shot knocks through
+pub const CRACKED_WALL: u8 = 12;
+// A screen set into the wall showing the level's security cameras
+pub const MONITOR: u8 = 13;
+// A stone wall with a torch burning on it
+pub const TORCH_WALL: u8 = 14;
+// Open floor out under the sky
+pub const OUTDOOR: u8 = 15;
+// A grating that blocks the way but can be seen through
+pub const FENCE: u8 = 16;
+// A brick wall with a pane of glass in it
+pub const WINDOW: u8 = 17;
+// Half as high as a wall, so it can be seen over but not walked through
+pub const LOW_WALL: u8 = 18;
+// A column half as high again as a wall
+pub const PILLAR: u8 = 19;
+// Shows the view reflected off its face
+pub const MIRROR: u8 = 20;
+// One of a linked pair; whatever goes in one comes out of the other
+pub const PORTAL: u8 = 21;
+// Knee-deep, so it slows the player down
+pub const WATER: u8 = 22;
+
+// Open floor, safe or not
+pub const fn is_floor(tile: u8) -> bool {
+    matches!(tile, EMPTY | HAZARD | LAVA | OUTDOOR | PORTAL | WATER)
+}
+
+pub const fn is_wall(tile: u8) -> bool {
+    matches!(
+        tile,
+        WALL | STONE_WALL
+            | METAL_WALL
+            | CRACKED_WALL
+            | MONITOR
+            | TORCH_WALL
+            | WINDOW
+            | LOW_WALL
+            | PILLAR
+            | MIRROR
+    )
+}
+
+// How high a wall tile stands, as a share of a full wall
+pub fn wall_height(tile: u8) -> f32 {
+    match tile {
+        LOW_WALL => 0.5,
+        PILLAR => 1.5,
+        _ => 1.0,
+    }
+}
+
+// Drawn over whatever shows through the blank parts of its texture
+pub const fn is_see_through(tile: u8) -> bool {
+    matches!(tile, FENCE | WINDOW | MIRROR | PORTAL)
+}
+
+pub const fn is_door(tile: u8) -> bool {
+    matches!(tile, DOOR | SILVER_DOOR | BRASS_DOOR | IRON_DOOR)
+}
+
+// The key needed to open a door tile, if it is locked
+pub const fn door_lock(tile: u8) -> Option<Key> {
+    match tile {
+        SILVER_DOOR => Some(Key::Silver),
+        BRASS_DOOR => Some(Key::Brass),
+        IRON_DOOR => Some(Key::Iron),
+        _ => None,
+    }
+}
+
+// Largest map side in tiles, and the tiles a map has room for
+pub const MAX_SIZE: usize = 32;
+pub const MAX_TILES: usize = MAX_SIZE * MAX_SIZE;
+
+// A level's starting grid of tiles as stored in the cart, row by row and
+// packed with rle
+pub struct Layout {
+    pub width: u8,
+    pub height: u8,
+    pub tiles: &'static [u8],
+}
+
+// The grid being played, copied into RAM from a layout when the level loads
+// so gameplay can change it
+#[derive(Clone, Copy)]
+pub struct Map {
+    width: u8,
+    height: u8,
+    tiles: [u8; MAX_TILES],
+}
+
+impl Map {
+    pub const fn load(layout: &Layout) -> Self {
+        let mut tiles = [WALL; MAX_TILES];
+        rle::unpack(layout.tiles, &mut tiles);
+
+        Map { width: layout.width, height: layout.height, tiles }
+    }
+
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+
+    // Number of cells holding a tile type
+    pub const fn count(&self, tile: u8) -> u32 {
+        let mut count = 0;
+        let mut idx = 0;
+        while idx < self.width as usize * self.height as usize {
+            count += (self.tiles[idx] == tile) as u32;
+            idx += 1;
+        }
+        count
+    }
+
+    // Where the first cell holding a tile type is in reading order, after
+    // skipping the given number of others that hold it
+    pub fn find(&self, tile: u8, mut skip: u32) -> Option<(i32, i32)> {
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                if self.tile(x, y) != tile {
+                    continue;
+                }
+                if skip == 0 {
+                    return Some((x, y));
+                }
+                skip -= 1;
+            }
+        }
+        None
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        let inside = (0..self.width as i32).contains(&x) && (0..self.height as i32).contains(&y);
+        inside.then(|| (y * self.width as i32 + x) as usize)
+    }
+
+    // Get the tile type of a map cell, treating anything off the map as wall,
+    // including negative coordinates past the left and top edges
+    pub fn tile(&self, x: i32, y: i32) -> u8 {
+        self.index(x, y).map_or(WALL, |idx| self.tiles[idx])
+    }
+
+    // Change a map cell; cells off the map stay wall
+    pub fn set_tile(&mut self, x: i32, y: i32, tile: u8) {
+        if let Some(idx) = self.index(x, y) {
+            self.tiles[idx] = tile;
+        }
+    }
+}
+
+// Check if a circle overlaps a tile, using the tile's closest point
+pub fn circle_overlaps_tile(x: f32, y: f32, radius: f32, tile_x: i32, tile_y: i32) -> bool {
+    let closest_x = x.clamp(tile_x as f32, tile_x as f32 + 1.0);
+    let closest_y = y.clamp(tile_y as f32, tile_y as f32 + 1.0);
+
+    let (dx, dy) = (x - closest_x, y - closest_y);
+    dx * dx + dy * dy < radius * radius
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::floor;
+
+    // Open along the top-left edge, so a coordinate that wraps or clamps to 0
+    // would wrongly come back as empty
+    const TILES: [u8; 6] = [
+        EMPTY, EMPTY, DOOR,
+        EMPTY, WALL, EXIT,
+    ];
+    const PACKED: [u8; rle::packed_len(&TILES)] = rle::pack(&TILES);
+    const OPEN_CORNER: Layout = Layout { width: 3, height: 2, tiles: &PACKED };
+
+    #[test]
+    fn tile_inside_map() {
+        let map = Map::load(&OPEN_CORNER);
+        assert_eq!(map.tile(0, 0), EMPTY);
+        assert_eq!(map.tile(2, 0), DOOR);
+        assert_eq!(map.tile(1, 1), WALL);
+        assert_eq!(map.tile(2, 1), EXIT);
+    }
+
+    #[test]
+    fn tile_left_and_top_of_map_is_wall() {
+        let map = Map::load(&OPEN_CORNER);
+        assert_eq!(map.tile(-1, 0), WALL);
+        assert_eq!(map.tile(0, -1), WALL);
+        assert_eq!(map.tile(-1, -1), WALL);
+        assert_eq!(map.tile(i32::MIN, i32::MIN), WALL);
+    }
+
+    #[test]
+    fn tile_right_and_bottom_of_map_is_wall() {
+        let map = Map::load(&OPEN_CORNER);
+        assert_eq!(map.tile(3, 0), WALL);
+        assert_eq!(map.tile(0, 2), WALL);
+        assert_eq!(map.tile(i32::MAX, i32::MAX), WALL);
+    }
+
+    #[test]
+    fn set_tile_changes_only_cells_on_the_map() {
+        let mut map = Map::load(&OPEN_CORNER);
+        map.set_tile(1, 1, EMPTY);
+        map.set_tile(-1, 0, DOOR);
+        map.set_tile(3, 0, DOOR);
+
+        assert_eq!(map.tile(1, 1), EMPTY);
+        assert_eq!(map.tile(-1, 0), WALL);
+        assert_eq!(map.tile(3, 0), WALL);
+        // Writing past the right edge must not wrap into the next row
+        assert_eq!(map.tile(0, 1), EMPTY);
+    }
+
+    #[test]
+    fn tile_just_past_the_edge_is_wall() {
+        let map = Map::load(&OPEN_CORNER);
+
+        // floor() rounds towards negative infinity, so a point just left of the
+        // map lands on column -1 instead of column 0
+        assert_eq!(map.tile(floor(-0.01) as i32, 0), WALL);
+        assert_eq!(map.tile(0, floor(-0.01) as i32), WALL);
+    }
+}
@@ -0,0 +1,146 @@
+// Random mazes for endless play, carved into a tile buffer in RAM from a
+// seed so every run can get a new layout without storing it in the cart
+use core::f32::consts::PI;
+
+use crate::map::{EMPTY, EXIT, MAX_SIZE, WALL};
+use crate::rng::Rng;
+
+// Mazes are carved between cells on the odd tiles, so at most this many
+const MAX_CELLS: usize = (MAX_SIZE / 2) * (MAX_SIZE / 2);
+
+// Tile steps to the neighbouring cells, east, north, west and south, and
+// the view angle facing each
+const STEPS: [(i32, i32, f32); 4] =
+    [(2, 0, 0.0), (0, -2, PI / 2.0), (-2, 0, PI), (0, 2, PI * 1.5)];
+
+// Carve a perfect maze into the first width * height tiles, row by row, with
+// a recursive backtracker: every open tile is reachable and there are no
+// loops. The exit switch goes on a wall at the cell furthest from the start.
+// Sides are rounded down to odd so the maze is walled all round, and must be
+// at least 3. Returns the spawn point and angle
+pub fn generate(seed: u32, width: usize, height: usize, tiles: &mut [u8]) -> (f32, f32, f32) {
+    let width = (width.min(MAX_SIZE) - 1) | 1;
+    let height = (height.min(MAX_SIZE) - 1) | 1;
+    let tiles = &mut tiles[..width * height];
+    tiles.fill(WALL);
+
+    let mut rng = Rng::new(seed as u64);
+    let (cells_x, cells_y) = (width / 2, height / 2);
+
+    let index = |x: i32, y: i32| y as usize * width + x as usize;
+    let start = rng.below((cells_x * cells_y) as u32) as usize;
+    let (start_x, start_y) = ((start % cells_x * 2 + 1) as i32, (start / cells_x * 2 + 1) as i32);
+    tiles[index(start_x, start_y)] = EMPTY;
+
+    let mut stack = [(0, 0); MAX_CELLS];
+    stack[0] = (start_x, start_y);
+    let mut depth = 1;
+    let mut furthest = (start_x, start_y, 0);
+    let mut spawn_angle = None;
+
+    while depth > 0 {
+        let (x, y) = stack[depth - 1];
+
+        // Try the neighbours from a random one onwards, in a random direction
+        let first = rng.below(4) as usize;
+        let turn = if rng.chance(0.5) { 1 } else { 3 };
+        let next = (0..4).map(|n| STEPS[(first + n * turn) % 4]).find(|&(dx, dy, _)| {
+            let (nx, ny) = (x + dx, y + dy);
+            nx > 0
+                && ny > 0
+                && (nx as usize) < width
+                && (ny as usize) < height
+                && tiles[index(nx, ny)] == WALL
+        });
+
+        let Some((dx, dy, angle)) = next else {
+            depth -= 1;
+            continue;
+        };
+
+        // Face down the first corridor carved from the start
+        if depth == 1 {
+            spawn_angle.get_or_insert(angle);
+        }
+
+        tiles[index(x + dx / 2, y + dy / 2)] = EMPTY;
+        tiles[index(x + dx, y + dy)] = EMPTY;
+        stack[depth] = (x + dx, y + dy);
+        depth += 1;
+
+        if depth > furthest.2 {
+            furthest = (x + dx, y + dy, depth);
+        }
+    }
+
+    // Cells in a perfect maze always have a wall on at least one side
+    let (end_x, end_y, _) = furthest;
+    if let Some(&(dx, dy, _)) =
+        STEPS.iter().find(|&&(dx, dy, _)| tiles[index(end_x + dx / 2, end_y + dy / 2)] == WALL)
+    {
+        tiles[index(end_x + dx / 2, end_y + dy / 2)] = EXIT;
+    }
+
+    (start_x as f32 + 0.5, start_y as f32 + 0.5, spawn_angle.unwrap_or(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MAX_TILES;
+
+    // Flood fill the open tiles from the spawn, counting how many it reaches
+    fn reachable(tiles: &[u8], width: usize, x: usize, y: usize) -> usize {
+        let mut seen = [false; MAX_TILES];
+        let mut queue = [0; MAX_TILES];
+        let (mut head, mut tail) = (0, 1);
+        queue[0] = y * width + x;
+        seen[queue[0]] = true;
+
+        while head < tail {
+            let idx = queue[head];
+            head += 1;
+
+            for next in [idx - 1, idx + 1, idx - width, idx + width] {
+                if tiles[next] == EMPTY && !seen[next] {
+                    seen[next] = true;
+                    queue[tail] = next;
+                    tail += 1;
+                }
+            }
+        }
+
+        tail
+    }
+
+    #[test]
+    fn generated_mazes_are_walled_connected_and_have_an_exit() {
+        for seed in [0, 1, 7, 0xdead_beef] {
+            let mut tiles = [0; MAX_TILES];
+            let (x, y, _) = generate(seed, 21, 15, &mut tiles);
+            let tiles = &tiles[..21 * 15];
+
+            for idx in 0..21 {
+                assert!(tiles[idx] != EMPTY && tiles[14 * 21 + idx] != EMPTY);
+            }
+            for idx in 0..15 {
+                assert!(tiles[idx * 21] != EMPTY && tiles[idx * 21 + 20] != EMPTY);
+            }
+
+            let open = tiles.iter().filter(|&&tile| tile == EMPTY).count();
+            assert_eq!(reachable(tiles, 21, x as usize, y as usize), open);
+            assert_eq!(tiles.iter().filter(|&&tile| tile == EXIT).count(), 1);
+        }
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_maze() {
+        let (mut first, mut second) = ([0; MAX_TILES], [0; MAX_TILES]);
+        generate(42, 31, 31, &mut first);
+        generate(42, 31, 31, &mut second);
+        assert!(first == second);
+
+        generate(43, 31, 31, &mut second);
+        assert!(first != second);
+    }
+}
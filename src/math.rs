@@ -0,0 +1,49 @@
+// Math backend: libm by default, or Q16.16 lookup tables with the
+// `fixed-point` feature for a smaller cartridge. Either is plain Rust built
+// into the cart on float operations WebAssembly rounds the same on every
+// machine, so netplay peers fed the same input stay bit-identical
+
+#[cfg(not(any(feature = "libm", feature = "fixed-point")))]
+compile_error!("enable either the `libm` or the `fixed-point` feature");
+
+#[cfg(all(feature = "libm", not(feature = "fixed-point")))]
+pub use libm::{atan2f as atan2, fabsf as abs, floorf as floor, sinf as sin, sqrtf as sqrt};
+
+// The sine a quarter turn on, so the cart carries only libm's sine
+#[cfg(all(feature = "libm", not(feature = "fixed-point")))]
+pub fn cos(x: f32) -> f32 {
+    libm::sinf(x + core::f32::consts::FRAC_PI_2)
+}
+
+#[cfg(feature = "fixed-point")]
+pub use self::fixed_point::*;
+
+#[cfg(feature = "fixed-point")]
+mod fixed_point {
+    use crate::fixed::Fixed;
+
+    pub fn sin(x: f32) -> f32 {
+        Fixed::from_f32(x).sin().to_f32()
+    }
+
+    pub fn cos(x: f32) -> f32 {
+        Fixed::from_f32(x).cos().to_f32()
+    }
+
+    pub fn atan2(y: f32, x: f32) -> f32 {
+        Fixed::from_f32(y).atan2(Fixed::from_f32(x)).to_f32()
+    }
+
+    pub fn sqrt(x: f32) -> f32 {
+        Fixed::from_f32(x).sqrt().to_f32()
+    }
+
+    pub fn abs(x: f32) -> f32 {
+        if x < 0.0 { -x } else { x }
+    }
+
+    pub fn floor(x: f32) -> f32 {
+        let truncated = x as i32 as f32;
+        if truncated > x { truncated - 1.0 } else { truncated }
+    }
+}
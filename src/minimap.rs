@@ -0,0 +1,55 @@
+// Top-down overview of the current level in the corner of the screen
+use crate::map::{is_door, EMPTY, EXIT, HAZARD, LAVA, OUTDOOR, PORTAL, WATER};
+use crate::math::{cos, sin};
+use crate::platform::{line, rect, DRAW_COLORS};
+use crate::{compass, State};
+
+// Pixels per map tile
+const SCALE: i32 = 2;
+
+// Offset from the top-left corner of the screen, and from the top below
+// the compass
+const MARGIN: i32 = 2;
+const TOP: i32 = compass::HEIGHT + MARGIN;
+
+// Length of the facing indicator in pixels
+const POINTER: f32 = 5.0;
+
+pub unsafe fn render(state: &State) {
+    let map = state.map();
+
+    // Backdrop with an outline
+    *DRAW_COLORS = 0x41;
+    rect(
+        MARGIN - 1,
+        TOP - 1,
+        (map.width() as i32 * SCALE + 2) as u32,
+        (map.height() as i32 * SCALE + 2) as u32,
+    );
+
+    for y in 0..map.height() as i32 {
+        for x in 0..map.width() as i32 {
+            *DRAW_COLORS = match map.tile(x, y) {
+                EMPTY | OUTDOOR | PORTAL | WATER => continue,
+                tile if is_door(tile) || tile == EXIT => 0x2,
+                HAZARD | LAVA => 0x3,
+                _ => 0x4,
+            };
+
+            rect(MARGIN + x * SCALE, TOP + y * SCALE, SCALE as u32, SCALE as u32);
+        }
+    }
+
+    // Player position and facing direction
+    let player_x = MARGIN + (state.player_x * SCALE as f32) as i32;
+    let player_y = TOP + (state.player_y * SCALE as f32) as i32;
+
+    *DRAW_COLORS = 0x3;
+    line(
+        player_x,
+        player_y,
+        player_x + (cos(state.player_angle) * POINTER) as i32,
+        player_y - (sin(state.player_angle) * POINTER) as i32,
+    );
+    rect(player_x - 1, player_y - 1, 3, 3);
+}
@@ -0,0 +1,84 @@
+// The rear-view mirror: a strip over the top of the view showing what is
+// behind the player, flipped the way a mirror would show it. It is a cheap
+// second pass of COLUMNS rays drawn PIXEL wide, with plain walls and enemies
+// as pale blocks so anything giving chase stands out
+use core::f32::consts::PI;
+
+use crate::math::{cos, sin, sqrt};
+use crate::platform::{rect, DRAW_COLORS, SCREEN_SIZE};
+use crate::{compass, State};
+
+const COLUMNS: usize = 40;
+const PIXEL: i32 = 2;
+const WIDTH: i32 = COLUMNS as i32 * PIXEL;
+const LEFT: i32 = (SCREEN_SIZE - WIDTH) / 2;
+const TOP: i32 = compass::HEIGHT + 2;
+const ROWS: i32 = 20;
+
+// How tall an enemy stands against a wall, and how wide against its height
+const ENEMY_HEIGHT: f32 = 0.7;
+const ENEMY_WIDTH: f32 = 0.5;
+
+pub unsafe fn render(state: &State) {
+    let angle = state.player_angle + PI;
+    let (forward_x, forward_y) = (cos(angle), -sin(angle));
+    let (left_x, left_y) = (-sin(angle), -cos(angle));
+
+    // The focal length in columns, and the height in pixels of a wall a
+    // tile away
+    let half_fov = state.config.fov / 2.0;
+    let focal = COLUMNS as f32 / 2.0 * cos(half_fov) / sin(half_fov);
+    let scale = focal * PIXEL as f32;
+    let middle = TOP + ROWS / 2;
+
+    // A frame, with the sky above the middle and the floor below
+    *DRAW_COLORS = 0x41;
+    rect(LEFT - 1, TOP - 1, WIDTH as u32 + 2, ROWS as u32 + 2);
+    *DRAW_COLORS = 0x22;
+    rect(LEFT, middle, WIDTH as u32, (ROWS / 2) as u32);
+
+    // Columns count from the mirror's left, which shows the player's left,
+    // so across the view behind they run from its right
+    let mut depths = [f32::MAX; COLUMNS];
+    for (column, depth) in depths.iter_mut().enumerate() {
+        let offset = column as f32 + 0.5 - COLUMNS as f32 / 2.0;
+        let length = sqrt(focal * focal + offset * offset);
+        let dir_x = (focal * forward_x + offset * left_x) / length;
+        let dir_y = (focal * forward_y + offset * left_y) / length;
+
+        let hit = state.cast_ray_along(state.player_x, state.player_y, (dir_x, dir_y));
+        if hit.distance >= state.view_distance() {
+            continue;
+        }
+
+        *depth = hit.distance * focal / length;
+        let height = (scale / *depth).min(ROWS as f32) as i32;
+        *DRAW_COLORS = if hit.shadow { 0x44 } else { 0x33 };
+        rect(LEFT + column as i32 * PIXEL, middle - height / 2, PIXEL as u32, height as u32);
+    }
+
+    *DRAW_COLORS = 0x11;
+    for enemy in state.enemies.iter().flatten() {
+        let (rel_x, rel_y) = (enemy.x - state.player_x, enemy.y - state.player_y);
+        let depth = rel_x * forward_x + rel_y * forward_y;
+        if depth < 0.1 {
+            continue;
+        }
+
+        // Standing on the floor, cut off by the frame and by nearer walls
+        let center = COLUMNS as f32 / 2.0 + (rel_x * left_x + rel_y * left_y) * focal / depth;
+        let height = scale * ENEMY_HEIGHT / depth;
+        let half_columns = height * ENEMY_WIDTH / PIXEL as f32 / 2.0;
+        let bottom = (middle as f32 + scale / depth / 2.0).min((TOP + ROWS) as f32);
+        let top = (bottom - height).max(TOP as f32);
+
+        let first = (center - half_columns).max(0.0) as usize;
+        let last = ((center + half_columns + 1.0).max(0.0) as usize).min(COLUMNS);
+        for (column, &wall) in (first..).zip(depths.get(first..last).unwrap_or_default()) {
+            if depth < wall {
+                let x = LEFT + column as i32 * PIXEL;
+                rect(x, top as i32, PIXEL as u32, (bottom - top) as u32);
+            }
+        }
+    }
+}
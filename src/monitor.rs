@@ -0,0 +1,98 @@
+// Security cameras: fixed cameras around a level, watched on the monitors
+// set into its walls. Using a monitor puts the first camera's feed in place
+// of the view, left and right flick between the level's cameras and BUTTON_1
+// or BUTTON_2 looks away again. The level goes on meanwhile, so whatever the
+// cameras show can still come for the player
+use core::f32::consts::PI;
+
+use crate::levels::LEVELS;
+use crate::platform::{text, DRAW_COLORS};
+use crate::raycast::Viewport;
+use crate::sprites::{self, Sprite, MAX_SPRITES};
+use crate::{render, GameMode, State};
+
+// The cameras' field of view, wide to take in a whole room
+pub const FOV: f32 = PI / 2.0;
+
+impl State {
+    // Look at the monitor, if the level has cameras to show on it; returns
+    // false if not
+    pub(crate) fn watch(&mut self) -> bool {
+        if LEVELS[self.level].cameras.is_empty() {
+            return false;
+        }
+
+        self.feed = 0;
+        self.mode = GameMode::Watching;
+        true
+    }
+
+    // Flick between the cameras or look away, while the level plays on
+    pub(crate) fn update_monitor(&mut self) {
+        let count = LEVELS[self.level].cameras.len();
+        let input = self.input;
+
+        if input.left.pressed() {
+            self.feed = (self.feed + count - 1) % count;
+        }
+        if input.right.pressed() {
+            self.feed = (self.feed + 1) % count;
+        }
+        if self.input.action.take() || input.button_2.tapped() {
+            self.mode = GameMode::Playing;
+            return;
+        }
+
+        self.update_level();
+    }
+}
+
+// The watched camera's feed across the view, with the player in it
+pub unsafe fn render(state: &State) {
+    let camera = LEVELS[state.level].cameras[state.feed];
+    let (mut sprites, mut count) = state.sprites();
+    if count < MAX_SPRITES {
+        let (x, y) = (state.player_x, state.player_y);
+        let image = sprites::FIGURES.side(x, y, state.player_angle, camera.x, camera.y);
+        sprites[count] = Sprite { x, y, image };
+        count += 1;
+    }
+
+    let full = Viewport::FULL;
+    render::draw_scene(state, camera, full.top + full.rows / 2, &sprites[..count], full);
+
+    // Light on dark, to read over whatever the camera sees
+    *DRAW_COLORS = 0x41;
+    text(b"CAM", 4, 4);
+    text(&[b'1' + state.feed as u8], 36, 4);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::{BUTTON_1, BUTTON_RIGHT};
+
+    #[test]
+    fn monitors_cycle_through_the_level_cameras() {
+        let mut state = State::new();
+        state.load_level(1);
+        state.transition = 0;
+
+        // Facing the monitor in the wall above the corridor by the spawn
+        state.teleport(4.5, 1.5, PI / 2.0);
+        state.input.replay(BUTTON_1);
+        state.update();
+        assert!(state.mode == GameMode::Watching);
+
+        for gamepad in [BUTTON_RIGHT, 0, BUTTON_RIGHT, 0] {
+            state.input.replay(gamepad);
+            state.update_monitor();
+        }
+        assert_eq!(state.feed, 0);
+
+        state.input.replay(BUTTON_1);
+        state.update_monitor();
+        assert!(state.mode == GameMode::Playing);
+        assert_eq!(state.player(), (4.5, 1.5, PI / 2.0));
+    }
+}
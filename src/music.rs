@@ -0,0 +1,183 @@
+// A small tracker: each level's track is a few looping patterns of notes,
+// one per channel, stepped through a note at a time as the frames go by
+use crate::audio::{self, DUTY_25, DUTY_50, NOISE, PULSE_1, TRIANGLE};
+
+// Pattern steps with no note
+const REST: u8 = 0;
+
+// Music plays at this share of its volume while a sound effect is playing
+const DUCK_DIVISOR: u32 = 3;
+
+// Pitches of the octave from middle C, in Hz
+const OCTAVE: [u32; 12] = [262, 277, 294, 311, 330, 349, 370, 392, 415, 440, 466, 494];
+
+// One channel's part of a track
+pub struct Voice {
+    // Notes as MIDI numbers, or REST; loops on its own, so parts of
+    // different lengths repeat at their own pace
+    notes: &'static [u8],
+    flags: u32,
+    volume: u32,
+}
+
+pub struct Track {
+    // Frames each step lasts
+    step: u32,
+    voices: [Voice; 3],
+}
+
+// Where the music is up to
+#[derive(Clone, Copy)]
+pub struct Music {
+    frame: u32,
+    // Frames left of the music being ducked
+    duck: u32,
+}
+
+impl Music {
+    pub const fn new() -> Self {
+        Music { frame: 0, duck: 0 }
+    }
+
+    // Keep the music down for the next `frames` frames
+    pub fn duck(&mut self, frames: u32) {
+        self.duck = self.duck.max(frames);
+    }
+
+    // Start any notes that fall on this frame, then move on a frame
+    pub fn update(&mut self, track: &Track) {
+        if self.frame.is_multiple_of(track.step) {
+            let step = (self.frame / track.step) as usize;
+
+            for voice in &track.voices {
+                let note = voice.notes[step % voice.notes.len()];
+                if note == REST {
+                    continue;
+                }
+
+                let volume =
+                    if self.duck > 0 { voice.volume / DUCK_DIVISOR } else { voice.volume };
+                audio::note(frequency(note), track.step - 1, volume, voice.flags);
+            }
+        }
+
+        self.frame = self.frame.wrapping_add(1);
+        self.duck = self.duck.saturating_sub(1);
+    }
+}
+
+// A MIDI note's pitch in Hz, with 60 as middle C
+fn frequency(note: u8) -> u32 {
+    let pitch = OCTAVE[note as usize % 12];
+    let octave = note as i32 / 12 - 5;
+
+    if octave >= 0 { pitch << octave } else { pitch >> -octave }
+}
+
+// Steps written tracker style, separated by spaces: a note name, a sharp or
+// a dash, and an octave, like "C-4" or "F#2", with "..." for a rest
+const fn pattern<const N: usize>(text: &[u8]) -> [u8; N] {
+    assert!(text.len() + 1 == N * 4, "pattern steps must be three characters each");
+
+    let mut notes = [REST; N];
+    let mut step = 0;
+    while step < N {
+        let (name, sharp, octave) = (text[step * 4], text[step * 4 + 1], text[step * 4 + 2]);
+
+        if name != b'.' {
+            let semitone = match name {
+                b'C' => 0,
+                b'D' => 2,
+                b'E' => 4,
+                b'F' => 5,
+                b'G' => 7,
+                b'A' => 9,
+                b'B' => 11,
+                _ => panic!("unknown note name"),
+            };
+            let sharp = if sharp == b'#' { 1 } else { 0 };
+            notes[step] = (octave - b'0' + 1) * 12 + semitone + sharp;
+        }
+        step += 1;
+    }
+    notes
+}
+
+// Compile a pattern string into a slice of notes
+macro_rules! pattern {
+    ($text:literal) => {{
+        const TEXT: &[u8] = $text;
+        const NOTES: [u8; (TEXT.len() + 1) / 4] = pattern(TEXT);
+        &NOTES
+    }};
+}
+
+const fn lead(notes: &'static [u8]) -> Voice {
+    Voice { notes, flags: PULSE_1 | DUTY_25, volume: 14 }
+}
+
+const fn bass(notes: &'static [u8]) -> Voice {
+    Voice { notes, flags: TRIANGLE, volume: 28 }
+}
+
+const fn drums(notes: &'static [u8]) -> Voice {
+    Voice { notes, flags: NOISE, volume: 8 }
+}
+
+// A steady march in A minor for the first level
+pub const MARCH: Track = Track {
+    step: 8,
+    voices: [
+        lead(pattern!(
+            b"A-4 ... C-5 ... E-5 ... D-5 C-5 B-4 ... G-4 ... ... ... ... ... \
+              A-4 ... C-5 ... B-4 ... A-4 ... G-4 ... E-4 ... ... ... ... ..."
+        )),
+        bass(pattern!(
+            b"A-2 ... A-2 ... E-2 ... E-2 ... F-2 ... F-2 ... G-2 ... G-2 ..."
+        )),
+        drums(pattern!(b"C-3 ... C-7 ... C-5 ... C-7 ...")),
+    ],
+};
+
+// Slower and lower for the second level's halls
+pub const CRYPT: Track = Track {
+    step: 12,
+    voices: [
+        Voice {
+            notes: pattern!(b"D-4 ... ... F-4 ... ... E-4 ... ... C-4 ... ... ... ... ... ..."),
+            flags: PULSE_1 | DUTY_50,
+            volume: 12,
+        },
+        bass(pattern!(b"D-2 ... D-2 ... A#1 ... A#1 ... C-2 ... C-2 ... A-1 ... A-1 ...")),
+        drums(pattern!(b"C-3 ... ... ... C-5 ... ... ...")),
+    ],
+};
+
+// Quick and busy for the last level
+pub const RUSH: Track = Track {
+    step: 6,
+    voices: [
+        lead(pattern!(
+            b"E-5 E-5 ... E-5 ... C-5 E-5 ... G-5 ... ... ... G-4 ... ... ... \
+              C-5 ... G-4 ... E-4 ... A-4 B-4 A#4 A-4 ... G-4 ... ... ... ..."
+        )),
+        bass(pattern!(b"E-2 E-3 E-2 E-3 C-2 C-3 C-2 C-3 D-2 D-3 D-2 D-3 G-1 G-2 G-1 G-2")),
+        drums(pattern!(b"C-3 C-7 C-5 C-7")),
+    ],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_read_as_midi_notes() {
+        let notes: &[u8] = pattern!(b"C-4 ... A#3 A-4");
+        assert_eq!(notes, [60, REST, 58, 69]);
+
+        assert_eq!(frequency(60), 262);
+        assert_eq!(frequency(69), 440);
+        assert_eq!(frequency(45), 110);
+        assert_eq!(frequency(81), 880);
+    }
+}
@@ -0,0 +1,211 @@
+// The options screen: player settings picked with the d-pad, kept on disk
+// with the save and applied to the Config
+use core::f32::consts::PI;
+
+use crate::config::Config;
+use crate::State;
+
+// Turn speeds as a share of the default
+const TURN_SPEEDS: [(f32, &[u8]); 5] =
+    [(0.5, b"1"), (0.75, b"2"), (1.0, b"3"), (1.25, b"4"), (1.5, b"5")];
+
+// Fields of view and their width in degrees
+const FOVS: [(f32, &[u8]); 4] =
+    [(PI / 3.0, b"60"), (PI / 2.7, b"67"), (PI / 2.4, b"75"), (PI / 2.0, b"90")];
+
+// The four colors of each palette as 0xRRGGBB. Shading steps towards the
+// darker end, so they stay ordered lightest to darkest
+pub const PALETTES: [([u32; 4], &[u8]); 3] = [
+    // Bone, sandstone, umber and soot
+    ([0xf0e4c8, 0xb09470, 0x5c4a3c, 0x1c1418], b"SEPIA"),
+    ([0xe0ecc8, 0x88a868, 0x3c5840, 0x101c18], b"MOSS"),
+    ([0xe4ecf4, 0x8ca0b8, 0x465870, 0x141c28], b"STEEL"),
+];
+
+// How far the view reaches through the fog, in tiles
+const FOGS: [(f32, &[u8]); 3] =
+    [(Config::new().fog_distance, b"OFF"), (12.0, b"LIGHT"), (7.0, b"THICK")];
+
+const LABELS: [&[u8]; ROWS] = [b"TURN", b"FOV", b"INVERT", b"BOB", b"COLORS", b"MIRROR", b"FOG"];
+pub const ROWS: usize = 7;
+
+// Bytes the settings take in the save
+pub const SIZE: usize = 7;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Settings {
+    // Indices into TURN_SPEEDS, FOVS, PALETTES and FOGS
+    pub turn_speed: u8,
+    pub fov: u8,
+    pub invert_turn: bool,
+    // Turn off for players who get motion sick from the sway
+    pub view_bob: bool,
+    pub palette: u8,
+    // Show what is behind in a strip over the top of the view
+    pub rear_view: bool,
+    pub fog: u8,
+}
+
+impl Settings {
+    pub const fn new() -> Self {
+        Settings {
+            turn_speed: 2,
+            fov: 1,
+            invert_turn: false,
+            view_bob: true,
+            palette: 0,
+            rear_view: false,
+            fog: 0,
+        }
+    }
+
+    // Carry the settings over to an engine Config
+    pub fn apply(&self, mut config: Config) -> Config {
+        let defaults = Config::new();
+        let scale = TURN_SPEEDS[self.turn_speed as usize].0;
+
+        config.turn_speed = defaults.turn_speed * scale;
+        config.max_turn_speed = defaults.max_turn_speed * scale;
+        config.fov = FOVS[self.fov as usize].0;
+        config.invert_turn = self.invert_turn;
+        config.fog_distance = FOGS[self.fog as usize].0;
+        config
+    }
+
+    pub fn colors(&self) -> [u32; 4] {
+        PALETTES[self.palette as usize].0
+    }
+
+    // Step a row's value forwards or back, wrapping around
+    fn change(&mut self, row: usize, forward: bool) {
+        let step = |value: u8, count: usize| {
+            let count = count as u8;
+            if forward { (value + 1) % count } else { (value + count - 1) % count }
+        };
+
+        match row {
+            0 => self.turn_speed = step(self.turn_speed, TURN_SPEEDS.len()),
+            1 => self.fov = step(self.fov, FOVS.len()),
+            2 => self.invert_turn = !self.invert_turn,
+            3 => self.view_bob = !self.view_bob,
+            4 => self.palette = step(self.palette, PALETTES.len()),
+            5 => self.rear_view = !self.rear_view,
+            _ => self.fog = step(self.fog, FOGS.len()),
+        }
+    }
+
+    // Each row's label and the text for its current value
+    pub fn rows(&self) -> [(&'static [u8], &'static [u8]); ROWS] {
+        let switch = |on: bool| -> &'static [u8] { if on { b"ON" } else { b"OFF" } };
+        let values = [
+            TURN_SPEEDS[self.turn_speed as usize].1,
+            FOVS[self.fov as usize].1,
+            switch(self.invert_turn),
+            switch(self.view_bob),
+            PALETTES[self.palette as usize].1,
+            switch(self.rear_view),
+            FOGS[self.fog as usize].1,
+        ];
+
+        core::array::from_fn(|row| (LABELS[row], values[row]))
+    }
+
+    pub fn encode(&self) -> [u8; SIZE] {
+        [
+            self.turn_speed,
+            self.fov,
+            self.invert_turn as u8,
+            self.view_bob as u8,
+            self.palette,
+            self.rear_view as u8,
+            self.fog,
+        ]
+    }
+
+    // Anything out of range, say from a damaged disk, keeps its default
+    pub fn decode(bytes: &[u8; SIZE]) -> Self {
+        let defaults = Settings::new();
+        let index = |value: u8, count: usize, default: u8| {
+            if (value as usize) < count { value } else { default }
+        };
+
+        Settings {
+            turn_speed: index(bytes[0], TURN_SPEEDS.len(), defaults.turn_speed),
+            fov: index(bytes[1], FOVS.len(), defaults.fov),
+            invert_turn: bytes[2] == 1,
+            view_bob: bytes[3] == 1,
+            palette: index(bytes[4], PALETTES.len(), defaults.palette),
+            rear_view: bytes[5] == 1,
+            fog: index(bytes[6], FOGS.len(), defaults.fog),
+        }
+    }
+}
+
+impl State {
+    // Move through the options with up and down and change them with left and
+    // right; returns true once BUTTON_1 or BUTTON_2 closes the screen
+    pub(crate) fn update_options(&mut self) -> bool {
+        let input = self.input;
+
+        if input.up.pressed() {
+            self.option = (self.option + ROWS - 1) % ROWS;
+        }
+        if input.down.pressed() {
+            self.option = (self.option + 1) % ROWS;
+        }
+        if input.left.pressed() || input.right.pressed() {
+            self.settings.change(self.option, input.right.pressed());
+            self.set_settings(self.settings);
+        }
+
+        self.input.action.take() || input.button_2.tapped()
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    // Switch to new settings, updating the Config to match
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+        self.config = settings.apply(self.config);
+        self.turn_speed = self.config.turn_speed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_wrap_around_and_survive_the_disk() {
+        let mut settings = Settings::new();
+        settings.change(0, true);
+        settings.change(0, true);
+        settings.change(0, true);
+        settings.change(1, false);
+        settings.change(3, true);
+
+        assert_eq!((settings.turn_speed, settings.fov, settings.view_bob), (0, 0, false));
+        assert_eq!(Settings::decode(&settings.encode()), settings);
+
+        // Out of range values fall back to the defaults
+        assert_eq!(Settings::decode(&[9, 9, 0, 1, 9, 0, 9]), Settings::new());
+    }
+
+    #[test]
+    fn settings_reach_the_config() {
+        let mut state = State::new();
+        let mut settings = Settings::new();
+        settings.fov = 3;
+        settings.invert_turn = true;
+        settings.fog = 2;
+        state.set_settings(settings);
+
+        assert_eq!(state.config().fov, PI / 2.0);
+        assert!(state.config().invert_turn);
+        assert_eq!(state.config().fog_distance, 7.0);
+        assert_eq!(state.config().render_distance, Config::new().render_distance);
+        assert_eq!(state.config().turn_speed, Config::new().turn_speed);
+    }
+}
@@ -0,0 +1,120 @@
+// Sparks, debris and blood thrown out where shots land and enemies fall.
+// They scatter across the floor, slowing as they go and stopping dead
+// against walls, and are drawn among the sprites until they fade
+use crate::rng::Rng;
+use crate::sprites::{self, Sprite};
+use crate::State;
+
+pub const MAX_PARTICLES: usize = 24;
+
+// Share of its speed a particle keeps each frame
+const DRAG: f32 = 0.85;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    // Off a wall an enemy's shot hit
+    Spark,
+    // Out of a rocket's burst
+    Debris,
+    // From an enemy as it dies
+    Blood,
+}
+
+impl Kind {
+    // How many are thrown out at once, their top speed along each axis in
+    // tiles per frame, and the frames they last
+    const fn burst(self) -> (u32, f32, u32) {
+        match self {
+            Kind::Spark => (4, 0.06, 10),
+            Kind::Debris => (6, 0.1, 24),
+            Kind::Blood => (5, 0.04, 18),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Particle {
+    x: f32,
+    y: f32,
+    kind: Kind,
+    // Movement per frame
+    step_x: f32,
+    step_y: f32,
+    frames: u32,
+}
+
+impl Particle {
+    pub fn sprite(&self) -> Sprite {
+        let image = match self.kind {
+            Kind::Spark => &sprites::SPARK,
+            Kind::Debris => &sprites::DEBRIS,
+            Kind::Blood => &sprites::BLOOD,
+        };
+        Sprite { x: self.x, y: self.y, image }
+    }
+}
+
+impl State {
+    // Throw out a burst of particles every which way from a point, as many
+    // as there are free slots for. Which ways they go comes from a generator
+    // of their own, as the game's drives enemies, which replays depend on
+    pub(crate) fn spray(&mut self, kind: Kind, x: f32, y: f32) {
+        let (count, speed, frames) = kind.burst();
+        let mut rng = Rng::new(self.stats.frames as u64 * 4 + kind as u64);
+
+        for _ in 0..count {
+            let Some(slot) = self.particles.iter_mut().find(|slot| slot.is_none()) else {
+                return;
+            };
+            let step_x = (rng.next_f32() * 2.0 - 1.0) * speed;
+            let step_y = (rng.next_f32() * 2.0 - 1.0) * speed;
+            *slot = Some(Particle { x, y, kind, step_x, step_y, frames });
+        }
+    }
+
+    pub(crate) fn update_particles(&mut self) {
+        for idx in 0..MAX_PARTICLES {
+            let Some(mut particle) = self.particles[idx] else { continue };
+            let (x, y) = (particle.x + particle.step_x, particle.y + particle.step_y);
+            if self.point_in_wall(x, y) {
+                (particle.step_x, particle.step_y) = (0.0, 0.0);
+            } else {
+                (particle.x, particle.y) = (x, y);
+            }
+            particle.step_x *= DRAG;
+            particle.step_y *= DRAG;
+            particle.frames -= 1;
+
+            self.particles[idx] = (particle.frames > 0).then_some(particle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn particles_scatter_short_of_the_walls_and_fade() {
+        let mut state = State::new();
+
+        // In the corridor a tile below the wall
+        state.spray(Kind::Debris, 5.5, 6.5);
+        state.spray(Kind::Blood, 5.5, 6.5);
+        let count = |state: &State| state.particles.iter().flatten().count();
+        assert_eq!(count(&state), 11);
+
+        for _ in 0..18 {
+            state.update_particles();
+        }
+        for particle in state.particles.iter().flatten() {
+            assert!(!state.point_in_wall(particle.x, particle.y));
+        }
+        assert_eq!(count(&state), 6);
+
+        for _ in 0..6 {
+            state.update_particles();
+        }
+        assert_eq!(count(&state), 0);
+    }
+}
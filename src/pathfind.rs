@@ -0,0 +1,117 @@
+// Bounded A* over the tile grid, so enemies can find their way around walls
+// and through open doors instead of sliding along them. Everything lives in
+// fixed-size arrays on the stack, and a search that grows too big gives up
+use crate::map::{MAX_SIZE, MAX_TILES};
+use crate::State;
+
+// Tiles waiting to be explored at once; neighbours past this are dropped
+const MAX_OPEN: usize = 64;
+
+// Tiles explored before a search gives up on a path too long to bother with
+const MAX_EXPANDED: usize = 256;
+
+const NO_TILE: u16 = u16::MAX;
+
+#[derive(Clone, Copy)]
+struct Open {
+    tile: u16,
+    // Steps taken so far, and those plus the straight-line guess of the
+    // steps left
+    cost: u16,
+    estimate: u16,
+}
+
+fn index(x: i32, y: i32) -> u16 {
+    (y as usize * MAX_SIZE + x as usize) as u16
+}
+
+fn position(tile: u16) -> (i32, i32) {
+    ((tile as usize % MAX_SIZE) as i32, (tile as usize / MAX_SIZE) as i32)
+}
+
+// Steps between two tiles with no walls in the way, never an overestimate
+// when moving only up, down, left and right
+fn manhattan(from: (i32, i32), to: (i32, i32)) -> u16 {
+    ((from.0 - to.0).abs() + (from.1 - to.1).abs()) as u16
+}
+
+impl State {
+    // The neighbouring tile to walk into first on the way from one tile to
+    // another, or None when there is no way through within the search limits.
+    // Tiles count as reached the first time they are seen, which can miss the
+    // very shortest path now and then but keeps the bookkeeping to one array
+    pub(crate) fn next_step(&self, from: (i32, i32), to: (i32, i32)) -> Option<(i32, i32)> {
+        if from == to || self.tile_blocked(to.0, to.1) {
+            return None;
+        }
+
+        let mut came_from = [NO_TILE; MAX_TILES];
+        let mut open = [Open { tile: 0, cost: 0, estimate: 0 }; MAX_OPEN];
+        let (start, goal) = (index(from.0, from.1), index(to.0, to.1));
+
+        came_from[start as usize] = start;
+        open[0] = Open { tile: start, cost: 0, estimate: manhattan(from, to) };
+        let mut len = 1;
+
+        for _ in 0..MAX_EXPANDED {
+            // Take the most promising open tile, with none left meaning no way
+            let best = (0..len).min_by_key(|&idx| open[idx].estimate)?;
+            let current = open[best];
+            len -= 1;
+            open[best] = open[len];
+
+            if current.tile == goal {
+                // Walk back until the tile right after the start
+                let mut tile = goal;
+                while came_from[tile as usize] != start {
+                    tile = came_from[tile as usize];
+                }
+                return Some(position(tile));
+            }
+
+            let (x, y) = position(current.tile);
+            for (x, y) in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+                if !(0..MAX_SIZE as i32).contains(&x) || !(0..MAX_SIZE as i32).contains(&y) {
+                    continue;
+                }
+
+                let tile = index(x, y);
+                if came_from[tile as usize] != NO_TILE || self.tile_blocked(x, y) || len == MAX_OPEN
+                {
+                    continue;
+                }
+
+                came_from[tile as usize] = current.tile;
+                let cost = current.cost + 1;
+                open[len] = Open { tile, cost, estimate: cost + manhattan((x, y), to) };
+                len += 1;
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_lead_around_walls_and_not_through_closed_doors() {
+        let state = State::new();
+
+        // Level 1 has a wall between these, and a closed door below
+        let (mut at, goal) = ((3, 2), (6, 3));
+        assert_eq!(state.next_step(at, goal), Some((3, 1)));
+
+        let mut steps = 0;
+        while at != goal {
+            at = state.next_step(at, goal).unwrap();
+            steps += 1;
+        }
+        assert_eq!(steps, 6);
+
+        assert_eq!(state.next_step(goal, goal), None);
+        assert_eq!(state.next_step(at, (4, 2)), None);
+    }
+}
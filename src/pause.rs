@@ -0,0 +1,42 @@
+// The pause menu, opened mid-level by pressing BUTTON_1 and BUTTON_2
+// together. The game stays frozen behind it until it closes
+use crate::State;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Choice {
+    Resume,
+    Restart,
+    Options,
+    Quit,
+}
+
+pub const ITEMS: [(Choice, &[u8]); 4] = [
+    (Choice::Resume, b"RESUME"),
+    (Choice::Restart, b"RESTART LEVEL"),
+    (Choice::Options, b"OPTIONS"),
+    (Choice::Quit, b"QUIT TO TITLE"),
+];
+
+impl State {
+    // Move through the menu with up and down; returns the item BUTTON_1
+    // picks, or Resume once BUTTON_2 closes the menu
+    pub(crate) fn update_pause(&mut self) -> Option<Choice> {
+        let input = self.input;
+
+        if input.up.pressed() {
+            self.pause_item = (self.pause_item + ITEMS.len() - 1) % ITEMS.len();
+        }
+        if input.down.pressed() {
+            self.pause_item = (self.pause_item + 1) % ITEMS.len();
+        }
+
+        if self.input.action.take() {
+            Some(ITEMS[self.pause_item].0)
+        } else if input.button_2.tapped() {
+            Some(Choice::Resume)
+        } else {
+            None
+        }
+    }
+}
+
@@ -0,0 +1,85 @@
+// Collectible items lying on the map
+use crate::sprites::{self, Sprite};
+use crate::texture::Texture;
+
+pub const MAX_PICKUPS: usize = 16;
+
+// How close the player has to get to grab an item
+pub const RADIUS: f32 = 0.5;
+
+// What each item gives
+pub const HEALTH_AMOUNT: i32 = 25;
+pub const AMMO_AMOUNT: u32 = 10;
+pub const ROCKET_AMOUNT: u32 = 5;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Item {
+    Health,
+    Ammo,
+    Rockets,
+    RapidGun,
+    // Puts the lights out, or back on
+    Flashlight,
+    Key(Key),
+}
+
+// Each key opens the locked doors of its color
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Silver,
+    Brass,
+    Iron,
+}
+
+impl Key {
+    pub const ALL: [Key; 3] = [Key::Silver, Key::Brass, Key::Iron];
+
+    // Flag for this key in State::keys
+    pub const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    // Palette index the key is drawn in
+    pub const fn shade(self) -> u8 {
+        self as u8 + 1
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Pickup {
+    pub x: f32,
+    pub y: f32,
+    pub item: Item,
+}
+
+impl Pickup {
+    fn image(&self) -> &'static Texture {
+        match self.item {
+            Item::Health => &sprites::MEDKIT,
+            Item::Ammo => &sprites::AMMO,
+            Item::Rockets => &sprites::ROCKETS,
+            Item::RapidGun => &sprites::RAPID_GUN,
+            Item::Flashlight => &sprites::FLASHLIGHT,
+            Item::Key(Key::Silver) => &sprites::SILVER_KEY,
+            Item::Key(Key::Brass) => &sprites::BRASS_KEY,
+            Item::Key(Key::Iron) => &sprites::IRON_KEY,
+        }
+    }
+
+    pub fn sprite(&self) -> Sprite {
+        Sprite { x: self.x, y: self.y, image: self.image() }
+    }
+}
+
+// Fill a fresh pool from a level's item placements
+pub const fn spawn(placed: &[Pickup]) -> [Option<Pickup>; MAX_PICKUPS] {
+    let mut pool = [None; MAX_PICKUPS];
+
+    let mut idx = 0;
+    while idx < placed.len() && idx < MAX_PICKUPS {
+        pool[idx] = Some(placed[idx]);
+        idx += 1;
+    }
+
+    pool
+}
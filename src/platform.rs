@@ -0,0 +1,91 @@
+// WASM-4 console interface: memory-mapped registers, imported drawing
+// functions and the panic handler
+#[cfg(target_arch = "wasm32")]
+use core::{arch::wasm32, panic::PanicInfo};
+
+// Import WASM functions
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    pub fn rect(x: i32, y: i32, width: u32, height: u32);
+    pub fn line(x1: i32, y1: i32, x2: i32, y2: i32);
+    pub fn blit(sprite: *const u8, x: i32, y: i32, width: u32, height: u32, flags: u32);
+    pub fn tone(frequency: u32, duration: u32, volume: u32, flags: u32);
+
+    #[link_name = "textUtf8"]
+    fn extern_text(text: *const u8, length: usize, x: i32, y: i32);
+
+    // Read and write the cart's 1024 bytes of persistent storage, returning
+    // how many bytes were transferred
+    pub fn diskr(dest: *mut u8, size: u32) -> u32;
+    pub fn diskw(src: *const u8, size: u32) -> u32;
+}
+
+// Stand-ins for the console's drawing and sound functions on other targets,
+// so the engine links off the console; they draw and play nothing
+#[cfg(not(target_arch = "wasm32"))]
+mod host {
+    pub unsafe fn rect(_x: i32, _y: i32, _width: u32, _height: u32) {}
+    pub unsafe fn line(_x1: i32, _y1: i32, _x2: i32, _y2: i32) {}
+    pub unsafe fn blit(_sprite: *const u8, _x: i32, _y: i32, _w: u32, _h: u32, _flags: u32) {}
+    pub unsafe fn tone(_frequency: u32, _duration: u32, _volume: u32, _flags: u32) {}
+    pub unsafe fn extern_text(_text: *const u8, _length: usize, _x: i32, _y: i32) {}
+    // An empty disk that never takes a write
+    pub unsafe fn diskr(_dest: *mut u8, _size: u32) -> u32 {
+        0
+    }
+    pub unsafe fn diskw(_src: *const u8, _size: u32) -> u32 {
+        0
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+use host::extern_text;
+#[cfg(not(target_arch = "wasm32"))]
+pub use host::{blit, diskr, diskw, line, rect, tone};
+
+// The four palette colors as 0xRRGGBB, lightest first; its address happens
+// to equal the alignment, which clippy mistakes for a dangling pointer
+#[allow(clippy::manual_dangling_ptr)]
+pub const PALETTE: *mut [u32; 4] = 0x04 as *mut [u32; 4];
+
+// Pointers to drawing colors and keyboard state
+pub const DRAW_COLORS: *mut u16 = 0x14 as *mut u16;
+pub const GAMEPAD1: *const u8 = 0x16 as *const u8;
+pub const GAMEPAD2: *const u8 = 0x17 as *const u8;
+pub const GAMEPAD3: *const u8 = 0x18 as *const u8;
+pub const GAMEPAD4: *const u8 = 0x19 as *const u8;
+
+// Mouse position in screen pixels and button state
+pub const MOUSE_X: *const i16 = 0x1a as *const i16;
+pub const MOUSE_BUTTONS: *const u8 = 0x1e as *const u8;
+pub const MOUSE_LEFT: u8 = 1;
+pub const MOUSE_RIGHT: u8 = 2;
+
+// Netplay state, with NETPLAY_ACTIVE set while a session runs; netplay
+// mirrors the gamepads to every peer, but not the mouse or the disk
+pub const NETPLAY: *const u8 = 0x20 as *const u8;
+pub const NETPLAY_ACTIVE: u8 = 0b100;
+
+// Pointer to the 160x160 2bpp framebuffer
+pub const FRAMEBUFFER: *mut u8 = 0xa0 as *mut u8;
+pub const SCREEN_SIZE: i32 = 160;
+
+// Binary masks for GAMEPAD
+pub const BUTTON_1: u8 = 1;       // 0b00000001
+pub const BUTTON_2: u8 = 2;       // 0b00000010
+pub const BUTTON_LEFT: u8 = 16;   // 0b00010000
+pub const BUTTON_RIGHT: u8 = 32;  // 0b00100000
+pub const BUTTON_UP: u8 = 64;     // 0b01000000
+pub const BUTTON_DOWN: u8 = 128;  // 0b10000000
+
+// Draw text with the current DRAW_COLORS
+pub fn text(text: &[u8], x: i32, y: i32) {
+    unsafe { extern_text(text.as_ptr(), text.len(), x, y) }
+}
+
+// Required by #![no_std] to handle panic; carts built on the engine share it
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn phandler(_: &PanicInfo<'_>) -> ! {
+    wasm32::unreachable();
+}
@@ -0,0 +1,286 @@
+// Player movement, collision and the view's horizon
+use core::f32::consts::{PI, TAU};
+
+use crate::map::{circle_overlaps_tile, is_door, is_floor, SECRET_WALL, WATER};
+use crate::math::{abs, cos, floor, sin, sqrt};
+use crate::raycast::Viewport;
+use crate::{audio, GameMode, State};
+
+// How much faster each held frame of a turn gets, in radians per frame; the
+// starting and fastest turn speeds are in the Config
+const TURN_ACCELERATION: f32 = 0.002;
+
+// Walking: speed gained per frame while a direction is held and the share of
+// speed kept per frame once released, in tiles per frame; the top speed is in
+// the Config
+const ACCELERATION: f32 = 0.006;
+const FRICTION: f32 = 0.75;
+// Below this the player is considered stopped
+const STOP_SPEED: f32 = 0.001;
+// Walking into a wall faster than this makes a thud; pressing on against it
+// never builds back up to it
+const BUMP_SPEED: f32 = 0.02;
+
+// View bobbing: pixels of sway, radians of sway per tile walked, and how
+// quickly the sway fades in and out when starting or stopping
+const BOB_AMPLITUDE: f32 = 2.0;
+const BOB_FREQUENCY: f32 = 10.0;
+const BOB_EASE: f32 = 0.1;
+
+// Wading: share of the top speed kept, and pixels the view sinks by
+const WADE_SPEED: f32 = 0.5;
+const WADE_DEPTH: i32 = 4;
+
+// Vertical look: pixels the horizon moves per frame and at most
+const PITCH_SPEED: i32 = 2;
+const MAX_PITCH: i32 = 40;
+
+// Zooming narrows the field of view to this share of the configured one,
+// easing in and out by ZOOM_EASE per frame
+const ZOOM_FOV: f32 = 0.4;
+const ZOOM_EASE: f32 = 0.15;
+
+// Radians turned per pixel of horizontal mouse movement
+const MOUSE_SENSITIVITY: f32 = 0.01;
+
+// How close the camera may get to a wall
+pub const RADIUS: f32 = 0.2;
+
+impl State {
+    // Move the character from this frame's input
+    // Holding BUTTON_2 turns left/right into sideways steps and up/down into
+    // looking up and down
+    pub(crate) fn move_player(&mut self) {
+        let input = self.input;
+        let (up, down) = (input.up.held(), input.down.held());
+        let (left, right) = (input.left.held(), input.right.held());
+
+        let turn = if self.config.invert_turn { -1.0 } else { 1.0 };
+        self.player_angle -= input.mouse_dx as f32 * MOUSE_SENSITIVITY * turn;
+
+        let (dir_x, dir_y) = (cos(self.player_angle), -sin(self.player_angle));
+        let (mut wish_x, mut wish_y) = (0.0, 0.0);
+
+        if input.button_2.held() {
+            // Step along the direction 90° clockwise of the view
+            if right {
+                wish_x -= dir_y;
+                wish_y += dir_x;
+            }
+            if left {
+                wish_x += dir_y;
+                wish_y -= dir_x;
+            }
+
+            // Look up and down by sliding the horizon
+            if up {
+                self.pitch = (self.pitch + PITCH_SPEED).min(MAX_PITCH);
+            }
+            if down {
+                self.pitch = (self.pitch - PITCH_SPEED).max(-MAX_PITCH);
+            }
+        } else {
+            // Move the player
+            if up {
+                wish_x += dir_x;
+                wish_y += dir_y;
+            }
+            if down {
+                wish_x -= dir_x;
+                wish_y -= dir_y;
+            }
+            if right {
+                self.player_angle -= self.turn_speed * turn;
+            }
+            if left {
+                self.player_angle += self.turn_speed * turn;
+            }
+        }
+
+        // Keep the angle small so the trig stays precise however long the
+        // player spins in one direction
+        self.player_angle = normalize_angle(self.player_angle);
+
+        // Turns start slow for fine aiming and build up while held
+        if (left || right) && !input.button_2.held() {
+            self.turn_speed = (self.turn_speed + TURN_ACCELERATION).min(self.config.max_turn_speed);
+        } else {
+            self.turn_speed = self.config.turn_speed;
+        }
+
+        self.accelerate(wish_x, wish_y);
+        let (start_x, start_y) = (self.player_x, self.player_y);
+        let mut blocked = 0.0;
+
+        // Resolve each axis on its own so the player slides along walls,
+        // losing speed on whichever axis is blocked; the spectator camera
+        // goes straight through
+        let solid = self.mode != GameMode::Spectating;
+        if solid && self.collides(self.player_x + self.velocity_x, self.player_y, RADIUS) {
+            blocked = abs(self.velocity_x);
+            self.velocity_x = 0.0;
+        } else {
+            self.player_x += self.velocity_x;
+        }
+        if solid && self.collides(self.player_x, self.player_y + self.velocity_y, RADIUS) {
+            blocked = abs(self.velocity_y).max(blocked);
+            self.velocity_y = 0.0;
+        } else {
+            self.player_y += self.velocity_y;
+        }
+        if blocked > BUMP_SPEED {
+            self.play_sound(&audio::BUMP);
+        }
+
+        let (moved_x, moved_y) = (self.player_x - start_x, self.player_y - start_y);
+        self.update_bob(sqrt(moved_x * moved_x + moved_y * moved_y));
+        self.step_through_portal(start_x, start_y);
+
+        self.zoom = if input.zoom.held() {
+            (self.zoom + ZOOM_EASE).min(1.0)
+        } else {
+            (self.zoom - ZOOM_EASE).max(0.0)
+        };
+    }
+
+    // Speed up towards the held direction, or slide to a stop when nothing is held
+    fn accelerate(&mut self, wish_x: f32, wish_y: f32) {
+        let length = sqrt(wish_x * wish_x + wish_y * wish_y);
+
+        if length == 0.0 {
+            self.velocity_x *= FRICTION;
+            self.velocity_y *= FRICTION;
+
+            if abs(self.velocity_x) + abs(self.velocity_y) < STOP_SPEED {
+                (self.velocity_x, self.velocity_y) = (0.0, 0.0);
+            }
+            return;
+        }
+
+        self.velocity_x += wish_x / length * ACCELERATION;
+        self.velocity_y += wish_y / length * ACCELERATION;
+
+        let speed = sqrt(self.velocity_x * self.velocity_x + self.velocity_y * self.velocity_y);
+        let max_speed = self.config.max_speed * if self.wading() { WADE_SPEED } else { 1.0 };
+        if speed > max_speed {
+            self.velocity_x *= max_speed / speed;
+            self.velocity_y *= max_speed / speed;
+        }
+    }
+
+    // Advance the walk cycle by the distance actually covered this frame,
+    // with a footstep each time the sway crosses the middle
+    fn update_bob(&mut self, moved: f32) {
+        let stride = floor(self.bob_phase / PI);
+        self.bob_phase += moved * BOB_FREQUENCY;
+        if floor(self.bob_phase / PI) != stride {
+            self.play_sound(&audio::FOOTSTEP);
+        }
+        if self.bob_phase > TAU {
+            self.bob_phase -= TAU;
+        }
+
+        self.bob_weight = if moved > 0.0 {
+            (self.bob_weight + BOB_EASE).min(1.0)
+        } else {
+            (self.bob_weight - BOB_EASE).max(0.0)
+        };
+    }
+
+    // Field of view right now, narrowed while zoomed in
+    pub fn fov(&self) -> f32 {
+        self.config.fov * (1.0 - self.zoom * (1.0 - ZOOM_FOV))
+    }
+
+    // Screen row of the horizon across a viewport, shifted by the pitch,
+    // raised while wading, as the view sinks, jolted by any screen shake and
+    // swaying with the walk cycle
+    pub fn horizon(&self, viewport: Viewport) -> i32 {
+        let sunk = if self.wading() { WADE_DEPTH } else { 0 };
+        let shift = self.pitch - sunk + self.shake_offset().0;
+        let horizon = viewport.top + viewport.rows / 2 + shift;
+        if !self.settings.view_bob {
+            return horizon;
+        }
+
+        horizon + (sin(self.bob_phase) * BOB_AMPLITUDE * self.bob_weight) as i32
+    }
+
+    // Check if the player is standing in water
+    pub(crate) fn wading(&self) -> bool {
+        self.map().tile(floor(self.player_x) as i32, floor(self.player_y) as i32) == WATER
+    }
+
+    // Check if a tile can't be walked through
+    pub(crate) fn tile_blocked(&self, tile_x: i32, tile_y: i32) -> bool {
+        if self.push_wall_covers(tile_x, tile_y) {
+            return true;
+        }
+
+        match self.map().tile(tile_x, tile_y) {
+            tile if is_floor(tile) => false,
+            SECRET_WALL => !self.pushed(tile_x, tile_y),
+            tile if is_door(tile) => self.door_open(tile_x, tile_y) < 1.0,
+            _ => true,
+        }
+    }
+
+    // Check if a point is inside a tile that can't be walked through
+    pub fn point_in_wall(&self, x: f32, y: f32) -> bool {
+        self.tile_blocked(floor(x) as i32, floor(y) as i32)
+    }
+
+    // Check if a circle overlaps any blocked tile around it
+    pub fn collides(&self, x: f32, y: f32, radius: f32) -> bool {
+        for tile_y in floor(y - radius) as i32..=floor(y + radius) as i32 {
+            for tile_x in floor(x - radius) as i32..=floor(x + radius) as i32 {
+                if self.tile_blocked(tile_x, tile_y)
+                    && circle_overlaps_tile(x, y, radius, tile_x, tile_y)
+                {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+}
+
+// Wrap an angle into [0, 2π)
+pub fn normalize_angle(angle: f32) -> f32 {
+    let wrapped = angle - floor(angle / TAU) * TAU;
+
+    // Rounding can land exactly on 2π for tiny negative angles
+    if wrapped >= TAU { 0.0 } else { wrapped }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collides_only_near_blocked_tiles() {
+        let state = State::new();
+
+        assert!(!state.collides(1.5, 1.5, RADIUS));
+        assert!(state.collides(1.9, 1.5, RADIUS));
+        assert!(state.collides(1.5, 1.1, RADIUS));
+        assert!(!state.collides(1.5, 2.5, RADIUS));
+    }
+
+    #[test]
+    fn wading_slows_the_player_and_sinks_the_view() {
+        let mut state = State::new();
+        state.load_level(1);
+        let dry = state.horizon(Viewport::FULL);
+
+        // The pool in the corridor below the first rooms
+        (state.player_x, state.player_y) = (3.5, 7.5);
+        assert!(state.wading());
+        for _ in 0..60 {
+            state.accelerate(1.0, 0.0);
+        }
+        assert!(abs(state.velocity_x - state.config.max_speed * WADE_SPEED) < 1e-3);
+        assert_eq!(state.horizon(Viewport::FULL), dry - WADE_DEPTH);
+    }
+}
@@ -0,0 +1,67 @@
+// Linked pairs of portal tiles. Each is a slice across the middle of its
+// tile, like a door's, and the view carries on through one from the same spot
+// in the other, while a player stepping through comes out there too
+use crate::map::PORTAL;
+use crate::math::floor;
+use crate::State;
+
+impl State {
+    // How far it is from a portal tile to the one it is linked to. The first
+    // two portals in the map are linked to each other, and any others, or
+    // one on its own, lead nowhere
+    pub(crate) fn portal_offset(&self, tile_x: i32, tile_y: i32) -> Option<(f32, f32)> {
+        let (a, b) = (self.map().find(PORTAL, 0)?, self.map().find(PORTAL, 1)?);
+        let to = if (tile_x, tile_y) == a {
+            b
+        } else if (tile_x, tile_y) == b {
+            a
+        } else {
+            return None;
+        };
+        Some(((to.0 - tile_x) as f32, (to.1 - tile_y) as f32))
+    }
+
+    // Carry the player over to the other portal if their move from
+    // (from_x, from_y) crossed the slice through one
+    pub(crate) fn step_through_portal(&mut self, from_x: f32, from_y: f32) {
+        let (tile_x, tile_y) = (floor(self.player_x) as i32, floor(self.player_y) as i32);
+        let Some((off_x, off_y)) = self.portal_offset(tile_x, tile_y) else {
+            return;
+        };
+
+        let (from, to, tile) = if self.slice_along_x(tile_x, tile_y) {
+            (from_y, self.player_y, tile_y)
+        } else {
+            (from_x, self.player_x, tile_x)
+        };
+        let middle = tile as f32 + 0.5;
+        if (from - middle) * (to - middle) < 0.0 {
+            self.player_x += off_x;
+            self.player_y += off_y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walking_into_a_portal_comes_out_of_the_other() {
+        let mut state = State::new();
+        state.load_level(2);
+        let (a, b) = (state.map().find(PORTAL, 0).unwrap(), state.map().find(PORTAL, 1).unwrap());
+        assert_eq!(state.portal_offset(b.0, b.1), Some(((a.0 - b.0) as f32, 0.0)));
+
+        // Stopping short of the slice stays put, while crossing it goes over
+        let (x, y) = (a.0 as f32 + 0.5, a.1 as f32 + 0.4);
+        state.player_x = x;
+        state.player_y = y;
+        state.step_through_portal(x, y - 0.1);
+        assert_eq!(state.player_x, x);
+
+        state.player_y = y + 0.2;
+        state.step_through_portal(x, y);
+        assert_eq!((state.player_x, state.player_y), (b.0 as f32 + 0.5, y + 0.2));
+    }
+}
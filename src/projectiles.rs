@@ -0,0 +1,274 @@
+// Shots in flight: enemies' slow shots at the player, and the player's
+// rockets. They travel through the grid until they hit something or run out
+// of time; rockets then burst, hurting every enemy nearby
+use crate::map::{CRACKED_WALL, EMPTY};
+use crate::math::{cos, floor, sin, sqrt};
+use crate::sprites::{self, Sprite};
+use crate::{audio, enemies, particles, weapon, State};
+
+pub const MAX_PROJECTILES: usize = 16;
+
+// How close a shot has to come to hit the player
+const HIT_RADIUS: f32 = 0.3;
+
+// Radians between the shots of a volley
+const VOLLEY_SPREAD: f32 = 0.25;
+
+// Frames before a projectile that hit nothing fizzles out
+const LIFETIME: u32 = 240;
+
+// Rockets hurt enemies within SPLASH_RADIUS tiles of the burst, at full
+// damage within the inner radius and half beyond
+const ROCKET_DAMAGE: i32 = 4;
+const SPLASH_RADIUS: f32 = 1.5;
+const INNER_RADIUS: f32 = 0.6;
+
+// Tiles away enemies hear a burst from
+const BLAST_NOISE: f32 = 10.0;
+
+// Frames a burst stays on screen
+const BLAST_TIME: u32 = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    // An enemy's shot, taking this much health off the player
+    Shot(i32),
+    // The player's rocket, bursting on the first enemy or wall it meets
+    Rocket,
+    // What is left of a rocket for a moment after it bursts
+    Blast,
+}
+
+impl Kind {
+    // Tiles travelled per frame; shots are slow enough to sidestep
+    const fn speed(self) -> f32 {
+        match self {
+            Kind::Shot(_) => 0.06,
+            Kind::Rocket => 0.15,
+            Kind::Blast => 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Projectile {
+    pub x: f32,
+    pub y: f32,
+    pub kind: Kind,
+    // Movement per frame
+    step_x: f32,
+    step_y: f32,
+    frames: u32,
+}
+
+impl Projectile {
+    // A projectile from one point headed straight for another
+    pub fn aimed(kind: Kind, from_x: f32, from_y: f32, to_x: f32, to_y: f32) -> Projectile {
+        let (dx, dy) = (to_x - from_x, to_y - from_y);
+        let length = sqrt(dx * dx + dy * dy).max(f32::EPSILON);
+        let speed = kind.speed();
+
+        Projectile {
+            x: from_x,
+            y: from_y,
+            kind,
+            step_x: dx / length * speed,
+            step_y: dy / length * speed,
+            frames: LIFETIME,
+        }
+    }
+
+    pub fn sprite(&self) -> Sprite {
+        let image = match self.kind {
+            Kind::Shot(_) => &sprites::SHOT,
+            Kind::Rocket => &sprites::ROCKET,
+            Kind::Blast => &weapon::FLASH,
+        };
+        Sprite { x: self.x, y: self.y, image }
+    }
+}
+
+impl State {
+    // Launch a shot, unless the pool is full
+    pub(crate) fn launch(&mut self, projectile: Projectile) {
+        if let Some(slot) = self.projectiles.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some(projectile);
+        }
+    }
+
+    // Launch `count` shots fanned out evenly around the line from one point
+    // to another
+    pub(crate) fn launch_volley(
+        &mut self,
+        kind: Kind,
+        from_x: f32,
+        from_y: f32,
+        to_x: f32,
+        to_y: f32,
+        count: u32,
+    ) {
+        let (dx, dy) = (to_x - from_x, to_y - from_y);
+
+        for idx in 0..count {
+            let turn = (idx as f32 - (count - 1) as f32 / 2.0) * VOLLEY_SPREAD;
+            let (cos, sin) = (cos(turn), sin(turn));
+            let (x, y) = (from_x + dx * cos - dy * sin, from_y + dx * sin + dy * cos);
+            self.launch(Projectile::aimed(kind, from_x, from_y, x, y));
+        }
+    }
+
+    // Fire a rocket down the view direction; returns false when out of
+    // rockets
+    pub(crate) fn fire_rocket(&mut self) -> bool {
+        if self.rockets == 0 {
+            return false;
+        }
+        self.rockets -= 1;
+
+        let (x, y) = (self.player_x, self.player_y);
+        let (to_x, to_y) = (x + cos(self.player_angle), y - sin(self.player_angle));
+        self.launch(Projectile::aimed(Kind::Rocket, x, y, to_x, to_y));
+        self.play_sound(&audio::SHOOT);
+        true
+    }
+
+    // Move every projectile along, removing any that hit something
+    pub(crate) fn update_projectiles(&mut self) {
+        for idx in 0..MAX_PROJECTILES {
+            let Some(mut shot) = self.projectiles[idx] else { continue };
+            shot.x += shot.step_x;
+            shot.y += shot.step_y;
+            shot.frames -= 1;
+
+            self.projectiles[idx] = match shot.kind {
+                Kind::Shot(damage) => self.move_shot(shot, damage),
+                Kind::Rocket => self.move_rocket(shot),
+                Kind::Blast => (shot.frames > 0).then_some(shot),
+            };
+        }
+    }
+
+    fn move_shot(&mut self, shot: Projectile, damage: i32) -> Option<Projectile> {
+        let hits = |x: f32, y: f32| {
+            let (dx, dy) = (shot.x - x, shot.y - y);
+            dx * dx + dy * dy < HIT_RADIUS * HIT_RADIUS
+        };
+
+        // The one hit sees where it came from and feels it in their own view
+        let first_hit = hits(self.player_x, self.player_y);
+        let partner_hit = !first_hit && self.partner.is_some_and(|p| hits(p.x, p.y));
+        if first_hit || partner_hit {
+            if partner_hit {
+                self.swap_partner();
+            }
+            self.indicate_hit(shot.x - shot.step_x, shot.y - shot.step_y);
+            self.hurt(damage);
+            if partner_hit {
+                self.swap_partner();
+            }
+            None
+        } else if self.point_in_wall(shot.x, shot.y) {
+            self.play_sound_at(&audio::SPLASH, shot.x, shot.y);
+            self.spray(particles::Kind::Spark, shot.x - shot.step_x, shot.y - shot.step_y);
+            None
+        } else {
+            (shot.frames > 0).then_some(shot)
+        }
+    }
+
+    fn move_rocket(&mut self, rocket: Projectile) -> Option<Projectile> {
+        let hit_enemy = self.enemies.iter().flatten().any(|enemy| {
+            let (dx, dy) = (enemy.x - rocket.x, enemy.y - rocket.y);
+            dx * dx + dy * dy < enemies::HIT_RADIUS * enemies::HIT_RADIUS
+        });
+        let (tile_x, tile_y) = (floor(rocket.x) as i32, floor(rocket.y) as i32);
+        let hit_wall = self.point_in_wall(rocket.x, rocket.y);
+
+        if !hit_enemy && !hit_wall {
+            return (rocket.frames > 0).then_some(rocket);
+        }
+
+        // Burst just short of the wall, and knock it through if it is cracked
+        let (x, y) = if hit_wall {
+            if self.map.tile(tile_x, tile_y) == CRACKED_WALL {
+                self.map.set_tile(tile_x, tile_y, EMPTY);
+            }
+            (rocket.x - rocket.step_x, rocket.y - rocket.step_y)
+        } else {
+            (rocket.x, rocket.y)
+        };
+        self.burst(x, y);
+        self.spray(particles::Kind::Debris, x, y);
+
+        Some(Projectile { x, y, kind: Kind::Blast, step_x: 0.0, step_y: 0.0, frames: BLAST_TIME })
+    }
+
+    // Hurt every enemy within reach of a burst that it isn't walled off from
+    fn burst(&mut self, x: f32, y: f32) {
+        self.play_sound_at(&audio::BLAST, x, y);
+        self.make_noise(x, y, BLAST_NOISE);
+        self.shake_from_blast(x, y);
+
+        for idx in 0..enemies::MAX_ENEMIES {
+            let Some(enemy) = self.enemies[idx] else { continue };
+            let (dx, dy) = (enemy.x - x, enemy.y - y);
+            let distance = sqrt(dx * dx + dy * dy);
+
+            if distance < SPLASH_RADIUS && self.line_of_sight(x, y, enemy.x, enemy.y) {
+                let damage =
+                    if distance < INNER_RADIUS { ROCKET_DAMAGE } else { ROCKET_DAMAGE / 2 };
+                self.damage_enemy(idx, damage);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enemies::{Archetype, Enemy};
+
+    #[test]
+    fn shots_fly_until_they_hit_the_player_or_a_wall() {
+        let mut state = State::new();
+        state.teleport(8.5, 6.5, 0.0);
+        state.launch(Projectile::aimed(Kind::Shot(8), 4.5, 6.5, 8.5, 6.5));
+
+        for _ in 0..55 {
+            state.update_projectiles();
+        }
+        assert!(state.projectiles[0].is_some());
+        for _ in 0..15 {
+            state.update_projectiles();
+        }
+        assert!(state.projectiles[0].is_none());
+        assert_eq!(state.health, crate::MAX_HEALTH - 8);
+
+        // Straight up into the wall north of the corridor
+        state.launch(Projectile::aimed(Kind::Shot(8), 5.5, 6.5, 5.5, 5.0));
+        for _ in 0..20 {
+            state.update_projectiles();
+        }
+        assert!(state.projectiles[0].is_none());
+        assert_eq!(state.health, crate::MAX_HEALTH - 8);
+    }
+
+    #[test]
+    fn rockets_burst_on_the_first_enemy_and_splash_the_next() {
+        let mut state = State::new();
+        state.teleport(4.5, 6.5, 0.0);
+        state.enemies = [None; enemies::MAX_ENEMIES];
+        state.enemies[0] = Some(Enemy::new(Archetype::Guard, 7.5, 6.5));
+        state.enemies[1] = Some(Enemy::new(Archetype::Guard, 8.5, 6.5));
+        (state.weapon, state.rockets) = (weapon::Weapon::Launcher, 1);
+
+        assert!(state.fire_rocket());
+        for _ in 0..30 {
+            state.update_projectiles();
+        }
+
+        assert!(state.enemies[0].is_none());
+        assert_eq!(state.enemies[1].map(|enemy| enemy.health), Some(1));
+        assert_eq!(state.stats.kills, 1);
+    }
+}
@@ -0,0 +1,173 @@
+// Secret walls that slide back when used, opening the way behind them
+use crate::map::is_floor;
+use crate::math::abs;
+use crate::raycast::Hit;
+use crate::State;
+
+pub const MAX_PUSH_WALLS: usize = 4;
+
+// Tiles slid per frame, and the furthest a wall goes before it stops
+const SPEED: f32 = 1.0 / 48.0;
+const MAX_DISTANCE: i32 = 3;
+
+// A secret wall that has been pushed; it keeps its final position until the
+// level is reloaded
+#[derive(Clone, Copy)]
+pub struct PushWall {
+    // The secret tile it started from and the direction it slides in
+    tile_x: i32,
+    tile_y: i32,
+    step_x: i32,
+    step_y: i32,
+    // Tiles slid so far, and how far it goes in total
+    offset: f32,
+    distance: f32,
+}
+
+impl PushWall {
+    // Top-left corner of the wall block right now
+    fn position(&self) -> (f32, f32) {
+        (
+            self.tile_x as f32 + self.offset * self.step_x as f32,
+            self.tile_y as f32 + self.offset * self.step_y as f32,
+        )
+    }
+
+    // Check if any part of the block is inside a tile
+    fn covers(&self, tile_x: i32, tile_y: i32) -> bool {
+        let (x, y) = self.position();
+        abs(tile_x as f32 - x) < 1.0 && abs(tile_y as f32 - y) < 1.0
+    }
+
+    // Intersect a ray with the sides of the block, wherever it has slid to
+    fn hit(&self, x: f32, y: f32, dir_x: f32, dir_y: f32) -> Option<Hit> {
+        let (left, top) = self.position();
+
+        // Ray lengths to the near and far side of the block on each axis
+        let (near_x, far_x) = slab(x, dir_x, left);
+        let (near_y, far_y) = slab(y, dir_y, top);
+
+        let near = near_x.max(near_y);
+        if near < 0.0 || near > far_x.min(far_y) {
+            return None;
+        }
+
+        // Entering through an east/west side first, like crossing a vertical
+        // grid line
+        let shadow = near_x > near_y;
+        let u = if shadow {
+            let u = y + near * dir_y - top;
+            if dir_x > 0.0 { u } else { 1.0 - u }
+        } else {
+            let u = x + near * dir_x - left;
+            if dir_y > 0.0 { 1.0 - u } else { u }
+        };
+
+        Some(Hit {
+            distance: near,
+            shadow,
+            tile_x: self.tile_x,
+            tile_y: self.tile_y,
+            u: u.clamp(0.0, 0.999),
+        })
+    }
+}
+
+// Ray lengths from `from` to the two sides of a unit span starting at `start`
+fn slab(from: f32, dir: f32, start: f32) -> (f32, f32) {
+    let (a, b) = ((start - from) / dir, (start + 1.0 - from) / dir);
+    if a < b { (a, b) } else { (b, a) }
+}
+
+impl State {
+    // Start a secret wall sliding away from the player, as far as the open
+    // tiles behind it allow
+    pub(crate) fn push_wall(&mut self, tile_x: i32, tile_y: i32) {
+        let (dx, dy) = (tile_x as f32 + 0.5 - self.player_x, tile_y as f32 + 0.5 - self.player_y);
+        let (step_x, step_y) = if abs(dx) > abs(dy) {
+            (if dx > 0.0 { 1 } else { -1 }, 0)
+        } else {
+            (0, if dy > 0.0 { 1 } else { -1 })
+        };
+
+        let mut distance = 0;
+        while distance < MAX_DISTANCE {
+            let next = (tile_x + step_x * (distance + 1), tile_y + step_y * (distance + 1));
+            if !is_floor(self.map().tile(next.0, next.1)) {
+                break;
+            }
+            distance += 1;
+        }
+
+        if distance == 0 {
+            return;
+        }
+
+        let distance = distance as f32;
+        if let Some(slot) = self.push_walls.iter_mut().find(|w| w.is_none()) {
+            *slot = Some(PushWall { tile_x, tile_y, step_x, step_y, offset: 0.0, distance });
+            self.stats.secrets += 1;
+        }
+    }
+
+    pub(crate) fn update_push_walls(&mut self) {
+        for wall in self.push_walls.iter_mut().flatten() {
+            wall.offset = (wall.offset + SPEED).min(wall.distance);
+        }
+    }
+
+    // Check if the secret wall starting in a tile has been pushed
+    pub(crate) fn pushed(&self, tile_x: i32, tile_y: i32) -> bool {
+        self.push_walls.iter().flatten().any(|w| (w.tile_x, w.tile_y) == (tile_x, tile_y))
+    }
+
+    // Check if a sliding wall is in the way anywhere inside a tile
+    pub(crate) fn push_wall_covers(&self, tile_x: i32, tile_y: i32) -> bool {
+        self.push_walls.iter().flatten().any(|w| w.covers(tile_x, tile_y))
+    }
+
+    // Ray hit on a sliding wall that is partly inside the tile the ray just
+    // entered, if any
+    pub(crate) fn push_wall_hit(
+        &self,
+        x: f32,
+        y: f32,
+        tile_x: i32,
+        tile_y: i32,
+        dir_x: f32,
+        dir_y: f32,
+    ) -> Option<Hit> {
+        self.push_walls
+            .iter()
+            .flatten()
+            .filter(|w| w.covers(tile_x, tile_y))
+            .find_map(|w| w.hit(x, y, dir_x, dir_y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pushed_wall_slides_away_and_stops_before_the_next_wall() {
+        // The secret wall at (9, 14) on the first level, pushed from the west
+        // into a corridor three tiles long
+        let mut state = State::new();
+        state.teleport(8.5, 14.5, 0.0);
+        state.push_wall(9, 14);
+
+        for _ in 0..1000 {
+            state.update_push_walls();
+        }
+
+        assert!(state.pushed(9, 14));
+        assert!(!state.push_wall_covers(9, 14) && !state.push_wall_covers(11, 14));
+        assert!(state.push_wall_covers(12, 14));
+
+        // Rays now run through the old spot and hit the wall where it stopped
+        let hit = state.cast_ray(8.5, 14.5, 0.0);
+        assert!((hit.distance - 3.5).abs() < 1e-3);
+        assert_eq!((hit.tile_x, hit.tile_y), (9, 14));
+    }
+}
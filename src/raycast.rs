@@ -0,0 +1,691 @@
+// Grid raycasting from the player's point of view, or any other camera's
+use crate::map::{
+    is_door, is_floor, is_wall, EMPTY, FENCE, LOW_WALL, MIRROR, PORTAL, SECRET_WALL, WINDOW,
+};
+use crate::math::{abs, atan2, cos, floor, sin, sqrt};
+use crate::platform::SCREEN_SIZE;
+use crate::render::VIEW_HEIGHT;
+use crate::State;
+
+// Smallest ray direction component used when casting
+const MIN_RAY_COMPONENT: f32 = 1e-6;
+
+// How far past a mirror or portal a ray carries on from, so a reflected ray
+// sets out from the tile in front
+const HOP_GAP: f32 = 1e-3;
+
+// Result of casting a single ray
+pub struct Hit {
+    pub distance: f32,
+    pub shadow: bool,
+    pub tile_x: i32,
+    pub tile_y: i32,
+    pub u: f32,
+}
+
+// What the ray through one screen column of the view hit
+#[derive(Clone, Copy)]
+pub struct RayHit {
+    // Distance along the ray, and perpendicular to the screen
+    pub distance: f32,
+    pub depth: f32,
+    // Wall slice height in pixels
+    pub height: i32,
+    // East/west faces, drawn in shadow
+    pub side: bool,
+    pub tile_x: i32,
+    pub tile_y: i32,
+    // The tile type, EMPTY if the ray passed the render distance
+    pub tile: u8,
+    // Horizontal texture coordinate across the face, from 0 to 1
+    pub wall_u: f32,
+    // The first fence, window or low wall the ray saw past on the way: its
+    // depth, 0 if none, texture coordinate, tile type and whether it was an
+    // east/west face
+    pub through_depth: f32,
+    pub through_u: f32,
+    pub through_tile: u8,
+    pub through_side: bool,
+}
+
+impl RayHit {
+    // The fence, window or low wall in front of the wall, as a slice of its own
+    pub fn see_through(&self, wall_height: f32) -> Option<RayHit> {
+        (self.through_depth > 0.0).then(|| RayHit {
+            distance: self.through_depth,
+            depth: self.through_depth,
+            height: (wall_height / self.through_depth) as i32,
+            side: self.through_side,
+            tile: self.through_tile,
+            wall_u: self.through_u,
+            ..*self
+        })
+    }
+
+    // Depth of the nearest thing drawn in this column
+    pub fn nearest(&self) -> f32 {
+        if self.through_depth > 0.0 { self.through_depth } else { self.depth }
+    }
+}
+
+// Where a view is seen from: the player's eyes, or a camera fixed somewhere
+// in the level
+#[derive(Clone, Copy)]
+pub struct Camera {
+    pub x: f32,
+    pub y: f32,
+    pub angle: f32,
+    pub fov: f32,
+}
+
+impl Camera {
+    // Distance in pixels from the eye to a flat screen spanning the field of
+    // view
+    pub fn focal_length(&self) -> f32 {
+        focal_length(self.fov)
+    }
+}
+
+// The part of the screen a view is drawn across: all of it above the HUD,
+// half each in split-screen co-op or a quarter in deathmatch. A smaller
+// viewport keeps the focal length, so it shows the middle of the full view
+#[derive(Clone, Copy)]
+pub struct Viewport {
+    pub left: i32,
+    pub columns: usize,
+    pub top: i32,
+    pub rows: i32,
+}
+
+impl Viewport {
+    pub const FULL: Viewport =
+        Viewport { left: 0, columns: SCREEN_SIZE as usize, top: 0, rows: VIEW_HEIGHT };
+
+    // The middle column, counted from the viewport's left edge
+    pub fn center(&self) -> f32 {
+        self.columns as f32 / 2.0
+    }
+
+    // The screen row just below the viewport
+    pub fn bottom(&self) -> i32 {
+        self.top + self.rows
+    }
+}
+
+impl State {
+    // Slices between walls on the left and right run along the X axis
+    pub(crate) fn slice_along_x(&self, tile_x: i32, tile_y: i32) -> bool {
+        is_wall(self.map().tile(tile_x - 1, tile_y)) && is_wall(self.map().tile(tile_x + 1, tile_y))
+    }
+
+    // Doors, fences and portals are drawn as a slice through the middle of
+    // their tile, doors sliding sideways as they open
+    fn slice_hit(
+        &self,
+        x: f32,
+        y: f32,
+        tile_x: i32,
+        tile_y: i32,
+        dir_x: f32,
+        dir_y: f32,
+    ) -> Option<Hit> {
+        let along_x = self.slice_along_x(tile_x, tile_y);
+        let (distance, offset, tile) = if along_x {
+            let distance = (tile_y as f32 + 0.5 - y) / dir_y;
+            (distance, x + distance * dir_x, tile_x)
+        } else {
+            let distance = (tile_x as f32 + 0.5 - x) / dir_x;
+            (distance, y + distance * dir_y, tile_y)
+        };
+
+        // The ray has to cross the slice itself, and not a door's open part
+        let u = offset - tile as f32 - self.door_open(tile_x, tile_y);
+        if !(distance >= 0.0 && (0.0..1.0).contains(&u) && floor(offset) as i32 == tile) {
+            return None;
+        }
+
+        Some(Hit { distance, shadow: !along_x, tile_x, tile_y, u })
+    }
+
+    // Walk the grid cell by cell along a ray from (x, y) until it enters a
+    // wall or passes the render distance
+    pub fn cast_ray(&self, x: f32, y: f32, angle: f32) -> Hit {
+        self.cast_ray_along(x, y, ray_direction(angle))
+    }
+
+    // Same as cast_ray, for a unit direction instead of an angle. Rays pass
+    // through fences and windows and over low walls, which only block
+    // movement
+    pub fn cast_ray_along(&self, x: f32, y: f32, dir: (f32, f32)) -> Hit {
+        self.cast_ray_through(x, y, dir).0
+    }
+
+    // Same as cast_ray_along, also giving where the ray went past the first
+    // fence, window or low wall on its way, if it did
+    pub fn cast_ray_through(&self, x: f32, y: f32, dir: (f32, f32)) -> (Hit, Option<Hit>) {
+        self.cast_ray_within(x, y, dir, self.config.render_distance)
+    }
+
+    // How far the view's rays reach, no further than the fog lets them
+    pub(crate) fn view_distance(&self) -> f32 {
+        self.config.render_distance.min(self.config.fog_distance)
+    }
+
+    // Same as cast_ray_through, giving up past `reach` tiles
+    fn cast_ray_within(
+        &self,
+        x: f32,
+        y: f32,
+        (dir_x, dir_y): (f32, f32),
+        reach: f32,
+    ) -> (Hit, Option<Hit>) {
+        let (dir_x, dir_y) = (nudge(dir_x), nudge(dir_y));
+        let mut through = None;
+
+        let mut tile_x = floor(x) as i32;
+        let mut tile_y = floor(y) as i32;
+
+        // Ray length needed to cross one whole cell on each axis
+        let delta_x = abs(1.0 / dir_x);
+        let delta_y = abs(1.0 / dir_y);
+
+        // Ray length to the next vertical and horizontal grid line
+        let (step_x, mut side_x) = if dir_x < 0.0 {
+            (-1, (x - tile_x as f32) * delta_x)
+        } else {
+            (1, (tile_x as f32 + 1.0 - x) * delta_x)
+        };
+        let (step_y, mut side_y) = if dir_y < 0.0 {
+            (-1, (y - tile_y as f32) * delta_y)
+        } else {
+            (1, (tile_y as f32 + 1.0 - y) * delta_y)
+        };
+
+        loop {
+            // Crossing a vertical grid line hits an east/west face
+            let shadow = side_x < side_y;
+
+            let distance = if shadow {
+                tile_x += step_x;
+                side_x += delta_x;
+                side_x - delta_x
+            } else {
+                tile_y += step_y;
+                side_y += delta_y;
+                side_y - delta_y
+            };
+
+            if distance > reach {
+                let hit = Hit { distance: reach, shadow, tile_x, tile_y, u: 0.0 };
+                return (hit, through);
+            }
+
+            if let Some(hit) = self.push_wall_hit(x, y, tile_x, tile_y, dir_x, dir_y) {
+                return (hit, through);
+            }
+
+            let tile = self.map().tile(tile_x, tile_y);
+            match tile {
+                // Doors and portals stop the ray, while it carries on past
+                // fences
+                tile if is_door(tile) || matches!(tile, FENCE | PORTAL) => {
+                    match self.slice_hit(x, y, tile_x, tile_y, dir_x, dir_y) {
+                        Some(hit) if tile != FENCE => return (hit, through),
+                        hit => through = through.or(hit),
+                    }
+                    continue;
+                }
+                tile if is_floor(tile) => continue,
+                SECRET_WALL if self.pushed(tile_x, tile_y) => continue,
+                _ => {}
+            }
+
+            let u = if shadow {
+                let hit_y = y + distance * dir_y;
+                let u = hit_y - floor(hit_y);
+                if step_x > 0 { u } else { 1.0 - u }
+            } else {
+                let hit_x = x + distance * dir_x;
+                let u = hit_x - floor(hit_x);
+                if step_y > 0 { 1.0 - u } else { u }
+            };
+
+            // Windows and low walls have a face like any wall's, seen through
+            // or over
+            let hit = Hit { distance, shadow, tile_x, tile_y, u };
+            if matches!(tile, WINDOW | LOW_WALL) {
+                through = through.or(Some(hit));
+                continue;
+            }
+            return (hit, through);
+        }
+    }
+
+    // Same as cast_ray_through, bouncing once off a mirror or going once
+    // through a portal, as the view's rays do. The mirror or portal is seen
+    // through to what lies beyond unless something already was before it,
+    // and whatever shows past fences there is left out, as sprites are, being
+    // drawn only where they stand
+    fn cast_view_ray(&self, x: f32, y: f32, (dir_x, dir_y): (f32, f32)) -> (Hit, Option<Hit>) {
+        let reach = self.view_distance();
+        let (hit, through) = self.cast_ray_within(x, y, (dir_x, dir_y), reach);
+        if hit.distance >= reach {
+            return (hit, through);
+        }
+
+        let (mut from_x, mut from_y) = (x + hit.distance * dir_x, y + hit.distance * dir_y);
+        let (on_x, on_y) = match self.map().tile(hit.tile_x, hit.tile_y) {
+            // East/west faces turn the ray back along X, and the others along Y
+            MIRROR if hit.shadow => (-dir_x, dir_y),
+            MIRROR => (dir_x, -dir_y),
+            // Portals send it on the same way from the same spot in the other
+            PORTAL => match self.portal_offset(hit.tile_x, hit.tile_y) {
+                Some((off_x, off_y)) => {
+                    (from_x, from_y) = (from_x + off_x, from_y + off_y);
+                    (dir_x, dir_y)
+                }
+                None => return (hit, through),
+            },
+            _ => return (hit, through),
+        };
+        let from = (from_x + HOP_GAP * on_x, from_y + HOP_GAP * on_y);
+        let (next, _) = self.cast_ray_within(from.0, from.1, (on_x, on_y), reach);
+
+        let distance = hit.distance + HOP_GAP + next.distance;
+        (Hit { distance, ..next }, through.or(Some(hit)))
+    }
+
+    // Check if nothing blocks the straight line between two points
+    pub fn line_of_sight(&self, from_x: f32, from_y: f32, to_x: f32, to_y: f32) -> bool {
+        let (dx, dy) = (to_x - from_x, to_y - from_y);
+        let hit = self.cast_ray(from_x, from_y, atan2(-dy, dx));
+
+        hit.distance * hit.distance >= dx * dx + dy * dy
+    }
+
+    // The player's eyes, through the current field of view and turned by
+    // any screen shake
+    pub fn camera(&self) -> Camera {
+        let angle = self.player_angle + self.shake_offset().1;
+        Camera { x: self.player_x, y: self.player_y, angle, fov: self.fov() }
+    }
+
+    // Pixel height of a wall one tile away, magnified by a camera narrower
+    // than the configured field of view, as when zoomed in
+    pub fn projected_wall_height(&self, camera: Camera) -> f32 {
+        self.config.wall_height * camera.focal_length() / focal_length(self.config.fov)
+    }
+
+    // Unit direction of the ray cast through every screen column, and the
+    // cosine between it and the view direction. Columns are spread evenly
+    // across a flat screen rather than by equal angles, so correcting
+    // distances by that cosine keeps walls straight at any field of view.
+    // The table is rebuilt each frame from the live field of view, which
+    // costs one sine and cosine pair for the focal length and one for the
+    // view direction. Only the viewport's columns are filled in, from its
+    // left edge
+    pub fn column_rays(&self, camera: Camera, viewport: Viewport) -> [(f32, f32, f32); 160] {
+        let (cos_a, sin_a) = (cos(camera.angle), sin(camera.angle));
+        let focal = camera.focal_length();
+        let mut rays = [(0.0, 0.0, 0.0); 160];
+
+        for (column, ray) in rays.iter_mut().take(viewport.columns).enumerate() {
+            let offset = viewport.center() - column as f32;
+            let length = sqrt(focal * focal + offset * offset);
+
+            // Forward is (cos, -sin) in map space, and screen left is (-sin, -cos)
+            let dir_x = (focal * cos_a - offset * sin_a) / length;
+            let dir_y = (-focal * sin_a - offset * cos_a) / length;
+            *ray = (dir_x, dir_y, focal / length);
+        }
+
+        rays
+    }
+
+    // Returns what every column of the viewport hit, from its left edge;
+    // columns past the render distance or the viewport are left open, with
+    // no height and an empty tile
+    pub fn get_view(&self, camera: Camera, viewport: Viewport) -> [RayHit; 160] {
+        self.get_view_along(camera, viewport, &self.column_rays(camera, viewport))
+    }
+
+    // Same as get_view, down rays already worked out by column_rays
+    pub fn get_view_along(
+        &self,
+        camera: Camera,
+        viewport: Viewport,
+        rays: &[(f32, f32, f32); 160],
+    ) -> [RayHit; 160] {
+        let open = RayHit {
+            distance: 0.0,
+            depth: 0.0,
+            height: 0,
+            side: false,
+            tile_x: 0,
+            tile_y: 0,
+            tile: EMPTY,
+            wall_u: 0.0,
+            through_depth: 0.0,
+            through_u: 0.0,
+            through_tile: EMPTY,
+            through_side: false,
+        };
+        let mut walls = [open; 160];
+        let wall_height = self.projected_wall_height(camera);
+
+        for (wall, &(dir_x, dir_y, fisheye)) in walls.iter_mut().zip(&rays[..viewport.columns]) {
+            let (hit, through) = self.cast_view_ray(camera.x, camera.y, (dir_x, dir_y));
+            let depth = hit.distance * fisheye;
+            let (tile_x, tile_y) = (hit.tile_x, hit.tile_y);
+
+            *wall = if hit.distance >= self.view_distance() {
+                RayHit { distance: hit.distance, depth, tile_x, tile_y, ..open }
+            } else {
+                RayHit {
+                    distance: hit.distance,
+                    depth,
+                    height: (wall_height / depth) as i32,
+                    side: hit.shadow,
+                    tile_x,
+                    tile_y,
+                    tile: self.map().tile(tile_x, tile_y),
+                    wall_u: hit.u,
+                    ..open
+                }
+            };
+
+            if let Some(through) = through {
+                wall.through_depth = through.distance * fisheye;
+                wall.through_u = through.u;
+                wall.through_tile = self.map().tile(through.tile_x, through.tile_y);
+                wall.through_side = through.shadow;
+            }
+        }
+
+        walls
+    }
+}
+
+fn focal_length(fov: f32) -> f32 {
+    let half_fov = fov * 0.5;
+    (SCREEN_SIZE / 2) as f32 * cos(half_fov) / sin(half_fov)
+}
+
+// Unit direction of a ray, with neither component exactly zero so rays along
+// the grid axes never divide by zero
+pub fn ray_direction(angle: f32) -> (f32, f32) {
+    (nudge(cos(angle)), nudge(-sin(angle)))
+}
+
+fn nudge(component: f32) -> f32 {
+    if abs(component) >= MIN_RAY_COMPONENT {
+        component
+    } else if component < 0.0 {
+        -MIN_RAY_COMPONENT
+    } else {
+        MIN_RAY_COMPONENT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::f32::consts::PI;
+
+    use super::*;
+    use crate::config::Config;
+    use crate::enemies::{Archetype, Enemy, MAX_ENEMIES};
+    use crate::weapon::Weapon;
+
+    fn close(a: f32, b: f32) -> bool {
+        abs(a - b) < 0.01
+    }
+
+    #[test]
+    fn cast_ray_stops_at_nearest_wall() {
+        let state = State::new();
+
+        // The spawn cell is walled in to the east and north, and open for two
+        // cells to the south
+        let east = state.cast_ray(1.5, 1.5, 0.0);
+        assert!(close(east.distance, 0.5));
+        assert_eq!((east.tile_x, east.tile_y), (2, 1));
+        assert!(east.shadow);
+
+        let north = state.cast_ray(1.5, 1.5, PI / 2.0);
+        assert!(close(north.distance, 0.5));
+        assert_eq!((north.tile_x, north.tile_y), (1, 0));
+        assert!(!north.shadow);
+
+        let south = state.cast_ray(1.5, 1.5, -PI / 2.0);
+        assert!(close(south.distance, 2.5));
+        assert_eq!((south.tile_x, south.tile_y), (1, 4));
+    }
+
+    #[test]
+    fn cast_ray_texture_coordinate_follows_hit_point() {
+        let state = State::new();
+
+        // Slightly left of straight ahead lands just short of the middle of
+        // the face
+        let hit = state.cast_ray(1.5, 1.5, 0.1);
+        assert!(hit.u > 0.4 && hit.u < 0.5);
+    }
+
+    #[test]
+    fn get_view_has_no_fisheye_on_a_flat_wall() {
+        let mut state = State::new();
+        state.player_angle = PI / 2.0;
+
+        // Every column looking north from the spawn hits the flat top wall of
+        // the map at the same perpendicular depth, give or take rounding
+        let view = state.get_view(state.camera(), Viewport::FULL);
+        for wall in view.iter() {
+            assert!(close(wall.depth, 0.5));
+            assert!((wall.height - view[80].height).abs() <= 1);
+        }
+    }
+
+    #[test]
+    fn get_view_has_no_fisheye_at_narrow_and_wide_fov() {
+        for fov in [0.3, 2.0] {
+            let mut state = State::with_config(Config::new().with_fov(fov));
+            (state.player_x, state.player_angle) = (4.5, PI / 2.0);
+
+            // Four open tiles wide, so even the wide view only sees the top
+            // wall of the map
+            let view = state.get_view(state.camera(), Viewport::FULL);
+            for wall in view.iter() {
+                assert!(close(wall.depth, 0.5));
+                assert!((wall.height - view[80].height).abs() <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn zooming_narrows_the_view_and_magnifies_walls() {
+        let mut state = State::new();
+        state.player_angle = PI / 2.0;
+        let full = Viewport::FULL;
+        let camera = state.camera();
+        let edge = state.column_rays(camera, full)[0].2;
+        let height = state.get_view(camera, full)[80].height;
+
+        // The edge column's ray bends closer to the view direction
+        state.zoom = 1.0;
+        let camera = state.camera();
+        assert!(state.column_rays(camera, full)[0].2 > edge);
+        assert!(state.get_view(camera, full)[80].height > height);
+    }
+
+    #[test]
+    fn rays_see_through_fences_to_the_wall_behind() {
+        let mut state = State::new();
+        state.load_level(3);
+
+        // The fence across the boss arena stands in the middle of its tile
+        let (wall, fence) = state.cast_ray_through(1.5, 1.5, ray_direction(0.0));
+        let fence = fence.unwrap();
+        assert_eq!((fence.tile_x, fence.tile_y), (6, 1));
+        assert!(close(fence.distance, 5.0));
+        assert_eq!((wall.tile_x, wall.tile_y), (18, 1));
+
+        // The view keeps both, and the fence still blocks the way
+        let view = state.get_view(Camera { x: 1.5, y: 1.5, angle: 0.0, fov: 1.0 }, Viewport::FULL);
+        assert!(close(view[80].through_depth, 5.0) && close(view[80].depth, 16.5));
+        assert!(state.tile_blocked(6, 1));
+    }
+
+    #[test]
+    fn rays_see_through_windows_to_the_yard_outside() {
+        let mut state = State::new();
+        state.load_level(1);
+
+        let (wall, window) = state.cast_ray_through(3.5, 15.5, ray_direction(-PI / 2.0));
+        let window = window.unwrap();
+        assert_eq!(state.map().tile(window.tile_x, window.tile_y), WINDOW);
+        assert!(close(window.distance, 0.5) && close(wall.distance, 3.5));
+    }
+
+    #[test]
+    fn rays_see_over_low_walls_but_players_cannot_cross_them() {
+        let mut state = State::new();
+        state.load_level(2);
+
+        let (wall, low) = state.cast_ray_through(4.5, 3.5, ray_direction(-PI / 2.0));
+        let low = low.unwrap();
+        assert_eq!((low.tile_x, low.tile_y, wall.tile_x, wall.tile_y), (4, 4, 4, 5));
+        assert_eq!(state.map().tile(4, 4), LOW_WALL);
+        assert!(state.tile_blocked(4, 4));
+    }
+
+    #[test]
+    fn mirrors_show_the_view_turned_back() {
+        let mut state = State::new();
+        state.load_level(1);
+
+        // The mirror closing off the corridor east, half a tile ahead, shows
+        // the corridor back to its western end
+        let camera = Camera { x: 14.5, y: 13.5, angle: 0.0, fov: 1.0 };
+        let view = state.get_view(camera, Viewport::FULL);
+        assert_eq!((view[80].through_tile, view[80].tile_x, view[80].tile_y), (MIRROR, 0, 13));
+        assert!(close(view[80].through_depth, 0.5) && close(view[80].depth, 14.5));
+
+        // Shots and sight lines go no further than the glass
+        assert!(close(state.cast_ray(14.5, 13.5, 0.0).distance, 0.5));
+    }
+
+    #[test]
+    fn portals_show_the_view_from_the_other_one() {
+        let mut state = State::new();
+        state.load_level(2);
+
+        // Looking south into the portal below the spawn comes out of the one
+        // in the far corner, facing down its corridor
+        let camera = Camera { x: 1.5, y: 1.5, angle: -PI / 2.0, fov: 1.0 };
+        let view = state.get_view(camera, Viewport::FULL);
+        assert_eq!((view[80].through_tile, view[80].tile_x, view[80].tile_y), (PORTAL, 18, 6));
+        assert!(close(view[80].through_depth, 1.0) && close(view[80].depth, 4.5));
+    }
+
+    #[test]
+    fn get_view_leaves_columns_past_render_distance_open() {
+        let mut state = State::with_config(Config::new().with_render_distance(4.0));
+        (state.player_x, state.player_y) = (12.5, 6.5);
+
+        // The corridor runs ten tiles east, so the middle of the view is open
+        // while the walls right beside the player still show
+        let view = state.get_view(state.camera(), Viewport::FULL);
+        assert_eq!((view[80].height, view[80].tile), (0, EMPTY));
+        assert!(view[0].height > 0 && view[159].height > 0);
+    }
+
+    #[test]
+    fn fog_hides_the_far_end_but_sight_and_shots_reach_it() {
+        let mut state = State::with_config(Config { fog_distance: 7.0, ..Config::new() });
+        state.load_level(1);
+        state.teleport(1.5, 18.5, 0.0);
+        state.enemies = [None; MAX_ENEMIES];
+        state.enemies[0] = Some(Enemy::new(Archetype::Guard, 14.5, 18.5));
+
+        let view = state.get_view(state.camera(), Viewport::FULL);
+        assert_eq!((view[80].height, view[80].tile), (0, EMPTY));
+        assert!(state.line_of_sight(1.5, 18.5, 14.5, 18.5));
+
+        state.fire_bullet(Weapon::Pistol.spec());
+        assert!(state.enemies[0].unwrap().health < Archetype::Guard.stats().health);
+    }
+
+    #[test]
+    fn half_viewports_show_the_middle_of_the_full_view() {
+        let mut state = State::new();
+        state.teleport(12.5, 6.5, 0.3);
+
+        let camera = state.camera();
+        let full = state.get_view(camera, Viewport::FULL);
+        let half = state.get_view(camera, Viewport { left: 80, columns: 80, ..Viewport::FULL });
+        for (column, wall) in half.iter().enumerate() {
+            let expected = if column < 80 { full[40 + column].height } else { 0 };
+            assert_eq!(wall.height, expected);
+        }
+    }
+
+    // Known viewpoints as (name, level, x, y, angle), each with a stored
+    // snapshot of the column heights and shadows it renders
+    const SNAPSHOTS: [(&str, usize, f32, f32, f32); 6] = [
+        ("spawn", 0, 1.5, 1.5, 0.0),
+        ("long_corridor", 0, 12.5, 6.5, 0.0),
+        ("facing_door", 0, 3.5, 3.2, -PI / 2.0),
+        ("diagonal", 0, 14.5, 3.5, PI / 4.0),
+        ("pillar_hall", 1, 7.5, 16.5, 1.3),
+        ("off_grid", 2, 10.3, 9.7, 2.9),
+    ];
+
+    // One `height shadow` line per screen column
+    fn snapshot(state: &State) -> String {
+        let line = |wall: &RayHit| format!("{} {}\n", wall.height, wall.side as u8);
+        state.get_view(state.camera(), Viewport::FULL).iter().map(line).collect()
+    }
+
+    fn parse_column(line: &str) -> (i32, bool) {
+        let (height, shadow) = line.split_once(' ').unwrap();
+        (height.parse().unwrap(), shadow == "1")
+    }
+
+    // The snapshots are stored from the libm build; fixed-point rounding may
+    // still land a column one pixel shorter or taller
+    const TOLERANCE: i32 = if cfg!(feature = "fixed-point") { 1 } else { 0 };
+
+    // Run with UPDATE_SNAPSHOTS=1 to rewrite the stored snapshots after an
+    // intended change to the output
+    #[test]
+    fn get_view_matches_snapshots() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/snapshots");
+        let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+
+        for (name, level, x, y, angle) in SNAPSHOTS {
+            let mut state = State::new();
+            state.load_level(level);
+            (state.player_x, state.player_y, state.player_angle) = (x, y, angle);
+
+            let path = format!("{dir}/{name}.txt");
+            let actual = snapshot(&state);
+
+            if update {
+                std::fs::create_dir_all(dir).unwrap();
+                std::fs::write(&path, &actual).unwrap();
+                continue;
+            }
+
+            let expected = std::fs::read_to_string(&path)
+                .unwrap_or_else(|_| panic!("missing snapshot {path}, run with UPDATE_SNAPSHOTS=1"));
+            assert_eq!(actual.lines().count(), expected.lines().count(), "view {name}");
+
+            for (column, (actual, expected)) in actual.lines().zip(expected.lines()).enumerate() {
+                let (actual, expected) = (parse_column(actual), parse_column(expected));
+                let matches =
+                    actual.1 == expected.1 && (actual.0 - expected.0).abs() <= TOLERANCE;
+                assert!(matches, "view {name} column {column}: {actual:?} vs {expected:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,549 @@
+// Drawing the 3D view and the full-screen overlays into the framebuffer
+use core::f32::consts::{PI, TAU};
+
+use crate::flashlight::Light;
+use crate::lobby::Rules;
+use crate::map::{
+    is_see_through, wall_height, BRASS_DOOR, CRACKED_WALL, DOOR, EXIT, FENCE, HAZARD, IRON_DOOR,
+    LAVA, LOW_WALL, METAL_WALL, MIRROR, MONITOR, OUTDOOR, PILLAR, PORTAL, SILVER_DOOR, STONE_WALL,
+    TORCH_WALL, WATER, WINDOW,
+};
+use crate::math::{abs, atan2, floor, sin};
+use crate::options::Settings;
+use crate::platform::{rect, text, DRAW_COLORS, FRAMEBUFFER, SCREEN_SIZE};
+use crate::raycast::{Camera, RayHit, Viewport};
+use crate::save::{Leaderboard, Record, MAX_LEVELS};
+use crate::sprites::{self, Sprite};
+use crate::texture::{self, Id};
+use crate::{decals, hud, lighting, pause, LevelStats, State};
+
+// Rows of the 3D view, letterboxed above the HUD
+pub const VIEW_HEIGHT: i32 = SCREEN_SIZE - hud::HEIGHT;
+
+// Walls fade one dithered half-shade per band past SHADE_START
+const SHADE_START: f32 = 4.0;
+const SHADE_BAND: f32 = 3.0;
+const SHADE_BANDS: u8 = 3;
+
+// Half-shades of fog that take the lightest color down to the darkest,
+// thickening over the far half of the way the light reaches
+const FOG_SHADES: f32 = 6.0;
+
+// Border drawn right after taking damage, in pixels
+const DAMAGE_FLASH_WIDTH: i32 = 3;
+
+// Rows darkened at the bottom of the view while standing on a hazard
+const HAZARD_TINT_HEIGHT: i32 = 12;
+
+// Thresholds for dithering a share of a band's pixels to dark, out of 4
+const BAYER: [[i32; 2]; 2] = [[0, 2], [3, 1]];
+
+// The sideways wobble of the view while wading: pixels of sway, radians of
+// wave per row, and frames for a wave to roll past
+const RIPPLE_AMPLITUDE: f32 = 2.5;
+const RIPPLE_FREQUENCY: f32 = 0.2;
+const RIPPLE_PERIOD: u32 = 48;
+
+// Times the sky wraps around the horizon, and the rows it takes up above it
+const SKY_REPEATS: f32 = 4.0;
+const SKY_ROWS: f32 = 64.0;
+
+// Set a single framebuffer pixel to a palette index
+pub(crate) unsafe fn set_pixel(x: i32, y: i32, color: u8) {
+    let idx = (y * SCREEN_SIZE + x) as usize;
+    let shift = (idx % 4) * 2;
+    let byte = FRAMEBUFFER.add(idx / 4);
+
+    *byte = (*byte & !(0b11 << shift)) | (color << shift);
+}
+
+// Get the palette index of a single framebuffer pixel
+unsafe fn get_pixel(x: i32, y: i32) -> u8 {
+    let idx = (y * SCREEN_SIZE + x) as usize;
+    *FRAMEBUFFER.add(idx / 4) >> ((idx % 4) * 2) & 0b11
+}
+
+// Half-shades of fog at a distance, up to all of them where the light gives
+// out
+fn fog(distance: f32, reach: f32) -> u8 {
+    if distance >= reach {
+        return FOG_SHADES as u8;
+    }
+    ((distance / reach * 2.0 - 1.0) * FOG_SHADES).max(0.0) as u8
+}
+
+// Whole shades for a count of half-shades, checkerboard dithered between them
+fn dither(halves: u8, x: i32, y: i32) -> u8 {
+    halves / 2 + (halves % 2 == 1 && (x + y) % 2 == 0) as u8
+}
+
+// Extra shades of darkness for a wall seen at a distance, on a tile with the
+// given light level
+fn distance_shade(distance: f32, reach: f32, light: u8, x: i32, y: i32) -> u8 {
+    let band = (((distance - SHADE_START) / SHADE_BAND).max(0.0) as u8).min(SHADE_BANDS);
+    dither(band.max(fog(distance, reach)) + lighting::BRIGHT - light, x, y)
+}
+
+// Extra shades of darkness for the floor, ceiling or a sprite seen at a
+// distance, from the fog and the light alone
+pub(crate) fn fog_shade(distance: f32, reach: f32, light: u8, x: i32, y: i32) -> u8 {
+    dither(fog(distance, reach) + lighting::BRIGHT - light, x, y)
+}
+
+// Palette remaps for each wall type, lit and in shadow, laid out like
+// DRAW_COLORS: nibble n holds one plus the palette index for texture color n
+const fn wall_colors(tile: u8) -> (u16, u16) {
+    match tile {
+        STONE_WALL | LOW_WALL => (0x3211, 0x4322),
+        METAL_WALL | PILLAR => (0x2341, 0x3441),
+        _ => (0x4321, 0x4432),
+    }
+}
+
+// Draw a textured wall slice standing on the floor, down one column of a
+// viewport. A full wall is centered on the horizon, and others are taller or
+// shorter from the same foot, the texture keeping its scale. Only a slice
+// seen through leaves the blank parts of its texture open, as one a view ray
+// ends on has nothing behind it to show
+unsafe fn draw_wall_column(
+    state: &State,
+    reach: f32,
+    x: i32,
+    horizon: i32,
+    wall: &RayHit,
+    see_through: bool,
+    viewport: Viewport,
+) {
+    let full = wall.height;
+    let height = (full as f32 * wall_height(wall.tile)) as i32;
+    let top = horizon + full / 2 - height;
+    let image = state.textures.animated(wall_texture(wall.tile), state.stats.frames);
+    let light = state.lighting.level(wall.tile_x, wall.tile_y);
+    let (lit, shadow) = wall_colors(wall.tile);
+    let colors = if wall.side { shadow } else { lit };
+    // Slices seen through carry the tile of the wall behind them, and its
+    // holes with it
+    let hole = if see_through { None } else { state.bullet_hole(wall) };
+
+    for y in top.max(viewport.top)..(top + height).min(viewport.bottom()) {
+        let v = (y - top) as f32 / full as f32;
+        let color = texture::sample(image, wall.wall_u, v);
+        // Fences and windows are seen through where their texture is blank
+        if color == 0 && see_through && is_see_through(wall.tile) {
+            continue;
+        }
+        let color = (colors >> (color * 4) & 0xf) as u8 - 1;
+        let color = if decals::covers(hole, v) { 3 } else { color };
+
+        // Far walls fade out
+        set_pixel(x, y, (color + distance_shade(wall.distance, reach, light, x, y)).min(3));
+    }
+}
+
+fn wall_texture(tile: u8) -> Id {
+    match tile {
+        DOOR => Id::Door,
+        SILVER_DOOR => Id::SilverLock,
+        BRASS_DOOR => Id::BrassLock,
+        IRON_DOOR => Id::IronLock,
+        EXIT => Id::Exit,
+        CRACKED_WALL => Id::Cracked,
+        MONITOR => Id::Monitor,
+        TORCH_WALL => Id::Torch,
+        FENCE => Id::Fence,
+        WINDOW => Id::Window,
+        MIRROR => Id::Mirror,
+        PORTAL => Id::Portal,
+        _ => Id::Brick,
+    }
+}
+
+fn floor_texture(tile: u8) -> Id {
+    match tile {
+        HAZARD => Id::Hazard,
+        LAVA => Id::Lava,
+        WATER => Id::Water,
+        _ => Id::Floor,
+    }
+}
+
+// Cast the floor and ceiling around every wall slice
+unsafe fn render_floor(
+    state: &State,
+    camera: Camera,
+    horizon: i32,
+    walls: &[RayHit; 160],
+    rays: &[(f32, f32, f32); 160],
+    light: Light,
+    viewport: Viewport,
+) {
+    let wall_height = state.projected_wall_height(camera);
+
+    for (column, wall) in walls[..viewport.columns].iter().enumerate() {
+        let (dir_x, dir_y, fisheye) = rays[column];
+        let x = viewport.left + column as i32;
+        let height = wall.height;
+        let reach = light.reach(column as i32);
+
+        // How far round the horizon this column looks, so the sky pans as
+        // the camera turns
+        let sky_u = (PI - atan2(-dir_y, dir_x)) / TAU * SKY_REPEATS;
+
+        // Kept to the viewport's rows, which looking up or down can take the
+        // wall out of
+        let top = horizon - height / 2;
+        let (top, bottom) = (top.clamp(viewport.top, viewport.bottom()), top + height);
+        for y in viewport.top..viewport.bottom() {
+            if (top..bottom).contains(&y) {
+                continue;
+            }
+
+            // Perpendicular distance to the floor (or ceiling) seen by this row
+            let row = abs(y as f32 + 0.5 - horizon as f32);
+            let dist = wall_height / (2.0 * row * fisheye);
+
+            let floor_x = camera.x + dir_x * dist;
+            let floor_y = camera.y + dir_y * dist;
+            let (u, v) = (floor_x - floor(floor_x), floor_y - floor(floor_y));
+
+            let (tile_x, tile_y) = (floor(floor_x) as i32, floor(floor_y) as i32);
+            let tile = state.map().tile(tile_x, tile_y);
+            if y < horizon && tile == OUTDOOR {
+                let sky = state.textures.get(Id::Sky);
+                set_pixel(x, y, texture::sample(sky, sky_u, 1.0 - row / SKY_ROWS));
+                continue;
+            }
+
+            let surface = if y < horizon { Id::Ceiling } else { floor_texture(tile) };
+            let color = texture::sample(state.textures.get(surface), u, v);
+            let shade = fog_shade(dist, reach, state.lighting.level(tile_x, tile_y), x, y);
+            set_pixel(x, y, (color + shade).min(3));
+        }
+    }
+}
+
+// Fill the screen with a single palette index
+unsafe fn clear_screen(color: u8) {
+    let fill = color * 0b01010101;
+
+    for idx in 0..(SCREEN_SIZE * SCREEN_SIZE / 4) as usize {
+        *FRAMEBUFFER.add(idx) = fill;
+    }
+}
+
+// Darken every other pixel by a shade in a checkerboard, so the frame keeps
+// its shapes with only four colors to dim into
+unsafe fn dim_screen() {
+    const ROW_BYTES: usize = SCREEN_SIZE as usize / 4;
+
+    for idx in 0..ROW_BYTES * SCREEN_SIZE as usize {
+        let byte = FRAMEBUFFER.add(idx);
+        let first = (idx / ROW_BYTES % 2) * 2;
+        for shift in (first..8).step_by(4) {
+            let color = (*byte >> shift & 0b11).min(2) + 1;
+            *byte = (*byte & !(0b11 << shift)) | color << shift;
+        }
+    }
+}
+
+// The pause menu over the dimmed frame it froze on
+pub(crate) unsafe fn draw_pause(selected: usize) {
+    dim_screen();
+
+    *DRAW_COLORS = 0x24;
+    rect(16, 36, 128, 86);
+
+    *DRAW_COLORS = 0x1;
+    text(b"PAUSED", 56, 44);
+
+    for (row, (_, label)) in pause::ITEMS.iter().enumerate() {
+        let y = 62 + row as i32 * 14;
+        *DRAW_COLORS = if row == selected { 0x1 } else { 0x2 };
+        if row == selected {
+            text(b">", 24, y);
+        }
+        text(label, 36, y);
+    }
+}
+
+// Show the upcoming level's number between levels, over the screen from
+// column `from` on; letters left of it are already wiped away
+pub(crate) unsafe fn draw_transition(level: usize, from: i32) {
+    if from == 0 {
+        clear_screen(3);
+    } else {
+        *DRAW_COLORS = 0x44;
+        rect(from, 0, (SCREEN_SIZE - from) as u32, SCREEN_SIZE as u32);
+    }
+
+    *DRAW_COLORS = 0x1;
+    let name = [b'L', b'E', b'V', b'E', b'L', b' ', b'1' + level as u8];
+    for (idx, letter) in name.iter().enumerate() {
+        let x = 52 + idx as i32 * 8;
+        if x >= from {
+            text(&[*letter], x, 76);
+        }
+    }
+}
+
+// The game's name, the best run on each level so far, and how to start
+pub(crate) unsafe fn draw_title(leaderboards: &[Leaderboard], best_wave: u8) {
+    clear_screen(3);
+
+    *DRAW_COLORS = 0x1;
+    text(b"RAYCASTER", 44, 24);
+
+    let mut best = [Record::EMPTY; MAX_LEVELS];
+    for (best, board) in best.iter_mut().zip(leaderboards) {
+        *best = board[0];
+    }
+    if best.iter().any(|record| record.frames > 0) {
+        draw_records(b"LEVEL", &best[..leaderboards.len()], None, 56);
+    }
+
+    if best_wave > 0 {
+        let mut buffer = [0; 3];
+        text(b"BEST WAVE", 32, 116);
+        text(hud::digits(best_wave as u32, &mut buffer), 112, 116);
+    }
+
+    *DRAW_COLORS = 0x2;
+    text(b"PRESS X TO START", 16, 132);
+    text(b"Z FOR SURVIVAL", 24, 144);
+}
+
+// The name and a prompt in bands across the demo playing behind them
+pub(crate) unsafe fn draw_demo_title() {
+    *DRAW_COLORS = 0x44;
+    rect(0, 20, SCREEN_SIZE as u32, 16);
+    rect(0, 128, SCREEN_SIZE as u32, 16);
+
+    *DRAW_COLORS = 0x1;
+    text(b"RAYCASTER", 44, 24);
+    *DRAW_COLORS = 0x2;
+    text(b"PRESS ANY BUTTON", 16, 132);
+}
+
+pub(crate) unsafe fn draw_game_over() {
+    clear_screen(3);
+
+    *DRAW_COLORS = 0x1;
+    text(b"GAME OVER", 44, 68);
+    *DRAW_COLORS = 0x2;
+    text(b"PRESS X TO RESTART", 8, 84);
+}
+
+// Summary of the level just finished: time taken, kills and secrets found,
+// then the level's best runs with this one picked out if it placed
+pub(crate) unsafe fn draw_level_complete(
+    stats: &LevelStats,
+    board: &Leaderboard,
+    rank: Option<usize>,
+) {
+    clear_screen(3);
+
+    *DRAW_COLORS = 0x1;
+    text(b"LEVEL COMPLETE", 24, 16);
+
+    let (mut a, mut b) = ([0; 3], [0; 3]);
+
+    *DRAW_COLORS = 0x2;
+    let (mut minutes, mut seconds) = ([0; 3], [0; 2]);
+    draw_stat(b"TIME", &clock(stats.frames, &mut minutes, &mut seconds), 36);
+    let (kills, enemies) = (hud::digits(stats.kills, &mut a), hud::digits(stats.enemies, &mut b));
+    draw_stat(b"KILLS", &[kills, b"/", enemies], 48);
+    let secrets = hud::digits(stats.secrets, &mut a);
+    draw_stat(b"SECRETS", &[secrets, b"/", hud::digits(stats.secret_walls, &mut b)], 60);
+
+    draw_records(b"BEST", board, rank, 80);
+
+    *DRAW_COLORS = 0x2;
+    text(b"PRESS X TO GO ON", 16, 132);
+    text(b"Z TO RETRY", 40, 144);
+}
+
+// Numbered runs under a heading, one row each with the time and kills; the
+// run at `highlight` is drawn in the lightest color
+unsafe fn draw_records(heading: &[u8], records: &[Record], highlight: Option<usize>, y: i32) {
+    *DRAW_COLORS = 0x1;
+    text(heading, 24, y);
+    text(b"KILLS", 96, y);
+
+    for (idx, record) in records.iter().enumerate().filter(|(_, record)| record.frames > 0) {
+        let row = y + 12 * (idx as i32 + 1);
+        let (mut minutes, mut seconds, mut kills) = ([0; 3], [0; 2], [0; 3]);
+
+        *DRAW_COLORS = if highlight == Some(idx) { 0x1 } else { 0x2 };
+        text(&[b'1' + idx as u8], 24, row);
+        draw_pieces(&clock(record.frames, &mut minutes, &mut seconds), 40, row);
+        text(hud::digits(record.kills as u32, &mut kills), 96, row);
+    }
+}
+
+// A time given in frames as minutes and seconds, in pieces for draw_pieces
+fn clock<'a>(frames: u32, minutes: &'a mut [u8; 3], seconds: &'a mut [u8; 2]) -> [&'a [u8]; 3] {
+    // The console runs at 60 frames per second
+    let total = frames / 60;
+    *seconds = [b'0' + (total % 60 / 10) as u8, b'0' + (total % 10) as u8];
+
+    [hud::digits(total / 60, minutes), b":", seconds]
+}
+
+// The settings, one row each, with the picked row marked
+pub(crate) unsafe fn draw_options(settings: &Settings, selected: usize) {
+    clear_screen(3);
+
+    *DRAW_COLORS = 0x1;
+    text(b"OPTIONS", 52, 16);
+
+    for (row, (label, value)) in settings.rows().iter().enumerate() {
+        let y = 40 + row as i32 * 14;
+        *DRAW_COLORS = if row == selected { 0x1 } else { 0x2 };
+        if row == selected {
+            text(b">", 12, y);
+        }
+        draw_stat(label, &[value], y);
+    }
+
+    *DRAW_COLORS = 0x2;
+    text(b"X: BACK", 52, 140);
+}
+
+// The rules for a deathmatch and how many players are in so far
+pub(crate) unsafe fn draw_lobby(rules: &Rules, selected: usize, players: usize) {
+    clear_screen(3);
+
+    *DRAW_COLORS = 0x1;
+    text(b"DEATHMATCH", 40, 16);
+
+    for (row, (label, value)) in rules.rows().iter().enumerate() {
+        let y = 44 + row as i32 * 14;
+        *DRAW_COLORS = if row == selected { 0x1 } else { 0x2 };
+        if row == selected {
+            text(b">", 8, y);
+        }
+        text(label, 16, y);
+        text(value, 104, y);
+    }
+
+    *DRAW_COLORS = 0x2;
+    draw_pieces(&[b"PLAYERS ", &[b'0' + players as u8], b"/4"], 36, 100);
+    text(b"X ON PAD 2-4: JOIN", 8, 128);
+    text(b"Z: BACK", 52, 140);
+}
+
+// One row of the level summary, with the value laid out from a few pieces
+unsafe fn draw_stat(label: &[u8], value: &[&[u8]], y: i32) {
+    text(label, 24, y);
+    draw_pieces(value, 96, y);
+}
+
+// Draw pieces of text one after another from x
+unsafe fn draw_pieces(pieces: &[&[u8]], mut x: i32, y: i32) {
+    for piece in pieces {
+        text(piece, x, y);
+        x += piece.len() as i32 * 8;
+    }
+}
+
+// Outline the screen, or a player's quarter of it in deathmatch, in the
+// darkest color right after taking damage
+pub(crate) unsafe fn draw_damage_flash(left: i32, top: i32, size: i32) {
+    *DRAW_COLORS = 0x40;
+
+    for inset in 0..DAMAGE_FLASH_WIDTH {
+        let inner = (size - inset * 2) as u32;
+        rect(left + inset, top + inset, inner, inner);
+    }
+}
+
+// Darken the bottom of the view with a dither that thickens towards the
+// screen edge, while the player stands on a floor that hurts
+pub(crate) unsafe fn draw_hazard_tint(viewport: Viewport) {
+    let start = viewport.bottom() - HAZARD_TINT_HEIGHT;
+
+    for y in start..viewport.bottom() {
+        let coverage = 1 + (y - start) * 3 / HAZARD_TINT_HEIGHT;
+
+        for x in viewport.left..viewport.left + viewport.columns as i32 {
+            if BAYER[(y % 2) as usize][(x % 2) as usize] < coverage {
+                set_pixel(x, y, 3);
+            }
+        }
+    }
+}
+
+// Darken the edges of the view by a dither that thickens towards them, the
+// given number of pixels in
+pub(crate) unsafe fn draw_vignette(viewport: Viewport, depth: i32) {
+    let right = viewport.left + viewport.columns as i32;
+
+    for y in viewport.top..viewport.bottom() {
+        for x in viewport.left..right {
+            let edge = (x - viewport.left).min(right - 1 - x);
+            let edge = edge.min(y - viewport.top).min(viewport.bottom() - 1 - y);
+            let coverage = (depth - edge) * 4 / depth;
+            if BAYER[(y % 2) as usize][(x % 2) as usize] < coverage {
+                set_pixel(x, y, 3);
+            }
+        }
+    }
+}
+
+// Shift each row of a viewport sideways along a wave rolling down it, as if
+// seen through water; the pixels at the edges stretch to fill the gap
+pub(crate) unsafe fn draw_ripple(viewport: Viewport, frames: u32) {
+    let (left, right) = (viewport.left, viewport.left + viewport.columns as i32 - 1);
+    let roll = (frames % RIPPLE_PERIOD) as f32 * TAU / RIPPLE_PERIOD as f32;
+
+    for y in viewport.top..viewport.bottom() {
+        let shift = (sin(y as f32 * RIPPLE_FREQUENCY - roll) * RIPPLE_AMPLITUDE) as i32;
+        // Working away from where the row moves to reads each pixel before
+        // it is overwritten
+        for step in 0..=right - left {
+            let x = if shift > 0 { right - step } else { left + step };
+            set_pixel(x, y, get_pixel((x - shift).clamp(left, right), y));
+        }
+    }
+}
+
+/// Draw the 3D view across a viewport: floor and ceiling, textured walls,
+/// then sprites
+///
+/// # Safety
+///
+/// Writes straight to the WASM-4 framebuffer, so it must run on the console.
+pub unsafe fn draw_view(state: &State, viewport: Viewport) {
+    let (sprites, count) = state.sprites();
+    draw_scene(state, state.camera(), state.horizon(viewport), &sprites[..count], viewport);
+}
+
+// The level and the sprites in it as a camera sees them across a viewport,
+// with the horizon on the given row
+pub(crate) unsafe fn draw_scene(
+    state: &State,
+    camera: Camera,
+    horizon: i32,
+    sprites: &[Sprite],
+    viewport: Viewport,
+) {
+    let rays = state.column_rays(camera, viewport);
+    let walls = state.get_view_along(camera, viewport, &rays);
+    let light = Light::new(state, camera, viewport);
+    render_floor(state, camera, horizon, &walls, &rays, light, viewport);
+
+    // Walls, then the sprites seen through fences and windows, then those and
+    // the sprites in front of them
+    let wall_height = state.projected_wall_height(camera);
+    for see_through in [false, true] {
+        for (column, wall) in walls[..viewport.columns].iter().enumerate() {
+            let slice = if see_through { wall.see_through(wall_height) } else { Some(*wall) };
+            if let Some(slice) = slice {
+                let x = viewport.left + column as i32;
+                let reach = light.reach(column as i32);
+                draw_wall_column(state, reach, x, horizon, &slice, see_through, viewport);
+            }
+        }
+        sprites::render(state, camera, horizon, sprites, &walls, !see_through, light, viewport);
+    }
+}
@@ -0,0 +1,370 @@
+// Input replays: each level is recorded from its first frame as the gamepad
+// byte of every frame, with what the level started out with. The game keeps
+// no clock and draws all its randomness from the seeded Rng, so feeding the
+// same bytes from the same start plays the run out exactly again, for bug
+// reports and for the demo under the title. Mouse turning is not recorded,
+// so only runs played on the gamepad replay faithfully
+use crate::config::Config;
+use crate::input::Input;
+use crate::options::Settings;
+use crate::platform::{BUTTON_1, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP};
+use crate::rng::Rng;
+use crate::survival::Survival;
+use crate::weapon::{self, Weapon};
+use crate::{GameMode, State, MAX_HEALTH};
+
+// Gamepad changes kept per level, each with the frames it was held for;
+// recording stops once they run out
+const MAX_RUNS: usize = 512;
+
+// Frames the title sits untouched before the demo starts
+const ATTRACT_FRAMES: u32 = 600;
+
+// Steps written like the music's patterns, separated by spaces: U for up,
+// L or R for turning, F for BUTTON_1, each or a dash, then the frames they
+// are held for, like "UL-012" or "--F002"
+const fn script<const N: usize>(text: &[u8]) -> [(u8, u8); N] {
+    assert!(text.len() + 1 == N * 7, "script steps must be six characters each");
+
+    let mut runs = [(0, 0); N];
+    let mut step = 0;
+    while step < N {
+        let at = step * 7;
+        let mut gamepad = 0;
+        if text[at] == b'U' {
+            gamepad |= BUTTON_UP;
+        }
+        gamepad |= match text[at + 1] {
+            b'L' => BUTTON_LEFT,
+            b'R' => BUTTON_RIGHT,
+            _ => 0,
+        };
+        if text[at + 2] == b'F' {
+            gamepad |= BUTTON_1;
+        }
+
+        let mut frames = 0;
+        let mut digit = at + 3;
+        while digit < at + 6 {
+            frames = frames * 10 + (text[digit] - b'0') as u32;
+            digit += 1;
+        }
+        assert!(frames > 0 && frames <= u8::MAX as u32, "script steps last 1 to 255 frames");
+        runs[step] = (gamepad, frames as u8);
+        step += 1;
+    }
+    runs
+}
+
+// The demo under the title: a walk round the first level from a new game,
+// shooting the guards met on the way, played with the default turn speed
+const DEMO_SCRIPT: &[u8] =
+    b"-R-117 UR-004 U--022 UR-005 U--010 UR-001 -L-027 UL-004 U--024 UL-005 \
+      U--007 UL-001 -R-026 UR-005 U--002 U-F002 U--018 U-F002 U--006 UR-005 \
+      U--007 U-F002 U--006 UR-005 U--010 UR-005 -L-026 UL-005 U--011 UL-002 \
+      -R-022 UR-006 U--008 UR-005 -L-023 UL-005 U--114 UR-005 U--006 -L-027 \
+      UL-005 U--022 -R-020 -RF001 --F001 ---008 --F002 ---001 -L-016 UL-008 \
+      U--004 UL-003 -R-026 UR-005 U--026 UR-006 U--003 -L-026 UL-005 U--009 \
+      UL-005 -R-022 UR-005 U--018 -R-020 --F002 ---002 -L-011 UL-009 U--005 \
+      UL-005 -L-020 UL-005 U--033 UL-006 -L-021 UL-005 U--022 U--051 UR-005 \
+      U--004 -L-026 UL-005 U--011 UL-003 -R-023 UR-005 U--036 -L-024 UL-006 \
+      U--002 U--011 UL-002 ULF001 U-F001 U--018 U-F002 U--018 U-F002 U--004 \
+      UL-005 U--014 UL-002 UR-003 U--017 UR-002 -R-025 UR-005 U--082";
+const DEMO: [(u8, u8); (DEMO_SCRIPT.len() + 1) / 7] = script(DEMO_SCRIPT);
+
+// Everything carried into a level that play depends on
+#[derive(Clone, Copy)]
+struct Start {
+    level: usize,
+    mode: GameMode,
+    health: i32,
+    ammo: u32,
+    rockets: u32,
+    weapon: Weapon,
+    weapons: u8,
+    turn_speed: f32,
+    settings: Settings,
+    survival: Option<Survival>,
+    input: Input,
+    rng: Rng,
+}
+
+#[derive(Clone, Copy)]
+pub struct Replay {
+    start: Start,
+    // Each gamepad byte with the frames in a row it was held for
+    runs: [(u8, u8); MAX_RUNS],
+    len: usize,
+    // The run and frame in it playing next, while playing
+    cursor: Option<(usize, u8)>,
+    // Set when a level loads, so the next frame starts a new recording
+    fresh: bool,
+    // The live gamepad last frame, to tell new presses during playback
+    live: u8,
+    // Frames the title has sat untouched
+    idle: u32,
+}
+
+impl Replay {
+    pub const fn new() -> Self {
+        Replay {
+            start: Start {
+                level: 0,
+                mode: GameMode::Playing,
+                health: 0,
+                ammo: 0,
+                rockets: 0,
+                weapon: Weapon::Pistol,
+                weapons: 0,
+                turn_speed: 0.0,
+                settings: Settings::new(),
+                survival: None,
+                input: Input::new(),
+                rng: Rng::new(0),
+            },
+            runs: [(0, 0); MAX_RUNS],
+            len: 0,
+            cursor: None,
+            fresh: true,
+            live: 0,
+            idle: 0,
+        }
+    }
+
+    // The bundled demo, played with the look of the game as set up now
+    fn demo(settings: Settings, config: Config) -> Self {
+        let settings =
+            Settings { turn_speed: Settings::new().turn_speed, invert_turn: false, ..settings };
+
+        let mut demo = Replay::new();
+        demo.runs[..DEMO.len()].copy_from_slice(&DEMO);
+        demo.len = DEMO.len();
+        demo.start = Start {
+            level: 0,
+            mode: GameMode::Playing,
+            health: MAX_HEALTH,
+            ammo: weapon::START_AMMO,
+            rockets: 0,
+            weapon: Weapon::Pistol,
+            weapons: weapon::START_WEAPONS,
+            turn_speed: settings.apply(config).turn_speed,
+            settings,
+            survival: None,
+            input: Input::new(),
+            rng: Rng::new(0),
+        };
+        demo
+    }
+
+    pub fn playing(&self) -> bool {
+        self.cursor.is_some()
+    }
+
+    // Frames recorded
+    pub fn frames(&self) -> u32 {
+        self.runs[..self.len].iter().map(|&(_, frames)| frames as u32).sum()
+    }
+
+    fn record(&mut self, gamepad: u8) {
+        match self.runs[..self.len].last_mut() {
+            Some((last, frames)) if *last == gamepad && *frames < u8::MAX => *frames += 1,
+            _ if self.len < MAX_RUNS => {
+                self.runs[self.len] = (gamepad, 1);
+                self.len += 1;
+            }
+            _ => {}
+        }
+    }
+
+    // The next recorded byte, stopping at the end of the recording
+    fn next(&mut self) -> Option<u8> {
+        let (run, frame) = self.cursor?;
+        let Some(&(gamepad, frames)) = self.runs[..self.len].get(run) else {
+            self.cursor = None;
+            return None;
+        };
+
+        self.cursor = Some(if frame + 1 < frames { (run, frame + 1) } else { (run + 1, 0) });
+        Some(gamepad)
+    }
+}
+
+impl State {
+    // Start the next frame's recording over, unless one is playing
+    pub(crate) fn restart_recording(&mut self) {
+        self.replay.fresh = !self.replay.playing();
+    }
+
+    // This frame's gamepad byte while a replay is playing, or None to read
+    // the live input and record it. A live press takes back control
+    pub(crate) fn replayed_input(&mut self, live: u8) -> Option<u8> {
+        let pressed = live & !self.replay.live != 0;
+        self.replay.live = live;
+        if pressed {
+            self.replay.cursor = None;
+        }
+        if let Some(gamepad) = self.replay.next() {
+            return Some(gamepad);
+        }
+
+        if self.replay.fresh && self.mode != GameMode::Title {
+            self.replay.fresh = false;
+            self.replay.len = 0;
+            self.replay.start = Start {
+                level: self.level,
+                mode: self.mode,
+                health: self.health,
+                ammo: self.ammo,
+                rockets: self.rockets,
+                weapon: self.weapon,
+                weapons: self.weapons,
+                turn_speed: self.turn_speed,
+                settings: self.settings,
+                survival: self.survival,
+                input: self.input,
+                rng: self.rng,
+            };
+        }
+        None
+    }
+
+    // Add the input just read to the recording; the title isn't recorded,
+    // and neither are co-op and deathmatch, as only the first gamepad is kept
+    pub(crate) fn record_input(&mut self) {
+        let alone = self.partner.is_none() && self.deathmatch.is_none();
+        if !self.replay.fresh && self.mode != GameMode::Title && alone {
+            self.replay.record(self.input.gamepad());
+        }
+    }
+
+    // The latest recording, from the start of its level
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    // Go back to the start of a recording and play it out, handing control
+    // back when it runs out; false if there is nothing to play
+    pub fn play_replay(&mut self, replay: &Replay) -> bool {
+        if replay.len == 0 {
+            return false;
+        }
+
+        let start = replay.start;
+        self.replay = *replay;
+        self.replay.cursor = Some((0, 0));
+        self.survival = start.survival;
+        (self.partner, self.deathmatch) = (None, None);
+        self.load_level(start.level);
+
+        self.mode = start.mode;
+        self.health = start.health;
+        (self.ammo, self.rockets) = (start.ammo, start.rockets);
+        (self.weapon, self.weapons) = (start.weapon, start.weapons);
+        self.set_settings(start.settings);
+        self.turn_speed = start.turn_speed;
+        self.input = start.input;
+        self.rng = start.rng;
+        true
+    }
+
+    // Play the demo behind the title once it has sat untouched for a while
+    pub(crate) fn update_attract(&mut self) {
+        let touched = self.input.gamepad() != 0;
+        self.replay.idle = if touched { 0 } else { self.replay.idle + 1 };
+
+        if self.replay.idle >= ATTRACT_FRAMES {
+            self.replay.idle = 0;
+            self.demo = Some(self.save());
+            self.play_replay(&Replay::demo(self.settings, self.config));
+        }
+    }
+
+    // Back to the title once the demo runs out, is interrupted or leaves play
+    pub(crate) fn update_demo(&mut self) {
+        let Some(save) = self.demo else { return };
+        if self.replay.playing() && self.mode == GameMode::Playing {
+            return;
+        }
+
+        self.demo = None;
+        self.replay.cursor = None;
+        self.resume(&save);
+        self.input.swallow();
+        self.mode = GameMode::Title;
+        self.transition = 0;
+    }
+
+    // While the demo plays behind the title, which also keeps it from saving
+    pub(crate) fn attracting(&self) -> bool {
+        self.demo.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::{BUTTON_1, BUTTON_LEFT, BUTTON_UP};
+
+    // A frame as the cart runs it, with `gamepad` as the live input
+    fn step(state: &mut State, gamepad: u8) {
+        match state.replayed_input(gamepad) {
+            Some(gamepad) => state.input.replay(gamepad),
+            None => {
+                state.input.replay(gamepad);
+                state.record_input();
+            }
+        }
+
+        if state.transition > 0 {
+            state.transition -= 1;
+        } else if state.mode == GameMode::Playing {
+            state.update();
+        }
+    }
+
+    #[test]
+    fn replays_play_the_run_out_again() {
+        let mut state = State::new();
+
+        // Wander off from the spawn point firing, with the randomness for the
+        // spread stirred by the presses
+        for frame in 0..400 {
+            let fire = if frame % 20 < 3 { BUTTON_1 } else { 0 };
+            let turn = if frame % 150 > 100 { BUTTON_LEFT } else { 0 };
+            step(&mut state, BUTTON_UP | fire | turn);
+        }
+        let end = (state.player(), state.ammo, state.rng.next_u32());
+        assert_eq!(state.replay().frames(), 400);
+
+        let replay = *state.replay();
+        assert!(state.play_replay(&replay));
+        for _ in 0..400 {
+            step(&mut state, 0);
+        }
+        assert_eq!((state.player(), state.ammo, state.rng.next_u32()), end);
+
+        // Then control comes back
+        assert!(state.replayed_input(0).is_none() && !state.replay().playing());
+    }
+
+    #[test]
+    fn the_demo_still_finds_its_way_round() {
+        let mut state = State::new();
+        let demo = Replay::demo(Settings::new(), Config::new());
+        assert!(state.play_replay(&demo));
+
+        // Until the frame after the last, which hands back control
+        let mut frames = 0;
+        while state.replay().playing() {
+            step(&mut state, 0);
+            frames += 1;
+        }
+        assert_eq!(frames, demo.frames() + 1);
+
+        // Back where the corridor round the first rooms began, having shot
+        // its way through
+        let (x, y, _) = state.player();
+        assert!((x - 4.5).abs() < 0.5 && (y - 6.5).abs() < 0.5);
+        assert!(state.mode == GameMode::Playing && state.stats.kills > 0);
+    }
+}
@@ -0,0 +1,164 @@
+// Run-length packing for the level maps and textures kept in the cart, made
+// at compile time and unpacked into RAM when needed. Data is a series of
+// blocks, each starting with a header byte: below 128 it is followed by
+// header + 1 bytes to copy as they are, otherwise by one byte repeated
+// header - 125 times
+const MAX_LITERALS: usize = 128;
+const MIN_RUN: usize = 3;
+const MAX_RUN: usize = 130;
+
+// Bytes `data` takes up once packed
+pub const fn packed_len(data: &[u8]) -> usize {
+    encode(data, &mut [])
+}
+
+// Pack `data`; N must be its packed_len, which the array type usually gives
+pub const fn pack<const N: usize>(data: &[u8]) -> [u8; N] {
+    let mut packed = [0; N];
+    let len = encode(data, &mut packed);
+    assert!(len == N, "packed size does not match");
+    packed
+}
+
+// Pack `data` into the start of `out` and return the packed length. Nothing
+// is written past the end of `out`, so a length beyond it means no fit
+pub const fn pack_into(data: &[u8], out: &mut [u8]) -> usize {
+    encode(data, out)
+}
+
+// Unpack into the start of `out`, dropping anything past its end, and return
+// the number of bytes written. Data cut off partway through a block, as a
+// corrupt save can be, stops at the last whole one
+pub const fn unpack(packed: &[u8], out: &mut [u8]) -> usize {
+    let (mut from, mut len) = (0, 0);
+
+    while from < packed.len() {
+        let header = packed[from] as usize;
+        from += 1;
+
+        if header < MAX_LITERALS {
+            let end = from + header + 1;
+            if end > packed.len() {
+                break;
+            }
+            while from < end {
+                len = put(out, len, packed[from]);
+                from += 1;
+            }
+        } else {
+            if from == packed.len() {
+                break;
+            }
+            let mut run = header - 125;
+            while run > 0 {
+                len = put(out, len, packed[from]);
+                run -= 1;
+            }
+            from += 1;
+        }
+    }
+
+    if len < out.len() { len } else { out.len() }
+}
+
+// Write a byte if there is room and return the next position; measuring
+// passes an empty buffer
+const fn put(out: &mut [u8], at: usize, byte: u8) -> usize {
+    if at < out.len() {
+        out[at] = byte;
+    }
+    at + 1
+}
+
+const fn encode(data: &[u8], out: &mut [u8]) -> usize {
+    let (mut idx, mut literals, mut len) = (0, 0, 0);
+
+    while idx < data.len() {
+        let mut run = 1;
+        while idx + run < data.len() && data[idx + run] == data[idx] && run < MAX_RUN {
+            run += 1;
+        }
+
+        if run >= MIN_RUN {
+            len = flush(data, literals, idx, out, len);
+            len = put(out, len, (run + 125) as u8);
+            len = put(out, len, data[idx]);
+            idx += run;
+            literals = idx;
+        } else {
+            idx += 1;
+            if idx - literals == MAX_LITERALS {
+                len = flush(data, literals, idx, out, len);
+                literals = idx;
+            }
+        }
+    }
+
+    flush(data, literals, idx, out, len)
+}
+
+// Write data[start..end] as a block of literals, if there are any
+const fn flush(data: &[u8], start: usize, end: usize, out: &mut [u8], len: usize) -> usize {
+    if end == start {
+        return len;
+    }
+
+    let mut len = put(out, len, (end - start - 1) as u8);
+    let mut idx = start;
+    while idx < end {
+        len = put(out, len, data[idx]);
+        idx += 1;
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs longer than one block, a gap of literals longer than one block, and
+    // runs too short to be worth packing
+    const DATA: [u8; 600] = {
+        let mut data = [7; 600];
+        let mut idx = 200;
+        while idx < 400 {
+            data[idx] = (idx * 37 % 251) as u8;
+            idx += 1;
+        }
+        data[450] = 1;
+        data[451] = 1;
+        data[460] = 2;
+        data
+    };
+    const PACKED: [u8; packed_len(&DATA)] = pack(&DATA);
+
+    #[test]
+    fn unpacking_gives_back_the_original() {
+        let mut out = [0; 600];
+        assert_eq!(unpack(&PACKED, &mut out), 600);
+        assert!(out == DATA);
+
+        // Runs collapse to two bytes a block; the literals cost a header each
+        assert!(PACKED.len() < 250);
+    }
+
+    #[test]
+    fn unpacking_stops_at_a_block_cut_short() {
+        let mut out = [0; 600];
+        let whole = unpack(&PACKED, &mut out);
+
+        // Cut off anywhere short of the end, within a run's block or a
+        // block of literals
+        assert_eq!(unpack(&PACKED[..1], &mut out), 0);
+        for cut in 0..PACKED.len() {
+            assert!(unpack(&PACKED[..cut], &mut out) < whole);
+        }
+    }
+
+    #[test]
+    fn unpacking_stops_at_the_end_of_the_buffer() {
+        let mut out = [0; 100];
+        assert_eq!(unpack(&PACKED, &mut out), 100);
+        assert!(out.iter().all(|&byte| byte == 7));
+    }
+}
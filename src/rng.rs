@@ -0,0 +1,78 @@
+// Small seeded random number generator (PCG32) for level generation, AI and
+// effects; the same seed always gives the same sequence
+const MULTIPLIER: u64 = 6364136223846793005;
+const INCREMENT: u64 = 1442695040888963407;
+
+#[derive(Clone, Copy)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub const fn new(seed: u64) -> Self {
+        let mut rng = Rng { state: 0 };
+        rng.step();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.step();
+        rng
+    }
+
+    const fn step(&mut self) {
+        self.state = self.state.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.step();
+
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    // Uniform in 0..bound, which must not be zero
+    pub fn below(&mut self, bound: u32) -> u32 {
+        // Multiply-shift keeps the bias as small as a modulo without dividing
+        ((self.next_u32() as u64 * bound as u64) >> 32) as u32
+    }
+
+    // Uniform in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+
+    // Stir in outside entropy, such as the frame a button was pressed on
+    pub fn mix(&mut self, entropy: u32) {
+        self.state ^= entropy as u64;
+        self.step();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_the_same_sequence() {
+        let (mut a, mut b, mut c) = (Rng::new(7), Rng::new(7), Rng::new(8));
+        let first: [u32; 8] = core::array::from_fn(|_| a.next_u32());
+
+        assert_eq!(first, core::array::from_fn(|_| b.next_u32()));
+        assert_ne!(first, core::array::from_fn(|_| c.next_u32()));
+    }
+
+    #[test]
+    fn values_stay_in_range() {
+        let mut rng = Rng::new(0);
+        let mut seen = [false; 6];
+
+        for _ in 0..1000 {
+            seen[rng.below(6) as usize] = true;
+            assert!((0.0..1.0).contains(&rng.next_f32()));
+        }
+        assert!(seen.iter().all(|&seen| seen));
+    }
+}
@@ -0,0 +1,358 @@
+// Progress kept on the console's disk between sessions: the level reached
+// with the health and keys held, a leaderboard of the best runs on each
+// level, the player's settings and the best survival wave. The rest of the
+// disk holds the ghost
+use crate::options::{self, Settings};
+use crate::platform::{diskr, diskw};
+use crate::survival::Survival;
+use crate::{State, MAX_HEALTH};
+
+// Marks the disk as holding a save from this game
+const MAGIC: [u8; 2] = *b"RC";
+
+// Bump whenever the layout changes. Older layouts are read in `decode` for as
+// long as they are worth migrating; anything else is discarded
+const VERSION: u8 = 6;
+
+// Levels with room for a leaderboard, so adding levels keeps the layout
+pub const MAX_LEVELS: usize = 16;
+
+// Runs kept on each level's leaderboard
+pub const RANKS: usize = 3;
+
+// Magic, version, level, health and keys, then every level's leaderboard as
+// a little-endian frame count and a kill count per run, then the settings
+// and the best wave
+const HEADER: usize = 6;
+const RECORD_SIZE: usize = 5;
+const SETTINGS: usize = HEADER + RECORD_SIZE * RANKS * MAX_LEVELS;
+const BEST_WAVE: usize = SETTINGS + options::SIZE;
+pub const SIZE: usize = BEST_WAVE + 1;
+
+// All the disk the console gives a cart
+pub const DISK_SIZE: usize = 1024;
+
+// Version 1 kept only a best time per level, version 2 stopped before the
+// settings, version 3 before the best wave, version 4 had no mirror setting
+// yet and version 5 no fog setting. Their settings took up a fixed number of
+// bytes, however many later versions add
+const V1_SIZE: usize = HEADER + 4 * MAX_LEVELS;
+const V2_SIZE: usize = SETTINGS;
+const V4_SETTINGS: usize = 5;
+const V3_SIZE: usize = SETTINGS + V4_SETTINGS;
+const V4_SIZE: usize = V3_SIZE + 1;
+const V5_SETTINGS: usize = 6;
+const V5_SIZE: usize = SETTINGS + V5_SETTINGS + 1;
+
+// One finished run of a level
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Record {
+    // Frames taken, or 0 for an empty slot
+    pub frames: u32,
+    pub kills: u8,
+}
+
+impl Record {
+    pub const EMPTY: Record = Record { frames: 0, kills: 0 };
+
+    // Faster runs rank higher, and more kills break ties
+    fn beats(&self, other: &Record) -> bool {
+        other.frames == 0
+            || self.frames < other.frames
+            || (self.frames == other.frames && self.kills > other.kills)
+    }
+}
+
+// A level's best runs, best first
+pub type Leaderboard = [Record; RANKS];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Save {
+    pub level: u8,
+    pub health: u8,
+    pub keys: u8,
+    pub leaderboards: [Leaderboard; MAX_LEVELS],
+    pub settings: Settings,
+    pub best_wave: u8,
+}
+
+impl Save {
+    fn encode(&self) -> [u8; SIZE] {
+        let mut bytes = [0; SIZE];
+        bytes[..2].copy_from_slice(&MAGIC);
+        bytes[2..HEADER].copy_from_slice(&[VERSION, self.level, self.health, self.keys]);
+
+        let records = self.leaderboards.iter().flatten();
+        for (chunk, record) in bytes[HEADER..].chunks_exact_mut(RECORD_SIZE).zip(records) {
+            chunk[..4].copy_from_slice(&record.frames.to_le_bytes());
+            chunk[4] = record.kills;
+        }
+        bytes[SETTINGS..BEST_WAVE].copy_from_slice(&self.settings.encode());
+        bytes[BEST_WAVE] = self.best_wave;
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Save> {
+        if bytes.len() < HEADER || bytes[..2] != MAGIC {
+            return None;
+        }
+
+        let mut save = Save {
+            level: bytes[3],
+            health: bytes[4],
+            keys: bytes[5],
+            leaderboards: [[Record::EMPTY; RANKS]; MAX_LEVELS],
+            settings: Settings::new(),
+            best_wave: 0,
+        };
+
+        match bytes[2] {
+            VERSION if bytes.len() >= SIZE => {
+                save.decode_settings(bytes, options::SIZE);
+                save.best_wave = bytes[BEST_WAVE];
+            }
+            // The fog starts off
+            5 if bytes.len() >= V5_SIZE => {
+                save.decode_settings(bytes, V5_SETTINGS);
+                save.best_wave = bytes[V5_SIZE - 1];
+            }
+            // The mirror starts off
+            4 if bytes.len() >= V4_SIZE => {
+                save.decode_settings(bytes, V4_SETTINGS);
+                save.best_wave = bytes[V3_SIZE];
+            }
+            // No survival runs yet
+            3 if bytes.len() >= V3_SIZE => save.decode_settings(bytes, V4_SETTINGS),
+            // Settings start from the defaults
+            2 if bytes.len() >= V2_SIZE => save.decode_leaderboards(bytes),
+            // Each best time tops its level's board, with no kills on record
+            1 if bytes.len() >= V1_SIZE => {
+                let times = bytes[HEADER..V1_SIZE].chunks_exact(4);
+                for (board, chunk) in save.leaderboards.iter_mut().zip(times) {
+                    board[0].frames = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+                }
+            }
+            _ => return None,
+        }
+
+        Some(save)
+    }
+
+    // Settings from a layout with only the first `size` bytes of them keep
+    // their defaults for the rest
+    fn decode_settings(&mut self, bytes: &[u8], size: usize) {
+        self.decode_leaderboards(bytes);
+        let mut settings = Settings::new().encode();
+        settings[..size].copy_from_slice(&bytes[SETTINGS..SETTINGS + size]);
+        self.settings = Settings::decode(&settings);
+    }
+
+    fn decode_leaderboards(&mut self, bytes: &[u8]) {
+        let records = self.leaderboards.iter_mut().flatten();
+        for (record, chunk) in records.zip(bytes[HEADER..SETTINGS].chunks_exact(RECORD_SIZE)) {
+            let frames = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            *record = Record { frames, kills: chunk[4] };
+        }
+    }
+
+    // The save on disk, if there is one this version can read
+    pub unsafe fn read() -> Option<Save> {
+        let mut bytes = [0; SIZE];
+        let len = diskr(bytes.as_mut_ptr(), SIZE as u32) as usize;
+        Save::decode(&bytes[..len.min(SIZE)])
+    }
+
+    // Keeps whatever follows the save on disk
+    pub unsafe fn write(&self) {
+        let mut disk = [0; DISK_SIZE];
+        let len = diskr(disk.as_mut_ptr(), DISK_SIZE as u32) as usize;
+        disk[..SIZE].copy_from_slice(&self.encode());
+        diskw(disk.as_ptr(), len.max(SIZE) as u32);
+    }
+}
+
+// Put a run on a leaderboard if it makes the cut, returning its rank
+fn insert(board: &mut Leaderboard, record: Record) -> Option<usize> {
+    let rank = board.iter().position(|other| record.beats(other))?;
+    board.copy_within(rank..RANKS - 1, rank + 1);
+    board[rank] = record;
+    Some(rank)
+}
+
+impl State {
+    // The campaign's progress, which stays as it was left during survival
+    // and deathmatch
+    pub(crate) fn save(&self) -> Save {
+        let save = Save {
+            level: self.level as u8,
+            health: self.health.clamp(0, u8::MAX as i32) as u8,
+            keys: self.keys,
+            leaderboards: self.leaderboards,
+            settings: self.settings,
+            best_wave: self.best_wave,
+        };
+
+        let campaign = match (&self.survival, &self.deathmatch) {
+            (Some(Survival { campaign, .. }), _) => campaign,
+            (None, Some(deathmatch)) => &deathmatch.campaign,
+            (None, None) => return save,
+        };
+        Save { level: campaign.level, health: campaign.health, keys: campaign.keys, ..save }
+    }
+
+    // Pick up from the save read at start, if any. Netplay peers each have a
+    // disk of their own, so under netplay every peer starts a new game instead
+    pub(crate) fn resume_at_start(&mut self, save: Option<Save>, netplay: bool) {
+        if let Some(save) = save.filter(|_| !netplay) {
+            self.resume(&save);
+        }
+    }
+
+    // Pick up from a save, at the start of the level it was made on
+    pub(crate) fn resume(&mut self, save: &Save) {
+        self.load_level(save.level as usize);
+        self.health = (save.health as i32).clamp(1, MAX_HEALTH);
+        self.keys = save.keys;
+        self.leaderboards = save.leaderboards;
+        self.set_settings(save.settings);
+        self.best_wave = save.best_wave;
+    }
+
+    // Enter the level just finished on its leaderboard, remembering where the
+    // run placed
+    pub(crate) fn record_run(&mut self) {
+        // Replays are only watched, not run
+        if self.replay.playing() {
+            self.rank = None;
+            return;
+        }
+
+        let kills = self.stats.kills.min(u8::MAX as u32) as u8;
+        let record = Record { frames: self.stats.frames.max(1), kills };
+
+        self.rank = self.leaderboards.get_mut(self.level).and_then(|board| insert(board, record));
+        // A new best becomes the ghost to race
+        if self.rank == Some(0) {
+            self.ghost = self.recording;
+        }
+    }
+
+    // The current level's best runs
+    pub(crate) fn leaderboard(&self) -> &Leaderboard {
+        const EMPTY: Leaderboard = [Record::EMPTY; RANKS];
+        self.leaderboards.get(self.level).unwrap_or(&EMPTY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NO_RUNS: [Leaderboard; MAX_LEVELS] = [[Record::EMPTY; RANKS]; MAX_LEVELS];
+
+    #[test]
+    fn saves_read_back_the_same() {
+        let settings = Settings::new();
+        let leaderboards = NO_RUNS;
+        let mut save =
+            Save { level: 2, health: 75, keys: 0b101, leaderboards, settings, best_wave: 12 };
+        save.leaderboards[0][0] = Record { frames: 3600, kills: 4 };
+        save.leaderboards[15][2] = Record { frames: 70_000, kills: 255 };
+        save.settings.palette = 2;
+
+        assert_eq!(Save::decode(&save.encode()), Some(save));
+    }
+
+    #[test]
+    fn version_1_best_times_move_onto_the_leaderboards() {
+        let mut bytes = [0; V1_SIZE];
+        bytes[..HEADER].copy_from_slice(&[b'R', b'C', 1, 1, 60, 0b10]);
+        bytes[HEADER + 4..HEADER + 8].copy_from_slice(&5000u32.to_le_bytes());
+
+        let save = Save::decode(&bytes).unwrap();
+        assert_eq!((save.level, save.health, save.keys), (1, 60, 0b10));
+        assert_eq!(save.leaderboards[0], [Record::EMPTY; RANKS]);
+        assert_eq!(save.leaderboards[1][0], Record { frames: 5000, kills: 0 });
+        assert_eq!(save.settings, Settings::new());
+    }
+
+    #[test]
+    fn old_layouts_keep_what_they_had_room_for() {
+        let mut settings = Settings::new();
+        (settings.palette, settings.rear_view, settings.fog) = (1, true, 2);
+        let leaderboards = NO_RUNS;
+        let mut save =
+            Save { level: 2, health: 70, keys: 0b101, leaderboards, settings, best_wave: 6 };
+        save.leaderboards[2][1] = Record { frames: 1234, kills: 7 };
+        let only = |palette, rear_view| Settings { palette, rear_view, ..Settings::new() };
+
+        // Each layout from version 2 on as its version and size, and the
+        // settings and best wave it reads back with
+        for (version, size, settings, best_wave) in [
+            (2, V2_SIZE, Settings::new(), 0),
+            (3, V3_SIZE, only(1, false), 0),
+            (4, V4_SIZE, only(1, false), 6),
+            (5, V5_SIZE, only(1, true), 6),
+        ] {
+            let mut bytes = save.encode();
+            bytes[2] = version;
+            if best_wave > 0 {
+                bytes[size - 1] = best_wave;
+            }
+            let old = Save::decode(&bytes[..size]);
+            assert_eq!(old, Some(Save { settings, best_wave, ..save }), "version {version}");
+        }
+    }
+
+    #[test]
+    fn saves_from_other_versions_or_games_are_discarded() {
+        let settings = Settings::new();
+        let leaderboards = NO_RUNS;
+        let save = Save { level: 1, health: 50, keys: 0, leaderboards, settings, best_wave: 0 };
+        let mut bytes = save.encode();
+        assert!(Save::decode(&bytes[..SIZE - 1]).is_none());
+        assert!(Save::decode(&[]).is_none());
+
+        bytes[2] = VERSION + 1;
+        assert!(Save::decode(&bytes).is_none());
+
+        bytes[2] = VERSION;
+        bytes[0] = b'X';
+        assert!(Save::decode(&bytes).is_none());
+    }
+
+    #[test]
+    fn netplay_peers_start_the_same_game_whatever_their_disks_hold() {
+        let mut settings = Settings::new();
+        settings.fog = 2;
+        settings.invert_turn = true;
+        let leaderboards = NO_RUNS;
+        let save = Save { level: 2, health: 40, keys: 0b11, leaderboards, settings, best_wave: 3 };
+
+        let mut state = State::new();
+        state.resume_at_start(Some(save), true);
+        let fresh = State::new();
+        let first = (fresh.level, fresh.health, fresh.keys);
+        assert_eq!((state.level, state.health, state.keys), first);
+        assert_eq!(state.settings, fresh.settings);
+        assert_eq!(state.turn_speed, fresh.turn_speed);
+        assert_eq!(state.best_wave, fresh.best_wave);
+
+        // Played alone the save is picked up as usual
+        state.resume_at_start(Some(save), false);
+        assert_eq!((state.level, state.health, state.keys), (2, 40, 0b11));
+        assert!(state.config().invert_turn);
+    }
+
+    #[test]
+    fn runs_are_ranked_by_time_then_kills() {
+        let mut board = [Record::EMPTY; RANKS];
+        let run = |frames, kills| Record { frames, kills };
+
+        assert_eq!(insert(&mut board, run(900, 2)), Some(0));
+        assert_eq!(insert(&mut board, run(600, 1)), Some(0));
+        assert_eq!(insert(&mut board, run(900, 3)), Some(1));
+        assert_eq!(insert(&mut board, run(1000, 9)), None);
+        assert_eq!(board, [run(600, 1), run(900, 3), run(900, 2)]);
+    }
+}
@@ -0,0 +1,100 @@
+// Screen shake: a trauma level knocked up when the player is hurt or a rocket
+// bursts near them, wearing off a little each frame. While it lasts the
+// horizon and the view's angle are jolted by amounts rising with its square,
+// so light knocks barely show and heavy ones rattle the screen
+use crate::math::sqrt;
+use crate::rng::Rng;
+use crate::State;
+
+// Trauma wears off by this much per frame, from at most 1
+const RECOVERY: f32 = 0.04;
+// Pixels the horizon and radians the view are jolted by at full trauma
+const MAX_OFFSET: f32 = 8.0;
+const MAX_JITTER: f32 = 0.04;
+// Trauma per point of damage taken, and from a burst beside the player,
+// falling away to none BLAST_REACH tiles off
+const DAMAGE_TRAUMA: f32 = 0.04;
+const BLAST_TRAUMA: f32 = 1.0;
+const BLAST_REACH: f32 = 6.0;
+
+impl State {
+    pub(crate) fn shake_from_damage(&mut self, damage: i32) {
+        self.add_trauma(damage as f32 * DAMAGE_TRAUMA);
+    }
+
+    // Shake each player's view harder the nearer a burst at (x, y) is to them
+    pub(crate) fn shake_from_blast(&mut self, x: f32, y: f32) {
+        self.feel_blast(x, y);
+        if self.partner.is_some() {
+            self.swap_partner();
+            self.feel_blast(x, y);
+            self.swap_partner();
+        }
+    }
+
+    fn feel_blast(&mut self, x: f32, y: f32) {
+        let (dx, dy) = (x - self.player_x, y - self.player_y);
+        let nearness = 1.0 - sqrt(dx * dx + dy * dy) / BLAST_REACH;
+        if nearness > 0.0 {
+            self.add_trauma(nearness * BLAST_TRAUMA);
+        }
+    }
+
+    fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).min(1.0);
+    }
+
+    pub(crate) fn update_shake(&mut self) {
+        self.trauma = (self.trauma - RECOVERY).max(0.0);
+    }
+
+    // Pixels the horizon is jolted by this frame, and radians the view is
+    // turned by. Drawing asks for it, so nothing can be stepped here; seeding
+    // by the frame gives each frame a new jolt, and a paused frame the same
+    pub(crate) fn shake_offset(&self) -> (i32, f32) {
+        if self.trauma == 0.0 {
+            return (0, 0.0);
+        }
+
+        let strength = self.trauma * self.trauma;
+        let mut rng = Rng::new(self.stats.frames as u64);
+        let offset = (rng.next_f32() * 2.0 - 1.0) * MAX_OFFSET * strength;
+        let jitter = (rng.next_f32() * 2.0 - 1.0) * MAX_JITTER * strength;
+        (offset as i32, jitter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::abs;
+    use crate::raycast::Viewport;
+
+    #[test]
+    fn bursts_nearby_shake_the_view_until_it_settles() {
+        let mut state = State::new();
+        state.teleport(5.5, 6.5, 0.0);
+        let (horizon, angle) = (state.horizon(Viewport::FULL), state.camera().angle);
+
+        // Too far off to feel
+        state.shake_from_blast(6.0 + BLAST_REACH, 6.5);
+        assert_eq!(state.shake_offset(), (0, 0.0));
+
+        state.shake_from_blast(6.5, 6.5);
+        let mut shaken = false;
+        for _ in 0..10 {
+            state.stats.frames += 1;
+            let (offset, jitter) = state.shake_offset();
+            assert!(abs(offset as f32) <= MAX_OFFSET && abs(jitter) <= MAX_JITTER);
+            assert_eq!(state.horizon(Viewport::FULL), horizon + offset);
+            assert_eq!(state.camera().angle, angle + jitter);
+            shaken |= offset != 0;
+        }
+        assert!(shaken);
+
+        for _ in 0..=(1.0 / RECOVERY) as u32 {
+            state.update_shake();
+        }
+        assert_eq!(state.shake_offset(), (0, 0.0));
+    }
+}
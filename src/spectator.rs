@@ -0,0 +1,117 @@
+// The spectator camera: a free flight through the level, walls and all, for
+// debugging it or watching the other players. Holding up and down together
+// while pressing BUTTON_2 takes off from play, and so does tapping BUTTON_2
+// on the game over screen in co-op; BUTTON_1 lands again. The level goes on
+// meanwhile, though nobody can be hurt, and the player is put back where
+// they took off, so nothing the camera does counts
+use core::mem::swap;
+
+use crate::input::Input;
+use crate::player::RADIUS;
+use crate::{GameMode, State};
+
+#[derive(Clone, Copy)]
+pub struct Spectator {
+    // Where the player was and what they were doing when the camera took over
+    x: f32,
+    y: f32,
+    angle: f32,
+    mode: GameMode,
+}
+
+// Whether this frame's buttons are the combo that takes off from play
+pub fn toggled(input: &Input) -> bool {
+    input.up.held() && input.down.held() && input.button_2.pressed()
+}
+
+impl State {
+    // Hand the view over to the free camera, starting from the player's eyes
+    pub(crate) fn take_off(&mut self) {
+        let (x, y, angle) = (self.player_x, self.player_y, self.player_angle);
+        self.spectator = Some(Spectator { x, y, angle, mode: self.mode });
+        self.mode = GameMode::Spectating;
+    }
+
+    // Put the player back where the camera took over from
+    pub(crate) fn land(&mut self) {
+        let Some(spectator) = self.spectator.take() else { return };
+        (self.player_x, self.player_y) = (spectator.x, spectator.y);
+        self.player_angle = spectator.angle;
+        (self.velocity_x, self.velocity_y) = (0.0, 0.0);
+        self.mode = spectator.mode;
+    }
+
+    // A frame of flying about, kept over the map, while everyone else plays on
+    // around the player where they took off, so enemies never see the camera
+    pub(crate) fn update_spectator(&mut self) {
+        self.move_player();
+        let (width, height) = (self.map().width() as f32, self.map().height() as f32);
+        self.player_x = self.player_x.clamp(RADIUS, width - RADIUS);
+        self.player_y = self.player_y.clamp(RADIUS, height - RADIUS);
+
+        self.swap_camera();
+        self.update_level();
+        self.swap_camera();
+    }
+
+    // Trade the camera in the player's fields for where they took off, or back
+    fn swap_camera(&mut self) {
+        let Some(spectator) = &mut self.spectator else { return };
+        swap(&mut self.player_x, &mut spectator.x);
+        swap(&mut self.player_y, &mut spectator.y);
+        swap(&mut self.player_angle, &mut spectator.angle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enemies::{Archetype, Enemy, MAX_ENEMIES};
+    use crate::platform::{BUTTON_1, BUTTON_2, BUTTON_DOWN, BUTTON_UP};
+
+    #[test]
+    fn the_camera_flies_through_walls_and_lands_where_it_left() {
+        let mut state = State::new();
+        state.transition = 0;
+        state.teleport(4.2, 1.5, core::f32::consts::FRAC_PI_2);
+        let start = state.player();
+
+        state.input.replay(BUTTON_UP | BUTTON_DOWN | BUTTON_2);
+        assert!(toggled(&state.input));
+        state.take_off();
+
+        // Facing the wall along the top of the map and flying into it
+        for _ in 0..30 {
+            state.input.replay(BUTTON_UP);
+            state.update_spectator();
+        }
+        assert!(state.point_in_wall(state.player_x, state.player_y));
+        assert!(state.player_y >= RADIUS);
+
+        state.input.replay(BUTTON_1);
+        state.land();
+        assert_eq!(state.player(), start);
+        assert!(state.mode == GameMode::Playing);
+    }
+
+    #[test]
+    fn enemies_pay_the_camera_no_mind() {
+        let mut state = State::new();
+        state.transition = 0;
+        state.teleport(1.5, 1.5, 0.0);
+        state.enemies = [None; MAX_ENEMIES];
+        state.enemies[0] = Some(Enemy::new(Archetype::Guard, 8.5, 6.5));
+        state.take_off();
+
+        // Hovering right in front of the guard, in its sight the whole time
+        state.teleport(5.5, 6.5, 0.0);
+        for _ in 0..60 {
+            state.input.replay(0);
+            state.update_spectator();
+        }
+        let guard = state.enemies[0].unwrap();
+        assert!(!guard.mode.hunting());
+        assert_eq!((guard.x, guard.y), (8.5, 6.5));
+        assert!(state.projectiles.iter().all(Option::is_none));
+    }
+}
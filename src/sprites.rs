@@ -0,0 +1,751 @@
+// Billboard sprites projected into the 3D view
+use core::f32::consts::TAU;
+
+use crate::flashlight::Light;
+use crate::map::{MIRROR, PORTAL};
+use crate::math::{atan2, cos, floor, sin};
+use crate::raycast::{Camera, RayHit, Viewport};
+use crate::render::{fog_shade, set_pixel};
+use crate::texture::{self, Texture, BYTES, SIZE};
+use crate::State;
+
+// Most sprites drawn in a single frame
+pub const MAX_SPRITES: usize = 48;
+
+// Sprites closer than this are behind the camera plane
+const NEAR_PLANE: f32 = 0.1;
+
+// A world-positioned billboard; palette index 0 is transparent
+#[derive(Clone, Copy)]
+pub struct Sprite {
+    pub x: f32,
+    pub y: f32,
+    pub image: &'static Texture,
+}
+
+// Frames played one after another, FRAME_TIME frames each, holding on the last
+pub struct Animation {
+    pub frames: &'static [&'static Texture],
+}
+
+const FRAME_TIME: u32 = 8;
+
+impl Animation {
+    pub fn frame(&self, age: u32) -> &'static Texture {
+        let last = self.frames.len() - 1;
+        self.frames[(age / FRAME_TIME).min(last as u32) as usize]
+    }
+}
+
+// A billboard playing through an animation once
+#[derive(Clone, Copy)]
+pub struct Animated {
+    pub x: f32,
+    pub y: f32,
+    animation: &'static Animation,
+    // Frames since it started
+    age: u32,
+}
+
+impl Animated {
+    pub const fn new(x: f32, y: f32, animation: &'static Animation) -> Animated {
+        Animated { x, y, animation, age: 0 }
+    }
+
+    // Move the animation on a frame, stopping the count once it is done so
+    // it never wraps
+    pub fn tick(&mut self) {
+        let length = self.animation.frames.len() as u32 * FRAME_TIME;
+        self.age = (self.age + 1).min(length);
+    }
+
+    pub fn sprite(&self) -> Sprite {
+        Sprite { x: self.x, y: self.y, image: self.animation.frame(self.age) }
+    }
+}
+
+// Something drawn from eight sides, the one facing the viewer picked to
+// show which way it is turned
+pub struct Turning {
+    // Going round counterclockwise from the front
+    pub sides: [&'static Texture; 8],
+}
+
+impl Turning {
+    // The side seen of it at (x, y), facing `angle`, from (from_x, from_y)
+    pub fn side(&self, x: f32, y: f32, angle: f32, from_x: f32, from_y: f32) -> &'static Texture {
+        // Angles count counterclockwise with y pointing down the map
+        let towards = atan2(y - from_y, from_x - x);
+        let turns = (towards - angle) / TAU;
+        let eighth = floor((turns - floor(turns)) * 8.0 + 0.5) as usize;
+
+        self.sides[eighth % 8]
+    }
+}
+
+// An image flipped left to right, for the side of something turned the
+// other way
+const fn mirror(image: &Texture) -> Texture {
+    let mut mirrored = [0; BYTES];
+    let mut idx = 0;
+
+    while idx < SIZE * SIZE {
+        let from = idx - idx % SIZE + SIZE - 1 - idx % SIZE;
+        let color = image[from / 4] >> (6 - (from % 4) * 2) & 0b11;
+        mirrored[idx / 4] |= color << (6 - (idx % 4) * 2);
+        idx += 1;
+    }
+
+    mirrored
+}
+
+pub const BARREL: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x3f, 0xfc, 0x00, 0x03, 0xaa, 0xaa, 0xc0,
+    0x03, 0xff, 0xff, 0xc0, 0x03, 0x99, 0x99, 0xc0,
+    0x03, 0x99, 0x99, 0xc0, 0x03, 0xff, 0xff, 0xc0,
+    0x03, 0x99, 0x99, 0xc0, 0x03, 0x99, 0x99, 0xc0,
+    0x03, 0xff, 0xff, 0xc0, 0x00, 0xea, 0xab, 0x00,
+];
+
+pub const LAMP: Texture = [
+    0x00, 0x0f, 0xf0, 0x00, 0x00, 0x35, 0x5c, 0x00,
+    0x00, 0xd4, 0x17, 0x00, 0x00, 0xd0, 0x07, 0x00,
+    0x00, 0x34, 0x1c, 0x00, 0x00, 0x0f, 0xf0, 0x00,
+    0x00, 0x03, 0xc0, 0x00, 0x00, 0x03, 0xc0, 0x00,
+    0x00, 0x03, 0xc0, 0x00, 0x00, 0x03, 0xc0, 0x00,
+    0x00, 0x03, 0xc0, 0x00, 0x00, 0x03, 0xc0, 0x00,
+    0x00, 0x03, 0xc0, 0x00, 0x00, 0x03, 0xc0, 0x00,
+    0x00, 0x0e, 0xb0, 0x00, 0x00, 0x3f, 0xfc, 0x00,
+];
+
+pub const GUARD: Texture = [
+    0x00, 0x0f, 0xf0, 0x00, 0x00, 0x3a, 0xac, 0x00,
+    0x00, 0xe6, 0x9b, 0x00, 0x00, 0xee, 0xbb, 0x00,
+    0x00, 0x3a, 0xac, 0x00, 0x00, 0x0e, 0xb0, 0x00,
+    0x03, 0xfa, 0xaf, 0xc0, 0x0e, 0xab, 0xea, 0xb0,
+    0x3a, 0xba, 0xae, 0xac, 0x3b, 0x3a, 0xac, 0xec,
+    0x3b, 0x3a, 0xac, 0xec, 0x00, 0x3e, 0xbc, 0x00,
+    0x00, 0x38, 0x2c, 0x00, 0x00, 0x38, 0x2c, 0x00,
+    0x00, 0x38, 0x2c, 0x00, 0x00, 0xfc, 0x3f, 0x00,
+];
+
+// The guard with its arms raised to strike
+pub const GUARD_ATTACK: Texture = [
+    0x00, 0x0f, 0xf0, 0x00, 0x00, 0x3a, 0xac, 0x00,
+    0x00, 0xe6, 0x9b, 0x00, 0x3c, 0xee, 0xbb, 0x3c,
+    0x3b, 0x3a, 0xac, 0xec, 0x0e, 0xce, 0xb3, 0xb0,
+    0x03, 0xfa, 0xaf, 0xc0, 0x00, 0x3a, 0xac, 0x00,
+    0x00, 0x3a, 0xac, 0x00, 0x00, 0x3a, 0xac, 0x00,
+    0x00, 0x3a, 0xac, 0x00, 0x00, 0x3e, 0xbc, 0x00,
+    0x00, 0x38, 0x2c, 0x00, 0x00, 0x38, 0x2c, 0x00,
+    0x00, 0x38, 0x2c, 0x00, 0x00, 0xfc, 0x3f, 0x00,
+];
+
+// A lean runner, mid-stride
+pub const RUNNER: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x0f, 0xc0, 0x00,
+    0x00, 0x35, 0x70, 0x00, 0x00, 0x37, 0x70, 0x00,
+    0x00, 0x35, 0x70, 0x00, 0x00, 0x0d, 0xc0, 0x00,
+    0x00, 0xf5, 0x7c, 0x00, 0x03, 0x4d, 0x71, 0xc0,
+    0x0d, 0x0d, 0x70, 0x70, 0x0c, 0x0d, 0x70, 0x30,
+    0x00, 0x0d, 0x70, 0x00, 0x00, 0x3c, 0x7c, 0x00,
+    0x00, 0xd0, 0x1c, 0x00, 0x03, 0x40, 0x07, 0x00,
+    0x0d, 0x00, 0x01, 0xc0, 0x3f, 0x00, 0x03, 0xf0,
+];
+
+pub const RUNNER_ATTACK: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x0f, 0xc0, 0x00,
+    0x00, 0x35, 0x70, 0x00, 0x3c, 0x37, 0x70, 0xf0,
+    0x37, 0x35, 0x73, 0x70, 0x0d, 0xcd, 0xcd, 0xc0,
+    0x03, 0xd5, 0x5f, 0x00, 0x00, 0x0d, 0x70, 0x00,
+    0x00, 0x0d, 0x70, 0x00, 0x00, 0x0d, 0x70, 0x00,
+    0x00, 0x0d, 0x70, 0x00, 0x00, 0x3c, 0x7c, 0x00,
+    0x00, 0x34, 0x1c, 0x00, 0x00, 0x34, 0x1c, 0x00,
+    0x00, 0x34, 0x1c, 0x00, 0x00, 0xfc, 0x3f, 0x00,
+];
+
+// A hulking brute, slow but hard to bring down
+pub const BRUTE: Texture = [
+    0x00, 0x3f, 0xfc, 0x00, 0x00, 0xea, 0xab, 0x00,
+    0x03, 0x9a, 0xa6, 0xc0, 0x03, 0xaa, 0xaa, 0xc0,
+    0x00, 0xef, 0xfb, 0x00, 0x0f, 0xea, 0xab, 0xf0,
+    0x3a, 0xaa, 0xaa, 0xac, 0xea, 0xba, 0xae, 0xab,
+    0xeb, 0xea, 0xab, 0xeb, 0xeb, 0x3a, 0xaa, 0xce,
+    0xeb, 0x3f, 0xff, 0xce, 0xfc, 0x3a, 0xaa, 0xcf,
+    0x00, 0x3a, 0xaa, 0xc0, 0x00, 0x3a, 0xce, 0xc0,
+    0x00, 0xea, 0xce, 0xb0, 0x00, 0xff, 0xcf, 0xf0,
+];
+
+pub const BRUTE_ATTACK: Texture = [
+    0x3f, 0x3f, 0xfc, 0xfc, 0xeb, 0xea, 0xab, 0xeb,
+    0xeb, 0x9a, 0xa6, 0xeb, 0x3a, 0xea, 0xaa, 0xec,
+    0x0e, 0xef, 0xfb, 0xbc, 0x03, 0xea, 0xaa, 0xf0,
+    0x0e, 0xaa, 0xaa, 0xac, 0x0e, 0xae, 0xab, 0xab,
+    0x03, 0xea, 0xab, 0xc0, 0x00, 0x3a, 0xaa, 0xc0,
+    0x00, 0x3f, 0xff, 0xc0, 0x00, 0x3a, 0xaa, 0xc0,
+    0x00, 0x3a, 0xaa, 0xc0, 0x00, 0x3a, 0xce, 0xc0,
+    0x00, 0xea, 0xce, 0xb0, 0x00, 0xff, 0xcf, 0xf0,
+];
+
+// A hooded sniper, rifle levelled
+pub const SNIPER: Texture = [
+    0x00, 0x0f, 0xf0, 0x00, 0x00, 0x3f, 0xfc, 0x00,
+    0x00, 0x37, 0xdc, 0x00, 0x00, 0x3a, 0xac, 0x00,
+    0x00, 0x0e, 0xb0, 0x00, 0x00, 0x3a, 0xac, 0x00,
+    0x00, 0xea, 0xab, 0x00, 0x03, 0xba, 0xae, 0xc0,
+    0x03, 0xbf, 0xff, 0xff, 0x03, 0xba, 0xae, 0xc0,
+    0x00, 0xea, 0xa8, 0x00, 0x00, 0x3a, 0xac, 0x00,
+    0x00, 0x38, 0x2c, 0x00, 0x00, 0x38, 0x2c, 0x00,
+    0x00, 0x38, 0x2c, 0x00, 0x00, 0xfc, 0x3f, 0x00,
+];
+
+pub const SNIPER_ATTACK: Texture = [
+    0x00, 0x0f, 0xf0, 0x00, 0x00, 0x3f, 0xfc, 0x00,
+    0x00, 0x37, 0xdc, 0x00, 0x00, 0x3a, 0xac, 0x00,
+    0x00, 0x0e, 0xb0, 0x00, 0x00, 0x3a, 0xac, 0x00,
+    0x00, 0xea, 0xab, 0x04, 0x03, 0xba, 0xae, 0xd5,
+    0x03, 0xbf, 0xff, 0xff, 0x03, 0xba, 0xae, 0xd5,
+    0x00, 0xea, 0xa8, 0x04, 0x00, 0x3a, 0xac, 0x00,
+    0x00, 0x38, 0x2c, 0x00, 0x00, 0x38, 0x2c, 0x00,
+    0x00, 0x38, 0x2c, 0x00, 0x00, 0xfc, 0x3f, 0x00,
+];
+
+// The horned warlord guarding the last arena
+pub const BOSS: Texture = [
+    0xc0, 0x00, 0x00, 0x03, 0xf0, 0x3f, 0xfc, 0x0f,
+    0x3c, 0xea, 0xab, 0x3c, 0x0f, 0x9a, 0xa6, 0xf0,
+    0x03, 0xa9, 0x6a, 0xc0, 0x00, 0xef, 0xfb, 0x00,
+    0x0f, 0xea, 0xab, 0xf0, 0x3a, 0xaa, 0xaa, 0xac,
+    0xeb, 0xab, 0xea, 0xeb, 0xeb, 0xaa, 0xaa, 0xeb,
+    0xeb, 0xea, 0xab, 0xeb, 0xfc, 0xea, 0xab, 0x3f,
+    0x00, 0xea, 0xab, 0x00, 0x00, 0xeb, 0x3a, 0xc0,
+    0x03, 0xab, 0x3a, 0xb0, 0x03, 0xff, 0x3f, 0xf0,
+];
+
+pub const BOSS_ATTACK: Texture = [
+    0x10, 0x00, 0x00, 0x04, 0xdc, 0x3f, 0xfc, 0x37,
+    0xec, 0xea, 0xab, 0x3b, 0xef, 0x9a, 0xa6, 0xfb,
+    0x3b, 0xa9, 0x6a, 0xec, 0x0f, 0xef, 0xfb, 0xfc,
+    0x00, 0xea, 0xab, 0x00, 0x03, 0xaa, 0xaa, 0xc0,
+    0x03, 0xab, 0xea, 0xc0, 0x03, 0xaa, 0xaa, 0xc0,
+    0x03, 0xea, 0xab, 0xc0, 0x00, 0xea, 0xab, 0x00,
+    0x00, 0xea, 0xab, 0x00, 0x00, 0xeb, 0x3a, 0xc0,
+    0x03, 0xab, 0x3a, 0xb0, 0x03, 0xff, 0x3f, 0xf0,
+];
+
+pub const BOSS_FALLING: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x03,
+    0xf0, 0x3f, 0xfc, 0x0f, 0x3c, 0xea, 0xab, 0x3c,
+    0x0f, 0x9a, 0xa6, 0xf0, 0x03, 0xa9, 0x6a, 0xc0,
+    0x00, 0xef, 0xfb, 0x00, 0x0f, 0xea, 0xab, 0xf0,
+    0x3a, 0xaa, 0xaa, 0xac, 0xeb, 0xab, 0xea, 0xeb,
+    0xeb, 0xaa, 0xaa, 0xeb, 0xeb, 0xea, 0xab, 0xeb,
+    0x03, 0xab, 0x3a, 0xb0, 0x03, 0xff, 0x3f, 0xf0,
+];
+
+pub const BOSS_FALLEN: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xc0, 0x00, 0x00, 0x03, 0xf0, 0x3f, 0xfc, 0x0f,
+    0x3c, 0xea, 0xab, 0x3c, 0x0f, 0x9a, 0xa6, 0xf0,
+    0x03, 0xa9, 0x6a, 0xc0, 0x00, 0xef, 0xfb, 0x00,
+    0x03, 0xab, 0x3a, 0xb0, 0x03, 0xff, 0x3f, 0xf0,
+];
+
+pub const BOSS_CORPSE: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x0c, 0x00, 0x00, 0xc0, 0x3c, 0x00, 0xfc, 0xf0,
+    0x0f, 0xff, 0xab, 0xc0, 0x3e, 0xab, 0xe6, 0x9f,
+    0xea, 0xae, 0xab, 0xaa, 0x3f, 0xff, 0xff, 0xfc,
+];
+
+// Enemies sinking to the floor, then lying dead, for their death animations
+pub const GUARD_FALLING: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x0f, 0xf0, 0x00,
+    0x00, 0x3a, 0xac, 0x00, 0x00, 0xe6, 0x9b, 0x00,
+    0x00, 0xee, 0xbb, 0x00, 0x00, 0x3a, 0xac, 0x00,
+    0x00, 0x0e, 0xb0, 0x00, 0x03, 0xfa, 0xaf, 0xc0,
+    0x0e, 0xab, 0xea, 0xb0, 0x3a, 0xba, 0xae, 0xac,
+    0x3b, 0x3a, 0xac, 0xec, 0x00, 0x38, 0x2c, 0x00,
+    0x00, 0x38, 0x2c, 0x00, 0x00, 0xfc, 0x3f, 0x00,
+];
+
+pub const GUARD_FALLEN: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x0f, 0xf0, 0x00, 0x00, 0x3a, 0xac, 0x00,
+    0x00, 0xe6, 0x9b, 0x00, 0x00, 0xee, 0xbb, 0x00,
+    0x00, 0x3a, 0xac, 0x00, 0x00, 0x0e, 0xb0, 0x00,
+    0x00, 0x38, 0x2c, 0x00, 0x00, 0xfc, 0x3f, 0x00,
+];
+
+pub const GUARD_CORPSE: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xf0,
+    0x00, 0xff, 0x0e, 0xac, 0x3f, 0xaa, 0xf9, 0xa7,
+    0xea, 0xba, 0xae, 0xab, 0x3f, 0xff, 0xff, 0xfc,
+];
+
+pub const RUNNER_FALLING: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x0f, 0xc0, 0x00, 0x00, 0x35, 0x70, 0x00,
+    0x00, 0x37, 0x70, 0x00, 0x00, 0x35, 0x70, 0x00,
+    0x00, 0x0d, 0xc0, 0x00, 0x00, 0xf5, 0x7c, 0x00,
+    0x03, 0x4d, 0x71, 0xc0, 0x0d, 0x0d, 0x70, 0x70,
+    0x0c, 0x0d, 0x70, 0x30, 0x03, 0x40, 0x07, 0x00,
+    0x0d, 0x00, 0x01, 0xc0, 0x3f, 0x00, 0x03, 0xf0,
+];
+
+pub const RUNNER_FALLEN: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x0f, 0xc0, 0x00,
+    0x00, 0x35, 0x70, 0x00, 0x00, 0x37, 0x70, 0x00,
+    0x00, 0x35, 0x70, 0x00, 0x00, 0x0d, 0xc0, 0x00,
+    0x0d, 0x00, 0x01, 0xc0, 0x3f, 0x00, 0x03, 0xf0,
+];
+
+pub const RUNNER_CORPSE: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xf0,
+    0x00, 0xff, 0x0d, 0x5c, 0x3f, 0x55, 0xf7, 0x5f,
+    0xd5, 0x75, 0x5d, 0x57, 0x3f, 0xff, 0xff, 0xfc,
+];
+
+pub const BRUTE_FALLING: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x3f, 0xfc, 0x00,
+    0x00, 0xea, 0xab, 0x00, 0x03, 0x9a, 0xa6, 0xc0,
+    0x03, 0xaa, 0xaa, 0xc0, 0x00, 0xef, 0xfb, 0x00,
+    0x0f, 0xea, 0xab, 0xf0, 0x3a, 0xaa, 0xaa, 0xac,
+    0xea, 0xba, 0xae, 0xab, 0xeb, 0xea, 0xab, 0xeb,
+    0xeb, 0x3a, 0xaa, 0xce, 0x00, 0x3a, 0xce, 0xc0,
+    0x00, 0xea, 0xce, 0xb0, 0x00, 0xff, 0xcf, 0xf0,
+];
+
+pub const BRUTE_FALLEN: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x3f, 0xfc, 0x00, 0x00, 0xea, 0xab, 0x00,
+    0x03, 0x9a, 0xa6, 0xc0, 0x03, 0xaa, 0xaa, 0xc0,
+    0x00, 0xef, 0xfb, 0x00, 0x0f, 0xea, 0xab, 0xf0,
+    0x00, 0xea, 0xce, 0xb0, 0x00, 0xff, 0xcf, 0xf0,
+];
+
+pub const BRUTE_CORPSE: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xf0,
+    0x00, 0xff, 0x0e, 0xac, 0x3f, 0xaa, 0xf9, 0xa7,
+    0xea, 0xba, 0xae, 0xab, 0x3f, 0xff, 0xff, 0xfc,
+];
+
+pub const SNIPER_FALLING: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x0f, 0xf0, 0x00,
+    0x00, 0x3f, 0xfc, 0x00, 0x00, 0x37, 0xdc, 0x00,
+    0x00, 0x3a, 0xac, 0x00, 0x00, 0x0e, 0xb0, 0x00,
+    0x00, 0x3a, 0xac, 0x00, 0x00, 0xea, 0xab, 0x00,
+    0x03, 0xba, 0xae, 0xc0, 0x03, 0xbf, 0xff, 0xff,
+    0x03, 0xba, 0xae, 0xc0, 0x00, 0x38, 0x2c, 0x00,
+    0x00, 0x38, 0x2c, 0x00, 0x00, 0xfc, 0x3f, 0x00,
+];
+
+pub const SNIPER_FALLEN: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x0f, 0xf0, 0x00, 0x00, 0x3f, 0xfc, 0x00,
+    0x00, 0x37, 0xdc, 0x00, 0x00, 0x3a, 0xac, 0x00,
+    0x00, 0x0e, 0xb0, 0x00, 0x00, 0x3a, 0xac, 0x00,
+    0x00, 0x38, 0x2c, 0x00, 0x00, 0xfc, 0x3f, 0x00,
+];
+
+pub const SNIPER_CORPSE: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xf0,
+    0x00, 0xff, 0x0e, 0xac, 0x3f, 0xaa, 0xf9, 0xa7,
+    0xea, 0xba, 0xae, 0xab, 0x3f, 0xff, 0xff, 0xfc,
+];
+
+pub const MEDKIT: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x0f, 0xf0, 0x00,
+    0x00, 0x35, 0x5c, 0x00, 0x03, 0xff, 0xff, 0xc0,
+    0x03, 0x57, 0xd5, 0xc0, 0x03, 0x57, 0xd5, 0xc0,
+    0x03, 0x7f, 0xfd, 0xc0, 0x03, 0x7f, 0xfd, 0xc0,
+    0x03, 0x57, 0xd5, 0xc0, 0x03, 0xff, 0xff, 0xc0,
+];
+
+pub const AMMO: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0xcc, 0xcc, 0x00, 0x03, 0x77, 0x77, 0x00,
+    0x03, 0xbb, 0xbb, 0x00, 0x0f, 0xff, 0xff, 0xf0,
+    0x0e, 0xaa, 0xaa, 0xb0, 0x0e, 0xff, 0xff, 0xb0,
+    0x0e, 0xaa, 0xaa, 0xb0, 0x0f, 0xff, 0xff, 0xf0,
+];
+
+pub const FLASHLIGHT: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xf0,
+    0x0f, 0xff, 0xfd, 0x5c, 0x0e, 0xaa, 0xad, 0x5c,
+    0x0f, 0xff, 0xfd, 0x5c, 0x00, 0x00, 0x03, 0xf0,
+];
+
+pub const SILVER_KEY: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x0f, 0xf0, 0x00, 0x00,
+    0x35, 0x5c, 0x00, 0x00, 0xd4, 0x17, 0xff, 0xff,
+    0xd4, 0x15, 0x55, 0x57, 0x35, 0x5f, 0xfd, 0xdc,
+    0x0f, 0xf0, 0x03, 0x30, 0x00, 0x00, 0x00, 0x00,
+];
+
+pub const BRASS_KEY: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x0f, 0xf0, 0x00, 0x00,
+    0x3a, 0xac, 0x00, 0x00, 0xe8, 0x2b, 0xff, 0xff,
+    0xe8, 0x2a, 0xaa, 0xab, 0x3a, 0xaf, 0xfe, 0xec,
+    0x0f, 0xf0, 0x03, 0x30, 0x00, 0x00, 0x00, 0x00,
+];
+
+pub const IRON_KEY: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x0f, 0xf0, 0x00, 0x00,
+    0x3f, 0xfc, 0x00, 0x00, 0xfc, 0x3f, 0xff, 0xff,
+    0xfc, 0x3f, 0xff, 0xff, 0x3f, 0xff, 0xff, 0xfc,
+    0x0f, 0xf0, 0x03, 0x30, 0x00, 0x00, 0x00, 0x00,
+];
+
+// A glowing ball with a dark rim, for enemy shots in flight
+pub const SHOT: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xc0, 0x00,
+    0x00, 0x0d, 0x70, 0x00, 0x00, 0x35, 0x5c, 0x00,
+    0x00, 0x35, 0x5c, 0x00, 0x00, 0x0d, 0x70, 0x00,
+    0x00, 0x03, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+// A rocket in flight, nose first with its exhaust trailing
+pub const ROCKET: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x03, 0xc0, 0x00, 0x00, 0x0e, 0xb0, 0x00,
+    0x00, 0x0e, 0xb0, 0x00, 0x00, 0x01, 0x40, 0x00,
+    0x00, 0x01, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+// A spark struck off a wall
+pub const SPARK: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x40, 0x00,
+    0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+// Chunks of rubble thrown out across the floor by a burst
+pub const DEBRIS: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x30, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+// Blood spattered on the floor by a falling enemy
+pub const BLOOD: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0xc0, 0x00,
+    0x00, 0x03, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+// A pair of rockets standing on their fins, for the pickup
+pub const ROCKETS: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x30, 0x0c, 0x00, 0x00, 0xdc, 0x37, 0x00,
+    0x00, 0xec, 0x3b, 0x00, 0x00, 0xec, 0x3b, 0x00,
+    0x00, 0xec, 0x3b, 0x00, 0x00, 0xec, 0x3b, 0x00,
+    0x03, 0xef, 0xfb, 0xf0, 0x03, 0x33, 0x0c, 0xcc,
+];
+
+// The rapid-fire gun lying on its side, for the pickup
+pub const RAPID_GUN: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x0f, 0xff, 0xff, 0x00, 0x3a, 0xaa, 0xaa, 0xfc,
+    0x3b, 0xff, 0xf8, 0x00, 0x38, 0xce, 0xac, 0x00,
+    0x0c, 0x03, 0xac, 0x00, 0x00, 0x03, 0xfc, 0x00,
+];
+
+// A figure for the other players, seen from the front, three quarters on
+// and side on facing left, and from behind
+pub const FIGURE: Texture = [
+    0x00, 0x05, 0x50, 0x00, 0x00, 0x1a, 0xa4, 0x00,
+    0x00, 0x6a, 0xa9, 0x00, 0x00, 0x7f, 0xfd, 0x00,
+    0x00, 0x1a, 0xa4, 0x00, 0x00, 0x06, 0x90, 0x00,
+    0x01, 0x5a, 0xa5, 0x40, 0x06, 0xa9, 0x6a, 0x90,
+    0x1a, 0x9a, 0xa6, 0xa4, 0x19, 0x1a, 0xa4, 0x64,
+    0x19, 0x1a, 0xa4, 0x64, 0x00, 0x16, 0x94, 0x00,
+    0x00, 0x18, 0x24, 0x00, 0x00, 0x18, 0x24, 0x00,
+    0x00, 0x18, 0x24, 0x00, 0x00, 0x54, 0x15, 0x00,
+];
+
+pub const FIGURE_FRONT_LEFT: Texture = [
+    0x00, 0x05, 0x50, 0x00, 0x00, 0x1a, 0xa4, 0x00,
+    0x00, 0x6a, 0xa9, 0x00, 0x00, 0x7f, 0xa9, 0x00,
+    0x00, 0x1a, 0xa4, 0x00, 0x00, 0x06, 0x90, 0x00,
+    0x00, 0x56, 0xa5, 0x00, 0x01, 0xa9, 0x6a, 0x90,
+    0x06, 0x9a, 0xa6, 0xa4, 0x06, 0x46, 0xa9, 0x64,
+    0x06, 0x46, 0xa9, 0x50, 0x00, 0x16, 0x94, 0x00,
+    0x00, 0x18, 0x24, 0x00, 0x00, 0x60, 0x24, 0x00,
+    0x00, 0x60, 0x24, 0x00, 0x01, 0x50, 0x54, 0x00,
+];
+
+pub const FIGURE_LEFT: Texture = [
+    0x00, 0x05, 0x50, 0x00, 0x00, 0x1a, 0xa4, 0x00,
+    0x00, 0x7a, 0xa9, 0x00, 0x00, 0x7e, 0xa9, 0x00,
+    0x00, 0x1a, 0xa4, 0x00, 0x00, 0x06, 0x90, 0x00,
+    0x00, 0x06, 0xa4, 0x00, 0x00, 0x1a, 0xa9, 0x00,
+    0x00, 0x1a, 0xa9, 0x00, 0x00, 0x16, 0x69, 0x00,
+    0x00, 0x16, 0x69, 0x00, 0x00, 0x06, 0xa4, 0x00,
+    0x00, 0x06, 0x64, 0x00, 0x00, 0x18, 0x19, 0x00,
+    0x00, 0x18, 0x09, 0x00, 0x00, 0x54, 0x05, 0x40,
+];
+
+pub const FIGURE_BACK_LEFT: Texture = [
+    0x00, 0x05, 0x50, 0x00, 0x00, 0x1a, 0xa4, 0x00,
+    0x00, 0x6a, 0xa9, 0x00, 0x00, 0xea, 0xa9, 0x00,
+    0x00, 0x1a, 0xa4, 0x00, 0x00, 0x06, 0x90, 0x00,
+    0x00, 0x56, 0xa5, 0x00, 0x01, 0xaa, 0xaa, 0x90,
+    0x06, 0x9a, 0xaa, 0x64, 0x06, 0x46, 0xa9, 0x64,
+    0x06, 0x46, 0xa9, 0x50, 0x00, 0x16, 0x94, 0x00,
+    0x00, 0x18, 0x24, 0x00, 0x00, 0x18, 0x09, 0x00,
+    0x00, 0x18, 0x09, 0x00, 0x00, 0x15, 0x05, 0x40,
+];
+
+pub const FIGURE_BACK: Texture = [
+    0x00, 0x05, 0x50, 0x00, 0x00, 0x1a, 0xa4, 0x00,
+    0x00, 0x6a, 0xa9, 0x00, 0x00, 0x6a, 0xa9, 0x00,
+    0x00, 0x1a, 0xa4, 0x00, 0x00, 0x06, 0x90, 0x00,
+    0x01, 0x5a, 0xa5, 0x40, 0x06, 0xaa, 0xaa, 0x90,
+    0x1a, 0x6a, 0xa9, 0xa4, 0x19, 0x1a, 0xa4, 0x64,
+    0x19, 0x1a, 0xa4, 0x64, 0x00, 0x16, 0x94, 0x00,
+    0x00, 0x18, 0x24, 0x00, 0x00, 0x18, 0x24, 0x00,
+    0x00, 0x18, 0x24, 0x00, 0x00, 0x54, 0x15, 0x00,
+];
+
+// The figure from eight sides, going round counterclockwise from the front
+pub static FIGURES: Turning = Turning {
+    sides: [
+        &FIGURE,
+        &FIGURE_FRONT_LEFT,
+        &FIGURE_LEFT,
+        &FIGURE_BACK_LEFT,
+        &FIGURE_BACK,
+        &mirror(&FIGURE_BACK_LEFT),
+        &mirror(&FIGURE_LEFT),
+        &mirror(&FIGURE_FRONT_LEFT),
+    ],
+};
+
+// The figure again retracing the best run, drawn see-through. A static
+// rather than a const, so it has the one address to be told apart by
+pub static GHOST: Texture = FIGURE;
+
+// Returns the perpendicular depth and viewport column of a world point
+fn project(camera: Camera, x: f32, y: f32, viewport: Viewport) -> (f32, f32) {
+    let (rel_x, rel_y) = (x - camera.x, y - camera.y);
+    let (cos_a, sin_a) = (cos(camera.angle), sin(camera.angle));
+
+    let depth = rel_x * cos_a - rel_y * sin_a;
+    let lateral = rel_x * sin_a + rel_y * cos_a;
+
+    (depth, viewport.center() + lateral / depth * camera.focal_length())
+}
+
+// Draw sprites back to front across a viewport as a camera sees them,
+// hiding any columns behind a closer wall. Fences and windows are drawn
+// between two passes, one for the sprites seen through them and one for the
+// rest
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn render(
+    state: &State,
+    camera: Camera,
+    horizon: i32,
+    sprites: &[Sprite],
+    walls: &[RayHit; 160],
+    seen_through: bool,
+    light: Light,
+    viewport: Viewport,
+) {
+    let wall_height = state.projected_wall_height(camera);
+    let count = sprites.len().min(MAX_SPRITES);
+
+    let mut depths = [0.0; MAX_SPRITES];
+    let mut columns = [0.0; MAX_SPRITES];
+    let mut order = [0; MAX_SPRITES];
+
+    for (idx, sprite) in sprites.iter().take(count).enumerate() {
+        (depths[idx], columns[idx]) = project(camera, sprite.x, sprite.y, viewport);
+        order[idx] = idx;
+    }
+
+    // Insertion sort, farthest first
+    for i in 1..count {
+        let mut j = i;
+        while j > 0 && depths[order[j - 1]] < depths[order[j]] {
+            order.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+
+    for &idx in order.iter().take(count) {
+        let depth = depths[idx];
+        if depth < NEAR_PLANE {
+            continue;
+        }
+
+        let size = (wall_height / depth) as i32;
+        let left = columns[idx] as i32 - size / 2;
+        let (x, y) = (floor(sprites[idx].x) as i32, floor(sprites[idx].y) as i32);
+        let level = state.lighting.level(x, y);
+        let top = horizon - size / 2;
+        // The ghost covers only every other pixel, so the view shows through
+        let see_through = core::ptr::eq(sprites[idx].image, &GHOST);
+
+        for column in left.max(0)..(left + size).min(viewport.columns as i32) {
+            let wall = &walls[column as usize];
+            // Nothing is seen in a mirror or through a portal but the walls
+            let shown = if seen_through {
+                let behind = wall.through_depth < depth && depth <= wall.depth;
+                let bent = matches!(wall.through_tile, MIRROR | PORTAL);
+                wall.through_depth > 0.0 && !bent && behind
+            } else {
+                depth <= wall.nearest()
+            };
+            if !shown {
+                continue;
+            }
+
+            let u = (column - left) as f32 / size as f32;
+            let x = viewport.left + column;
+            let reach = light.reach(column);
+
+            for y in top.max(viewport.top)..(top + size).min(viewport.bottom()) {
+                let v = (y - top) as f32 / size as f32;
+                let color = texture::sample(sprites[idx].image, u, v);
+
+                if color != 0 && !(see_through && (x + y) % 2 == 0) {
+                    set_pixel(x, y, (color + fog_shade(depth, reach, level, x, y)).min(3));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::PI;
+
+    #[test]
+    fn figures_show_the_side_turned_to_the_viewer() {
+        // Facing along +x, seen from in front, behind and either side
+        let side = |angle, from_x, from_y| FIGURES.side(5.0, 5.0, angle, from_x, from_y);
+        assert_eq!(side(0.0, 8.0, 5.0), &FIGURE);
+        assert_eq!(side(0.0, 2.0, 5.0), &FIGURE_BACK);
+        assert_eq!(side(0.0, 5.0, 2.0), &FIGURE_LEFT);
+        assert_eq!(side(0.0, 5.0, 8.0), &mirror(&FIGURE_LEFT));
+        assert_eq!(side(0.0, 8.0, 2.5), &FIGURE_FRONT_LEFT);
+
+        // Turning to face up the map brings the front round to that side
+        assert_eq!(side(PI / 2.0, 5.0, 2.0), &FIGURE);
+        assert_eq!(side(-PI / 2.0, 5.0, 2.0), &FIGURE_BACK);
+    }
+
+    #[test]
+    fn mirroring_flips_images_left_to_right() {
+        let mirrored = mirror(&FIGURE_LEFT);
+        assert_eq!(mirror(&mirrored), FIGURE_LEFT);
+
+        for (u, v) in [(0.1, 0.2), (0.3, 0.5), (0.45, 0.9)] {
+            let flipped = texture::sample(&FIGURE_LEFT, 1.0 - u, v);
+            assert_eq!(texture::sample(&mirrored, u, v), flipped);
+        }
+    }
+}
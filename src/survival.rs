@@ -0,0 +1,161 @@
+// Survival: endless, growing waves of enemies on the first level's map,
+// arriving at its enemy spawn points, with a breather and fresh pickups
+// between waves. The best wave reached is kept on disk
+use crate::enemies::{Archetype, Enemy, Noise, MAX_ENEMIES};
+use crate::hud::digits;
+use crate::levels::LEVELS;
+use crate::platform::{text, DRAW_COLORS, SCREEN_SIZE};
+use crate::save::Save;
+use crate::{compass, pickups, State};
+
+// The campaign level played on
+const LEVEL: usize = 0;
+
+// Frames of quiet before each wave, and between enemies arriving in one
+const BREATHER_FRAMES: u32 = 240;
+const SPAWN_INTERVAL: u32 = 40;
+
+// Enemies never arrive this close to the player, in tiles
+const MIN_SPAWN_DISTANCE: f32 = 4.0;
+
+// Later waves bring tougher enemies along with the guards, one more kind
+// every other wave
+const ARRIVALS: [Archetype; 4] =
+    [Archetype::Guard, Archetype::Runner, Archetype::Sniper, Archetype::Brute];
+
+#[derive(Clone, Copy)]
+pub struct Survival {
+    pub wave: u32,
+    // Enemies still to arrive this wave, and frames until the next one does
+    // or, once the wave is cleared, until the breather ends
+    left: u32,
+    timer: u32,
+    // The campaign progress to keep on disk and go back to afterwards
+    pub campaign: Save,
+}
+
+fn wave_size(wave: u32) -> u32 {
+    (wave * 2 + 1).min(MAX_ENEMIES as u32)
+}
+
+impl State {
+    // Leave the campaign for a fresh run from the first wave
+    pub(crate) fn start_survival(&mut self) {
+        let campaign = self.save();
+        self.survival = Some(Survival { wave: 0, left: 0, timer: 0, campaign });
+        self.level = LEVEL;
+        self.restart();
+    }
+
+    // Back to the start of the campaign level left for survival, keeping the
+    // best wave and any settings changed since
+    pub(crate) fn end_survival(&mut self) {
+        let save = self.save();
+        self.survival = None;
+        self.resume(&save);
+    }
+
+    // Start over from the first wave, on the level emptied of its enemies
+    pub(crate) fn reset_survival(&mut self) {
+        let Some(survival) = &mut self.survival else { return };
+        (survival.wave, survival.left, survival.timer) = (0, 0, BREATHER_FRAMES);
+        self.enemies = [None; MAX_ENEMIES];
+    }
+
+    pub(crate) fn update_survival(&mut self) {
+        let Some(mut survival) = self.survival else { return };
+
+        if survival.left > 0 {
+            survival.timer = survival.timer.saturating_sub(1);
+            if survival.timer == 0 && self.send_enemy(survival.wave) {
+                survival.left -= 1;
+                survival.timer = if survival.left > 0 { SPAWN_INTERVAL } else { BREATHER_FRAMES };
+            }
+        } else if self.enemies.iter().all(Option::is_none) {
+            // The wave is cleared, so breathe and then bring on the next
+            if survival.timer > 0 {
+                survival.timer -= 1;
+            } else {
+                survival.wave += 1;
+                survival.left = wave_size(survival.wave);
+                self.pickups = pickups::spawn(LEVELS[LEVEL].pickups);
+                self.best_wave = self.best_wave.max(survival.wave.min(u8::MAX as u32) as u8);
+            }
+        }
+
+        self.survival = Some(survival);
+    }
+
+    // Bring an enemy in at a spawn point away from the player, already on
+    // the hunt; false when there is no free slot or spawn point for it yet
+    fn send_enemy(&mut self, wave: u32) -> bool {
+        let Some(slot) = self.enemies.iter().position(Option::is_none) else { return false };
+
+        let points = LEVELS[LEVEL].enemies;
+        let kinds = (wave as usize).div_ceil(2).clamp(1, ARRIVALS.len());
+        let archetype = ARRIVALS[self.rng.below(kinds as u32) as usize];
+        let first = self.rng.below(points.len() as u32) as usize;
+
+        for step in 0..points.len() {
+            let point = &points[(first + step) % points.len()];
+            let (dx, dy) = (point.x - self.player_x, point.y - self.player_y);
+            if dx * dx + dy * dy < MIN_SPAWN_DISTANCE * MIN_SPAWN_DISTANCE {
+                continue;
+            }
+
+            let mut enemy = Enemy::new(archetype, point.x, point.y);
+            enemy.hear(&Noise { x: self.player_x, y: self.player_y, radius: f32::MAX });
+            self.enemies[slot] = Some(enemy);
+            return true;
+        }
+
+        false
+    }
+}
+
+// Kills so far in the top right, and the coming wave while waiting for it
+pub unsafe fn render(state: &State) {
+    let Some(survival) = &state.survival else { return };
+    let mut buffer = [0; 3];
+
+    *DRAW_COLORS = 0x1;
+    let top = compass::HEIGHT + 4;
+    text(b"KILLS", SCREEN_SIZE - 76, top);
+    text(digits(state.stats.kills, &mut buffer), SCREEN_SIZE - 28, top);
+
+    if survival.left == 0 && state.enemies.iter().all(Option::is_none) {
+        text(b"WAVE", 52, 40);
+        text(digits(survival.wave + 1, &mut buffer), 92, 40);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waves_grow_once_each_is_cleared() {
+        let mut state = State::new();
+        state.start_survival();
+        assert!(state.enemies.iter().all(Option::is_none));
+
+        for wave in 1..=3 {
+            while state.enemies.iter().flatten().count() < wave_size(wave) as usize {
+                state.update_survival();
+            }
+            assert_eq!(state.survival.unwrap().wave, wave);
+            assert_eq!(state.best_wave, wave as u8);
+
+            for enemy in state.enemies.iter().flatten() {
+                assert!(enemy.mode.hunting());
+                let (dx, dy) = (enemy.x - state.player_x, enemy.y - state.player_y);
+                assert!(dx * dx + dy * dy >= MIN_SPAWN_DISTANCE * MIN_SPAWN_DISTANCE);
+            }
+            state.enemies = [None; MAX_ENEMIES];
+        }
+
+        state.restart();
+        assert_eq!(state.survival.unwrap().wave, 0);
+        assert_eq!(state.best_wave, 3);
+    }
+}
@@ -0,0 +1,364 @@
+// Map textures, stored in the WASM-4 2BPP sprite format
+// (row-major, 4 pixels per byte, leftmost pixel in the high bits), and packed
+// with rle in the cart until State unpacks them into RAM
+use crate::rle;
+
+// Width and height of every texture in pixels
+pub const SIZE: usize = 16;
+
+// Bytes needed for one texture
+pub const BYTES: usize = SIZE * SIZE / 4;
+
+pub type Texture = [u8; BYTES];
+
+// The map textures, in the order they are packed
+#[derive(Clone, Copy)]
+pub enum Id {
+    Brick,
+    Cracked,
+    Door,
+    Exit,
+    SilverLock,
+    BrassLock,
+    IronLock,
+    Floor,
+    Hazard,
+    Lava,
+    Ceiling,
+    // Animated, with the frames after the first packed straight after it
+    Monitor,
+    Torch = 13,
+    Sky = 15,
+    // Mostly see-through, with palette index 0 left out when drawn
+    Fence,
+    Window,
+    Mirror,
+    Portal,
+    Water,
+}
+
+const COUNT: usize = 21;
+
+// Frames each frame of an animated texture is shown for
+const FRAME_TIME: u32 = 12;
+
+impl Id {
+    const fn frames(self) -> u32 {
+        match self {
+            Id::Monitor | Id::Torch => 2,
+            _ => 1,
+        }
+    }
+}
+
+// Run-length pack a texture at compile time, so only the packed bytes end up
+// in the cart
+macro_rules! packed {
+    ($texture:expr) => {{
+        const TEXTURE: [u8; rle::packed_len(&$texture)] = rle::pack(&$texture);
+        &TEXTURE
+    }};
+}
+
+const PACKED: [&[u8]; COUNT] = [
+    packed!(BRICK),
+    packed!(CRACKED),
+    packed!(DOOR),
+    packed!(EXIT),
+    packed!(SILVER_LOCK),
+    packed!(BRASS_LOCK),
+    packed!(IRON_LOCK),
+    packed!(FLOOR),
+    packed!(HAZARD),
+    packed!(LAVA),
+    packed!(CEILING),
+    packed!(MONITOR),
+    packed!(MONITOR_SCAN),
+    packed!(TORCH),
+    packed!(TORCH_FLICKER),
+    packed!(SKY),
+    packed!(FENCE),
+    packed!(WINDOW),
+    packed!(MIRROR),
+    packed!(PORTAL),
+    packed!(WATER),
+];
+
+// Every map texture, unpacked
+pub struct Textures([Texture; COUNT]);
+
+impl Textures {
+    pub const fn unpack() -> Self {
+        let mut images = [[0; BYTES]; COUNT];
+
+        let mut idx = 0;
+        while idx < COUNT {
+            rle::unpack(PACKED[idx], &mut images[idx]);
+            idx += 1;
+        }
+
+        Textures(images)
+    }
+
+    pub fn get(&self, id: Id) -> &Texture {
+        &self.0[id as usize]
+    }
+
+    // The frame of a texture showing at a point in time, counted in frames
+    pub fn animated(&self, id: Id, clock: u32) -> &Texture {
+        &self.0[id as usize + (clock / FRAME_TIME % id.frames()) as usize]
+    }
+}
+
+const BRICK: Texture = [
+    0xaa, 0xa9, 0xaa, 0xa9, 0xaa, 0xa9, 0xab, 0xa9,
+    0xae, 0xa9, 0xaa, 0xa9, 0x55, 0x55, 0x55, 0x55,
+    0xa9, 0xaa, 0xa9, 0xaa, 0xa9, 0xab, 0xa9, 0xaa,
+    0xa9, 0xaa, 0xa9, 0xae, 0x55, 0x55, 0x55, 0x55,
+    0xaa, 0xa9, 0xaa, 0xa9, 0xaa, 0xe9, 0xaa, 0xa9,
+    0xaa, 0xa9, 0xaa, 0xe9, 0x55, 0x55, 0x55, 0x55,
+    0xa9, 0xaa, 0xa9, 0xaa, 0xb9, 0xaa, 0xa9, 0xaa,
+    0xa9, 0xaa, 0xe9, 0xaa, 0x55, 0x55, 0x55, 0x55,
+];
+
+// Brick split by a crack, for walls that can be shot through
+const CRACKED: Texture = [
+    0xaa, 0xa9, 0xaa, 0xa9, 0xaa, 0xa9, 0xab, 0xa9,
+    0xae, 0xa9, 0x6a, 0xa9, 0x55, 0x55, 0x55, 0x55,
+    0xa9, 0xa9, 0xa9, 0xaa, 0xa9, 0xa7, 0xa9, 0xaa,
+    0xa9, 0x96, 0xa9, 0xae, 0x55, 0x55, 0x55, 0x55,
+    0xaa, 0xa9, 0x69, 0xa9, 0xaa, 0xe9, 0x96, 0xa9,
+    0xaa, 0xa9, 0x9a, 0xe9, 0x55, 0x55, 0x55, 0x55,
+    0xa9, 0xaa, 0x69, 0xaa, 0xb9, 0xaa, 0x99, 0xaa,
+    0xa9, 0xaa, 0xe5, 0xaa, 0x55, 0x55, 0x55, 0x55,
+];
+
+const DOOR: Texture = [
+    0xff, 0xff, 0xff, 0xff, 0xea, 0xaa, 0xaa, 0xab,
+    0xe5, 0x55, 0x55, 0x5b, 0xe6, 0xaa, 0xaa, 0x9b,
+    0xe6, 0xaa, 0xaa, 0x9b, 0xe6, 0xaa, 0xaa, 0x9b,
+    0xe5, 0x55, 0x55, 0x5b, 0xea, 0xaa, 0xaa, 0xfb,
+    0xea, 0xaa, 0xaa, 0xfb, 0xe5, 0x55, 0x55, 0x5b,
+    0xe6, 0xaa, 0xaa, 0x9b, 0xe6, 0xaa, 0xaa, 0x9b,
+    0xe6, 0xaa, 0xaa, 0x9b, 0xe5, 0x55, 0x55, 0x5b,
+    0xea, 0xaa, 0xaa, 0xab, 0xff, 0xff, 0xff, 0xff,
+];
+
+const EXIT: Texture = [
+    0xff, 0xff, 0xff, 0xff, 0xea, 0xaa, 0xaa, 0xab,
+    0xe5, 0x55, 0x55, 0x5b, 0xef, 0x77, 0x77, 0xfb,
+    0xed, 0x77, 0x75, 0xdb, 0xef, 0x5d, 0x75, 0xdb,
+    0xed, 0x77, 0x75, 0xdb, 0xef, 0x77, 0x75, 0xdb,
+    0xe5, 0x55, 0x55, 0x5b, 0xea, 0xaa, 0xaa, 0xab,
+    0xea, 0xaf, 0xfa, 0xab, 0xea, 0xad, 0x7a, 0xab,
+    0xea, 0xad, 0x7a, 0xab, 0xea, 0xaf, 0xfa, 0xab,
+    0xea, 0xaa, 0xaa, 0xab, 0xff, 0xff, 0xff, 0xff,
+];
+
+const SILVER_LOCK: Texture = [
+    0xff, 0xff, 0xff, 0xff, 0xea, 0xaa, 0xaa, 0xab,
+    0xe5, 0x55, 0x55, 0x5b, 0xe6, 0xaa, 0xaa, 0x9b,
+    0xe6, 0xaa, 0xaa, 0x9b, 0xe6, 0xaa, 0xaf, 0xff,
+    0xe5, 0x55, 0x5d, 0x5f, 0xea, 0xaa, 0xad, 0xdf,
+    0xea, 0xaa, 0xad, 0xdf, 0xe5, 0x55, 0x5d, 0x5f,
+    0xe6, 0xaa, 0xaf, 0xff, 0xe6, 0xaa, 0xaa, 0x9b,
+    0xe6, 0xaa, 0xaa, 0x9b, 0xe5, 0x55, 0x55, 0x5b,
+    0xea, 0xaa, 0xaa, 0xab, 0xff, 0xff, 0xff, 0xff,
+];
+
+const BRASS_LOCK: Texture = [
+    0xff, 0xff, 0xff, 0xff, 0xea, 0xaa, 0xaa, 0xab,
+    0xe5, 0x55, 0x55, 0x5b, 0xe6, 0xaa, 0xaa, 0x9b,
+    0xe6, 0xaa, 0xaa, 0x9b, 0xe6, 0xaa, 0xaf, 0xff,
+    0xe5, 0x55, 0x5e, 0xaf, 0xea, 0xaa, 0xae, 0xef,
+    0xea, 0xaa, 0xae, 0xef, 0xe5, 0x55, 0x5e, 0xaf,
+    0xe6, 0xaa, 0xaf, 0xff, 0xe6, 0xaa, 0xaa, 0x9b,
+    0xe6, 0xaa, 0xaa, 0x9b, 0xe5, 0x55, 0x55, 0x5b,
+    0xea, 0xaa, 0xaa, 0xab, 0xff, 0xff, 0xff, 0xff,
+];
+
+const IRON_LOCK: Texture = [
+    0xff, 0xff, 0xff, 0xff, 0xea, 0xaa, 0xaa, 0xab,
+    0xe5, 0x55, 0x55, 0x5b, 0xe6, 0xaa, 0xaa, 0x9b,
+    0xe6, 0xaa, 0xaa, 0x9b, 0xe6, 0xaa, 0xaf, 0xff,
+    0xe5, 0x55, 0x5f, 0xff, 0xea, 0xaa, 0xaf, 0x7f,
+    0xea, 0xaa, 0xaf, 0x7f, 0xe5, 0x55, 0x5f, 0xff,
+    0xe6, 0xaa, 0xaf, 0xff, 0xe6, 0xaa, 0xaa, 0x9b,
+    0xe6, 0xaa, 0xaa, 0x9b, 0xe5, 0x55, 0x55, 0x5b,
+    0xea, 0xaa, 0xaa, 0xab, 0xff, 0xff, 0xff, 0xff,
+];
+
+const FLOOR: Texture = [
+    0x95, 0x55, 0x55, 0x56, 0x55, 0x55, 0x51, 0x55,
+    0x51, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x45,
+    0x55, 0x51, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+    0x45, 0x55, 0x54, 0x55, 0x55, 0x55, 0x55, 0x55,
+    0x55, 0x55, 0x55, 0x55, 0x55, 0x51, 0x55, 0x51,
+    0x55, 0x55, 0x55, 0x55, 0x54, 0x55, 0x51, 0x55,
+    0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55,
+    0x55, 0x55, 0x45, 0x55, 0x95, 0x55, 0x55, 0x56,
+];
+
+const HAZARD: Texture = [
+    0xaa, 0xab, 0xfa, 0xaa, 0xa6, 0xaf, 0xab, 0xea,
+    0x9d, 0xba, 0xaa, 0xba, 0xa6, 0xaa, 0x9a, 0xae,
+    0xaa, 0xaa, 0xb6, 0xaa, 0xfe, 0xaa, 0xa6, 0xab,
+    0xab, 0xea, 0xaa, 0xfe, 0xaa, 0xaa, 0x6a, 0xaa,
+    0xaa, 0xa9, 0xda, 0xaa, 0xbf, 0xff, 0x9a, 0xaa,
+    0xea, 0xaa, 0xfa, 0xa6, 0xa6, 0xaa, 0xaf, 0xdd,
+    0x9d, 0xaa, 0xaa, 0xa6, 0xa6, 0xaf, 0xea, 0xaa,
+    0xaa, 0xbe, 0xaf, 0xaa, 0xaa, 0xea, 0xab, 0xfa,
+];
+
+const LAVA: Texture = [
+    0xfe, 0x41, 0xbf, 0xe4, 0xf9, 0x15, 0xbf, 0x91,
+    0xe4, 0x5a, 0xfe, 0x45, 0x91, 0xaf, 0xf9, 0x16,
+    0x46, 0xff, 0xf4, 0x6b, 0x1b, 0xff, 0xe4, 0x6f,
+    0x6f, 0xfa, 0x41, 0xbf, 0xbf, 0xe4, 0x16, 0xff,
+    0xff, 0x90, 0x5b, 0xff, 0xfe, 0x41, 0x6f, 0xfe,
+    0xf9, 0x05, 0xbf, 0xf9, 0xe4, 0x16, 0xff, 0xe4,
+    0x90, 0x5b, 0xff, 0x90, 0x41, 0x6f, 0xfe, 0x41,
+    0x05, 0xbf, 0xf9, 0x06, 0x16, 0xff, 0xe4, 0x1b,
+];
+
+const CEILING: Texture = [
+    0x40, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x40, 0x00,
+    0x00, 0x01, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x01,
+];
+
+// A screen in a metal casing, flickering with scan lines
+const MONITOR: Texture = [
+    0xff, 0xff, 0xff, 0xff, 0xea, 0xaa, 0xaa, 0xab,
+    0xef, 0xff, 0xff, 0xfb, 0xec, 0x00, 0x00, 0x3b,
+    0xed, 0x55, 0x55, 0x7b, 0xec, 0x00, 0x00, 0x3b,
+    0xed, 0x55, 0x55, 0x7b, 0xec, 0x00, 0x00, 0x3b,
+    0xed, 0x55, 0x55, 0x7b, 0xec, 0x00, 0x00, 0x3b,
+    0xed, 0x55, 0x55, 0x7b, 0xef, 0xff, 0xff, 0xfb,
+    0xea, 0xaa, 0xaa, 0xab, 0xea, 0xab, 0xea, 0xab,
+    0xea, 0xaa, 0xaa, 0xab, 0xff, 0xff, 0xff, 0xff,
+];
+
+// The same with the scan lines moved down a row
+const MONITOR_SCAN: Texture = [
+    0xff, 0xff, 0xff, 0xff, 0xea, 0xaa, 0xaa, 0xab,
+    0xef, 0xff, 0xff, 0xfb, 0xed, 0x55, 0x55, 0x7b,
+    0xec, 0x00, 0x00, 0x3b, 0xed, 0x55, 0x55, 0x7b,
+    0xec, 0x00, 0x00, 0x3b, 0xed, 0x55, 0x55, 0x7b,
+    0xec, 0x00, 0x00, 0x3b, 0xed, 0x55, 0x55, 0x7b,
+    0xec, 0x00, 0x00, 0x3b, 0xef, 0xff, 0xff, 0xfb,
+    0xea, 0xaa, 0xaa, 0xab, 0xea, 0xab, 0xea, 0xab,
+    0xea, 0xaa, 0xaa, 0xab, 0xff, 0xff, 0xff, 0xff,
+];
+
+// A torch burning in a bracket on a stone block
+const TORCH: Texture = [
+    0xff, 0xff, 0xff, 0xff, 0xaa, 0xaa, 0xaa, 0xab,
+    0xaa, 0xaa, 0xaa, 0xab, 0xaa, 0xa9, 0xaa, 0xab,
+    0xaa, 0xa4, 0x6a, 0xab, 0xaa, 0x90, 0x1a, 0xab,
+    0xaa, 0x90, 0x1a, 0xab, 0xaa, 0xa4, 0x6a, 0xab,
+    0xaa, 0xbf, 0xfa, 0xab, 0xaa, 0xaf, 0xea, 0xab,
+    0xaa, 0xab, 0xaa, 0xab, 0xaa, 0xab, 0xaa, 0xab,
+    0xaa, 0xab, 0xaa, 0xab, 0xaa, 0xaa, 0xaa, 0xab,
+    0xaa, 0xaa, 0xaa, 0xab, 0xff, 0xff, 0xff, 0xff,
+];
+
+// The same with the flame dipping to one side
+const TORCH_FLICKER: Texture = [
+    0xff, 0xff, 0xff, 0xff, 0xaa, 0xaa, 0xaa, 0xab,
+    0xaa, 0xaa, 0xaa, 0xab, 0xaa, 0xaa, 0xaa, 0xab,
+    0xaa, 0xaa, 0x6a, 0xab, 0xaa, 0xa4, 0x1a, 0xab,
+    0xaa, 0x90, 0x1a, 0xab, 0xaa, 0xa4, 0x6a, 0xab,
+    0xaa, 0xbf, 0xfa, 0xab, 0xaa, 0xaf, 0xea, 0xab,
+    0xaa, 0xab, 0xaa, 0xab, 0xaa, 0xab, 0xaa, 0xab,
+    0xaa, 0xab, 0xaa, 0xab, 0xaa, 0xaa, 0xaa, 0xab,
+    0xaa, 0xaa, 0xaa, 0xab, 0xff, 0xff, 0xff, 0xff,
+];
+
+// Clouds over outdoor areas, in a strip that wraps around the horizon
+const SKY: Texture = [
+    0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa,
+    0x69, 0xaa, 0xaa, 0xa9, 0x55, 0x56, 0xaa, 0x95,
+    0x55, 0x55, 0x55, 0x55, 0x55, 0x40, 0x55, 0x55,
+    0x54, 0x00, 0x05, 0x55, 0x55, 0x00, 0x15, 0x55,
+    0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x05,
+    0x05, 0x55, 0x50, 0x00, 0x55, 0x55, 0x55, 0x41,
+    0x55, 0x55, 0x55, 0x55, 0x11, 0x11, 0x11, 0x11,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+// Bars held between two rails, with gaps between them
+const FENCE: Texture = [
+    0x18, 0x18, 0x18, 0x18, 0x6a, 0xaa, 0xaa, 0xab,
+    0xff, 0xff, 0xff, 0xff, 0x18, 0x18, 0x18, 0x18,
+    0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18,
+    0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18,
+    0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18,
+    0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18,
+    0x18, 0x18, 0x18, 0x18, 0x6a, 0xaa, 0xaa, 0xab,
+    0xff, 0xff, 0xff, 0xff, 0x18, 0x18, 0x18, 0x18,
+];
+
+// Brick around a pane of glass, which is left blank
+const WINDOW: Texture = [
+    0xaa, 0xa9, 0xaa, 0xa9, 0xaa, 0xa9, 0xab, 0xa9,
+    0xaf, 0xff, 0xff, 0xf9, 0x5c, 0x01, 0x40, 0x35,
+    0xac, 0x01, 0x40, 0x3a, 0xac, 0x01, 0x40, 0x3a,
+    0xac, 0x01, 0x40, 0x3e, 0x5d, 0x55, 0x55, 0x75,
+    0xad, 0x55, 0x55, 0x79, 0xac, 0x01, 0x40, 0x39,
+    0xac, 0x01, 0x40, 0x39, 0x5c, 0x01, 0x40, 0x35,
+    0xac, 0x01, 0x40, 0x3a, 0xbf, 0xff, 0xff, 0xfa,
+    0xa9, 0xaa, 0xe9, 0xaa, 0x55, 0x55, 0x55, 0x55,
+];
+
+// A frame around glass, left blank for the reflection, with a glint on it
+const MIRROR: Texture = [
+    0xff, 0xff, 0xff, 0xff, 0xea, 0xaa, 0xaa, 0xab,
+    0xe0, 0x00, 0x00, 0x0b, 0xe0, 0x00, 0x10, 0x0b,
+    0xe0, 0x00, 0x40, 0x0b, 0xe0, 0x01, 0x00, 0x0b,
+    0xe0, 0x04, 0x00, 0x0b, 0xe0, 0x10, 0x00, 0x0b,
+    0xe0, 0x40, 0x01, 0x0b, 0xe1, 0x00, 0x04, 0x0b,
+    0xe0, 0x00, 0x10, 0x0b, 0xe0, 0x00, 0x40, 0x0b,
+    0xe0, 0x00, 0x00, 0x0b, 0xe0, 0x00, 0x00, 0x0b,
+    0xea, 0xaa, 0xaa, 0xab, 0xff, 0xff, 0xff, 0xff,
+];
+
+// A ring around an opening, left blank for what shows through it
+const PORTAL: Texture = [
+    0x00, 0x55, 0x55, 0x00, 0x05, 0xaa, 0xaa, 0x50,
+    0x1a, 0x00, 0x00, 0xa4, 0x18, 0x00, 0x00, 0x24,
+    0x60, 0x00, 0x00, 0x09, 0x60, 0x00, 0x00, 0x09,
+    0x60, 0x00, 0x00, 0x09, 0x60, 0x00, 0x00, 0x09,
+    0x60, 0x00, 0x00, 0x09, 0x60, 0x00, 0x00, 0x09,
+    0x60, 0x00, 0x00, 0x09, 0x60, 0x00, 0x00, 0x09,
+    0x60, 0x00, 0x00, 0x09, 0x60, 0x00, 0x00, 0x09,
+    0x60, 0x00, 0x00, 0x09, 0x60, 0x00, 0x00, 0x09,
+];
+
+// Ripples across dark water
+const WATER: Texture = [
+    0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa,
+    0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa,
+    0x96, 0xaa, 0xaa, 0xaa, 0x69, 0xaa, 0xaa, 0xaa,
+    0xaa, 0x41, 0xaa, 0xaa, 0xaa, 0x96, 0xaa, 0xaa,
+    0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa,
+    0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa,
+    0xaa, 0xaa, 0x96, 0xaa, 0xaa, 0xaa, 0x69, 0xaa,
+    0xaa, 0xaa, 0xaa, 0x41, 0xaa, 0xaa, 0xaa, 0x96,
+];
+
+// Get the palette index at texture coordinates u, v in [0, 1)
+pub fn sample(texture: &Texture, u: f32, v: f32) -> u8 {
+    let tx = (u * SIZE as f32) as usize % SIZE;
+    let ty = (v * SIZE as f32) as usize % SIZE;
+
+    let idx = ty * SIZE + tx;
+    let shift = 6 - (idx % 4) * 2;
+
+    (texture[idx / 4] >> shift) & 0b11
+}
@@ -0,0 +1,340 @@
+// Weapon tuning, the weapons carried, and the first-person viewmodel
+use core::f32::consts::PI;
+
+use crate::math::{abs, sin};
+use crate::raycast::Viewport;
+use crate::render::{set_pixel, VIEW_HEIGHT};
+use crate::texture::{self, Texture, SIZE};
+use crate::State;
+
+// Rounds carried at the start and at most
+pub const START_AMMO: u32 = 20;
+pub const MAX_AMMO: u32 = 99;
+
+// Rockets carried at most; the launcher starts empty
+pub const MAX_ROCKETS: u32 = 20;
+
+// Carried from the start of the game
+pub const START_WEAPONS: u8 = Weapon::Knife.bit() | Weapon::Pistol.bit();
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ammo {
+    None,
+    Bullets,
+    Rockets,
+}
+
+// In the order BUTTON_2 double-taps cycle through them
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Weapon {
+    Knife,
+    Pistol,
+    Rapid,
+    // Fires rockets that travel and burst on impact
+    Launcher,
+}
+
+pub struct Spec {
+    // Health taken off an enemy per hit; rockets deal theirs as they burst
+    pub damage: i32,
+    // Frames between attacks
+    pub cooldown: u32,
+    // Radians a shot may stray to either side of the aim
+    pub spread: f32,
+    // Tiles a hit reaches
+    pub range: f32,
+    pub ammo: Ammo,
+    // Keeps firing for as long as BUTTON_1 is held
+    pub automatic: bool,
+    // Tiles away enemies hear an attack from, through walls
+    pub noise: f32,
+    // The viewmodel held ready and straight after attacking, and whether a
+    // muzzle flash shows over it
+    frames: [&'static Texture; 2],
+    flash: bool,
+}
+
+const SPECS: [Spec; 4] = [
+    Spec {
+        damage: 2,
+        cooldown: 25,
+        spread: 0.0,
+        range: 1.0,
+        ammo: Ammo::None,
+        automatic: false,
+        noise: 0.0,
+        frames: [&KNIFE, &STAB],
+        flash: false,
+    },
+    Spec {
+        damage: 1,
+        cooldown: 20,
+        spread: 0.0,
+        range: f32::MAX,
+        ammo: Ammo::Bullets,
+        automatic: false,
+        noise: 10.0,
+        frames: [&GUN, &GUN],
+        flash: true,
+    },
+    Spec {
+        damage: 1,
+        cooldown: 6,
+        spread: 0.06,
+        range: f32::MAX,
+        ammo: Ammo::Bullets,
+        automatic: true,
+        noise: 10.0,
+        frames: [&RAPID, &RAPID],
+        flash: true,
+    },
+    Spec {
+        damage: 0,
+        cooldown: 50,
+        spread: 0.0,
+        range: f32::MAX,
+        ammo: Ammo::Rockets,
+        automatic: false,
+        noise: 8.0,
+        frames: [&LAUNCHER, &LAUNCHER],
+        flash: true,
+    },
+];
+
+impl Weapon {
+    pub const ALL: [Weapon; 4] = [Weapon::Knife, Weapon::Pistol, Weapon::Rapid, Weapon::Launcher];
+
+    pub const fn spec(self) -> &'static Spec {
+        &SPECS[self as usize]
+    }
+
+    // Flag for this weapon in State::weapons
+    pub const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl State {
+    // Rounds left for a weapon, or None if it needs none
+    pub(crate) fn ammo_for(&self, weapon: Weapon) -> Option<u32> {
+        match weapon.spec().ammo {
+            Ammo::None => None,
+            Ammo::Bullets => Some(self.ammo),
+            Ammo::Rockets => Some(self.rockets),
+        }
+    }
+
+    // Carried and able to fire
+    fn can_use(&self, weapon: Weapon) -> bool {
+        self.weapons & weapon.bit() != 0 && self.ammo_for(weapon) != Some(0)
+    }
+
+    // Take out the next weapon carried that can fire, wrapping around
+    pub(crate) fn cycle_weapon(&mut self) {
+        let count = Weapon::ALL.len();
+        let next = (1..count)
+            .map(|step| Weapon::ALL[(self.weapon as usize + step) % count])
+            .find(|&weapon| self.can_use(weapon));
+
+        if let Some(weapon) = next {
+            self.weapon = weapon;
+        }
+    }
+
+    // Add a weapon to those carried and take it out, loading it
+    pub(crate) fn give_weapon(&mut self, weapon: Weapon) {
+        self.weapons |= weapon.bit();
+        self.weapon = weapon;
+        self.reload();
+    }
+
+    // Lower the weapon in hand out of sight and bring it back up, as if
+    // loading it
+    pub(crate) fn reload(&mut self) {
+        self.reload = RELOAD_TIME;
+    }
+
+    // Once the weapon in hand runs dry, take out the best one that can fire;
+    // the knife always can
+    pub(crate) fn switch_if_empty(&mut self) {
+        if self.ammo_for(self.weapon) == Some(0) {
+            let best = Weapon::ALL.iter().rev().find(|&&weapon| self.can_use(weapon));
+            self.weapon = best.copied().unwrap_or(Weapon::Knife);
+        }
+    }
+}
+
+// Frames the muzzle flash stays up after a shot
+const FLASH_TIME: u32 = 4;
+
+// Frames the weapon takes to go down out of sight and back up when loaded
+const RELOAD_TIME: u32 = 30;
+
+// Texture pixels the weapon sways across and bobs down by with the walk at
+// its fullest, and kicks up by on an attack
+const SWAY: f32 = 2.0;
+const BOB: f32 = 1.0;
+const KICK: i32 = 2;
+
+// Screen pixels per texture pixel, in a full height view and a quarter one
+const SCALE: i32 = 3;
+const SMALL_SCALE: i32 = 2;
+
+const GUN: Texture = [
+    0x00, 0x0f, 0xf0, 0x00, 0x00, 0x0e, 0xb0, 0x00,
+    0x00, 0x0e, 0xb0, 0x00, 0x00, 0x0e, 0xb0, 0x00,
+    0x00, 0x0e, 0xb0, 0x00, 0x00, 0x3e, 0xbc, 0x00,
+    0x00, 0x3a, 0xac, 0x00, 0x00, 0xee, 0xbb, 0x00,
+    0x00, 0xea, 0xab, 0x00, 0x03, 0xab, 0xea, 0xc0,
+    0x03, 0xad, 0x7a, 0xc0, 0x0e, 0xad, 0x7a, 0xb0,
+    0x0e, 0xab, 0xea, 0xb0, 0x3a, 0xaa, 0xaa, 0xac,
+    0x3a, 0xab, 0xea, 0xac, 0xea, 0xab, 0xea, 0xab,
+];
+
+const KNIFE: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x37, 0x00,
+    0x00, 0x00, 0xdb, 0x00, 0x00, 0x03, 0x6c, 0x00,
+    0x00, 0x0d, 0xb0, 0x00, 0x00, 0xfe, 0xc0, 0x00,
+    0x00, 0x3f, 0xc0, 0x00, 0x00, 0xfc, 0xf0, 0x00,
+];
+
+// The knife thrust up and forward
+const STAB: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x30, 0x00, 0x00, 0x00, 0xdc, 0x00,
+    0x00, 0x03, 0x6c, 0x00, 0x00, 0x0d, 0xb0, 0x00,
+    0x00, 0x36, 0xc0, 0x00, 0x00, 0xdb, 0x00, 0x00,
+    0x0f, 0xec, 0x00, 0x00, 0x03, 0xfc, 0x00, 0x00,
+    0x0f, 0xcf, 0x00, 0x00, 0x0f, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+// Twin barrels over a drum magazine
+const RAPID: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x3c, 0x3c, 0x00,
+    0x00, 0x34, 0x1c, 0x00, 0x00, 0x38, 0x2c, 0x00,
+    0x00, 0x38, 0x2c, 0x00, 0x00, 0x3b, 0xeb, 0x80,
+    0x00, 0xea, 0xaa, 0xb0, 0x00, 0xef, 0xff, 0xb0,
+    0x00, 0xea, 0xaa, 0xb0, 0x03, 0xea, 0xaa, 0xbc,
+    0x0e, 0xaf, 0xff, 0xab, 0x0e, 0xac, 0x03, 0xab,
+    0x0e, 0xab, 0xfe, 0xab, 0x3a, 0xaa, 0xaa, 0xab,
+    0x3a, 0xaa, 0xaa, 0xab, 0xea, 0xaa, 0xaa, 0xab,
+];
+
+const LAUNCHER: Texture = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0x00,
+    0x00, 0xd5, 0x57, 0x00, 0x00, 0xea, 0xab, 0x00,
+    0x00, 0xef, 0xfb, 0x00, 0x00, 0xea, 0xab, 0x00,
+    0x00, 0xea, 0xab, 0x00, 0x00, 0xea, 0xab, 0x00,
+    0x03, 0xea, 0xab, 0xc0, 0x0e, 0xaa, 0xaa, 0xb0,
+    0x0e, 0xbf, 0xfe, 0xb0, 0x0e, 0xac, 0x3a, 0xb0,
+    0x0e, 0xaf, 0xfa, 0xac, 0x3a, 0xaa, 0xaa, 0xac,
+    0x3a, 0xaa, 0xaa, 0xac, 0xea, 0xaa, 0xaa, 0xab,
+];
+
+// Also drawn in the world as a rocket's blast
+pub const FLASH: Texture = [
+    0x00, 0x01, 0x40, 0x00, 0x04, 0x00, 0x40, 0x10,
+    0x01, 0x01, 0x40, 0x40, 0x00, 0x46, 0x91, 0x00,
+    0x00, 0x1a, 0xa4, 0x00, 0x11, 0x69, 0x69, 0x44,
+    0x05, 0xa5, 0x5a, 0x50, 0x56, 0x95, 0x56, 0x95,
+    0x05, 0xa5, 0x5a, 0x50, 0x11, 0x69, 0x69, 0x44,
+    0x00, 0x1a, 0xa4, 0x00, 0x00, 0x46, 0x91, 0x00,
+    0x01, 0x01, 0x40, 0x40, 0x04, 0x00, 0x40, 0x10,
+    0x00, 0x01, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+impl State {
+    // Texture pixels the weapon is moved right and down by this frame:
+    // swaying in a figure of eight as the view bobs, kicking up on an attack
+    // and dipping along a half wave while it reloads
+    fn viewmodel_offset(&self) -> (f32, f32) {
+        let spec = self.weapon.spec();
+        let attacking = self.weapon_cooldown > spec.cooldown.saturating_sub(FLASH_TIME);
+        let (mut x, mut y) = (0.0, 0.0);
+
+        if self.settings.view_bob {
+            x = sin(self.bob_phase / 2.0) * SWAY * self.bob_weight;
+            y = abs(sin(self.bob_phase)) * BOB * self.bob_weight;
+        }
+        if attacking {
+            y -= KICK as f32;
+        }
+        let reloaded = self.reload as f32 / RELOAD_TIME as f32;
+        (x, y + sin(reloaded * PI) * SIZE as f32)
+    }
+}
+
+// Draw the weapon at the bottom middle of a viewport, in its attacking frame
+// and flashing right after a shot. It shrinks to fit a deathmatch quarter
+pub unsafe fn render(state: &State, viewport: Viewport) {
+    let scale = if viewport.rows < VIEW_HEIGHT { SMALL_SCALE } else { SCALE };
+    let size = SIZE as i32 * scale;
+    let (x, y) = state.viewmodel_offset();
+    let left = viewport.left + viewport.columns as i32 / 2 - size / 2 + (x * scale as f32) as i32;
+    let top = viewport.bottom() - size + (y * scale as f32) as i32;
+    let spec = state.weapon.spec();
+    let attacking = state.weapon_cooldown > spec.cooldown.saturating_sub(FLASH_TIME);
+
+    if attacking && spec.flash {
+        draw(&FLASH, left, top - size / 2, scale, viewport);
+    }
+    draw(spec.frames[attacking as usize], left, top, scale, viewport);
+}
+
+// Blit a texture scaled up, cut off at the edges of the viewport; palette
+// index 0 is transparent
+unsafe fn draw(image: &Texture, left: i32, top: i32, scale: i32, viewport: Viewport) {
+    let columns = viewport.left..viewport.left + viewport.columns as i32;
+    let rows = viewport.top..viewport.bottom();
+
+    for ty in 0..SIZE {
+        for tx in 0..SIZE {
+            let u = (tx as f32 + 0.5) / SIZE as f32;
+            let v = (ty as f32 + 0.5) / SIZE as f32;
+            let color = texture::sample(image, u, v);
+            if color == 0 {
+                continue;
+            }
+
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    let (x, y) = (left + tx as i32 * scale + dx, top + ty as i32 * scale + dy);
+                    if columns.contains(&x) && rows.contains(&y) {
+                        set_pixel(x, y, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loading_a_weapon_lowers_it_out_of_sight_and_back() {
+        let mut state = State::new();
+        state.teleport(5.5, 6.5, 0.0);
+        assert_eq!(state.viewmodel_offset(), (0.0, 0.0));
+
+        state.give_weapon(Weapon::Rapid);
+        let mut lowest: f32 = 0.0;
+        for _ in 0..RELOAD_TIME {
+            state.tick_player();
+            lowest = lowest.max(state.viewmodel_offset().1);
+        }
+        assert!(lowest > SIZE as f32 * 0.9);
+        assert_eq!(state.viewmodel_offset(), (0.0, 0.0));
+
+        // Firing kicks it up
+        state.weapon_cooldown = Weapon::Rapid.spec().cooldown;
+        assert_eq!(state.viewmodel_offset(), (0.0, -KICK as f32));
+    }
+}
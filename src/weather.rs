@@ -0,0 +1,87 @@
+// Rain or snow falling over the view while the player is out under the sky.
+// Nothing about it is stored: each frame deals the particles out again from
+// the same seed, and the frame count carries each one down from where it
+// started, wrapping back round to the top
+use crate::levels::LEVELS;
+use crate::map::OUTDOOR;
+use crate::math::floor;
+use crate::platform::{rect, DRAW_COLORS};
+use crate::raycast::Viewport;
+use crate::rng::Rng;
+use crate::State;
+
+// Where the particles start out, the same every frame
+const SEED: u64 = 0x5eed;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Rain,
+    Snow,
+}
+
+// What falls in a level, and how many particles of it are in view at once
+#[derive(Clone, Copy)]
+pub struct Weather {
+    pub kind: Kind,
+    pub density: u32,
+}
+
+// Where the next particle dealt out is this frame. Rain falls fast and
+// straight, while snow drifts down, swaying a pixel either way
+fn place(kind: Kind, rng: &mut Rng, frames: u32, viewport: Viewport) -> (i32, i32) {
+    let (columns, rows) = (viewport.columns as u32, viewport.rows as u32);
+    let (x, y, speed) = (rng.below(columns), rng.below(rows), 1 + rng.below(2));
+    let (fallen, sway) = match kind {
+        Kind::Rain => (frames * (speed + 2), 0),
+        Kind::Snow => (frames * speed / 2, (x + frames / 16) % 2),
+    };
+
+    let x = viewport.left + ((x + sway) % columns) as i32;
+    (x, viewport.top + ((y + fallen) % rows) as i32)
+}
+
+pub unsafe fn render(state: &State, viewport: Viewport) {
+    let Some(weather) = LEVELS[state.level].weather else {
+        return;
+    };
+    let (x, y) = (floor(state.player_x) as i32, floor(state.player_y) as i32);
+    if state.map().tile(x, y) != OUTDOOR {
+        return;
+    }
+
+    // Streaks of rain, and flakes of snow
+    let (length, color) = match weather.kind {
+        Kind::Rain => (4, 0x2),
+        Kind::Snow => (1, 0x1),
+    };
+    *DRAW_COLORS = color;
+
+    let mut rng = Rng::new(SEED);
+    for _ in 0..weather.density {
+        let (x, y) = place(weather.kind, &mut rng, state.stats.frames, viewport);
+        rect(x, y, 1, length.min(viewport.bottom() - y) as u32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn particles_fall_within_the_viewport() {
+        let viewport = Viewport { left: 80, columns: 80, top: 20, rows: 60 };
+
+        for kind in [Kind::Rain, Kind::Snow] {
+            let (mut now, mut later) = (Rng::new(SEED), Rng::new(SEED));
+            for _ in 0..50 {
+                let (x, y) = place(kind, &mut now, 10, viewport);
+                assert!((80..160).contains(&x) && (20..80).contains(&y));
+
+                // The same particle, two frames on, is further down or has
+                // wrapped back round to the top
+                let (_, next) = place(kind, &mut later, 12, viewport);
+                assert!(next > y || next < 28);
+            }
+        }
+    }
+}